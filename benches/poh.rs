@@ -0,0 +1,66 @@
+// ---------------------------------------------------------------------------
+// PoH throughput benchmarks.
+//
+//   tick    — one tick at several hashes_per_tick; throughput is reported
+//             in hashes, so criterion's elem/s reads as hashes per second
+//   verify  — checking a 50,000-entry chain from the seed, sequentially
+//             and with verify_parallel, also in hashes per second
+//
+// Run with: cargo bench --bench poh
+// ---------------------------------------------------------------------------
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use poh_engine::runtime::poh::{self, PohGenerator};
+
+const SEED: &[u8] = b"bench-seed";
+
+// Length of the chain the verify benchmarks check.
+const VERIFY_ENTRIES: usize = 50_000;
+
+// Hashes per tick in the verified chain. Kept small so one verification of
+// 50k entries takes a fraction of a second.
+const VERIFY_HASHES_PER_TICK: u64 = 10;
+
+fn bench_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    for hashes_per_tick in [100u64, 1_000, 12_500] {
+        group.throughput(Throughput::Elements(hashes_per_tick));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(hashes_per_tick),
+            &hashes_per_tick,
+            |b, &hashes_per_tick| {
+                let mut poh = PohGenerator::new(SEED, hashes_per_tick, 64).unwrap();
+                b.iter(|| {
+                    poh.tick();
+                    // Entries pile up otherwise; only the hashing is measured.
+                    poh.entries.clear();
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let mut poh = PohGenerator::new(SEED, VERIFY_HASHES_PER_TICK, 64).unwrap();
+    for _ in 0..VERIFY_ENTRIES {
+        poh.tick();
+    }
+    let entries = poh.entries;
+
+    let mut group = c.benchmark_group("verify");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(VERIFY_ENTRIES as u64 * VERIFY_HASHES_PER_TICK));
+    group.bench_function("sequential", |b| {
+        b.iter(|| assert!(poh::verify(SEED, black_box(&entries))));
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| assert!(poh::verify_parallel(SEED, black_box(&entries))));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_tick, bench_verify);
+criterion_main!(benches);
@@ -0,0 +1,6 @@
+// The node's building blocks as a library, so the binary in main.rs and
+// anything outside it (benches/) share one public API.
+
+pub mod types;
+pub mod runtime;
+pub mod programs;
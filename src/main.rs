@@ -1,10 +1,191 @@
-mod types;
-mod runtime;
-mod programs;
+use poh_engine::runtime;
+use poh_engine::runtime::accounts_db::AccountHashAlgorithm;
+use poh_engine::runtime::epoch_schedule::EpochSchedule;
+use poh_engine::runtime::genesis::{ClusterType, GenesisConfig};
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
     // Pass --log-entries to print full entry details on every tick and record.
     // Usage: cargo run -- --log-entries
-    let log_entries = std::env::args().any(|a| a == "--log-entries");
-    runtime::rpc::start(log_entries);
+    let log_entries = args.iter().any(|a| a == "--log-entries");
+
+    // Pass --cluster <development|testnet|mainnet-beta> to pick the cluster
+    // type at genesis. Defaults to development.
+    let cluster_type = match args.iter().position(|a| a == "--cluster") {
+        Some(i) => match args.get(i + 1).and_then(|name| ClusterType::from_name(name)) {
+            Some(cluster_type) => cluster_type,
+            None => {
+                eprintln!("error: --cluster expects development, testnet or mainnet-beta");
+                std::process::exit(1);
+            }
+        },
+        None => ClusterType::default(),
+    };
+
+    // Pass --skip-sig-verify to accept badly-signed transactions. They must
+    // still carry signatures, just not valid ones.
+    // Development only — refused on mainnet-beta.
+    let skip_sig_verify = args.iter().any(|a| a == "--skip-sig-verify");
+
+    // Pass --airdrop-cap <lamports> to change the most /requestAirdrop will
+    // mint in one request. Defaults to 2 SOL.
+    let airdrop_cap = match args.iter().position(|a| a == "--airdrop-cap") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<u64>().ok()) {
+            Some(cap) => cap,
+            None => {
+                eprintln!("error: --airdrop-cap expects a number of lamports");
+                std::process::exit(1);
+            }
+        },
+        None => runtime::rpc::DEFAULT_AIRDROP_CAP,
+    };
+
+    // Pass --creation-time <unix seconds> to fix the genesis creation time,
+    // e.g. to reproduce another node's genesis. Defaults to now.
+    let creation_time = match args.iter().position(|a| a == "--creation-time") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<u64>().ok()) {
+            Some(t) => t,
+            None => {
+                eprintln!("error: --creation-time expects Unix seconds");
+                std::process::exit(1);
+            }
+        },
+        None => std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    // Pass --ledger <path> to persist PoH entries to a file. If the file
+    // already exists, the node replays it on startup instead of starting
+    // from genesis. Without it, the ledger lives only in memory.
+    let ledger_path = match args.iter().position(|a| a == "--ledger") {
+        Some(i) => match args.get(i + 1) {
+            Some(path) => Some(std::path::PathBuf::from(path)),
+            None => {
+                eprintln!("error: --ledger expects a file path");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // Pass --halt-on-divergence to exit if checkStateConsistency finds that
+    // replaying the ledger no longer gives the live state.
+    let halt_on_divergence = args.iter().any(|a| a == "--halt-on-divergence");
+
+    // Pass --account-hash <sha256|blake3> to pick the hash behind account
+    // hashes and state roots. Defaults to sha256, as in Solana.
+    let account_hash = match args.iter().position(|a| a == "--account-hash") {
+        Some(i) => match args.get(i + 1).and_then(|name| AccountHashAlgorithm::from_name(name)) {
+            Some(algorithm) => algorithm,
+            None => {
+                eprintln!("error: --account-hash expects sha256 or blake3");
+                std::process::exit(1);
+            }
+        },
+        None => AccountHashAlgorithm::default(),
+    };
+
+    // Pass --hashes-per-tick <n> to change how many hashes make one PoH
+    // tick, and --tick-ms <ms> to change how long the ticker sleeps between
+    // ticks. Together they set how much hashing the ledger holds per second
+    // of wall time, e.g. to compare verification against generation speed.
+    let hashes_per_tick = match args.iter().position(|a| a == "--hashes-per-tick") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<u64>().ok()).filter(|&n| n > 0) {
+            Some(n) => Some(n),
+            None => {
+                eprintln!("error: --hashes-per-tick expects a positive number");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let tick_ms = match args.iter().position(|a| a == "--tick-ms") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<u64>().ok()) {
+            Some(ms) => ms,
+            None => {
+                eprintln!("error: --tick-ms expects a number of milliseconds");
+                std::process::exit(1);
+            }
+        },
+        None => runtime::rpc::DEFAULT_TICK_MS,
+    };
+
+    // Pass --bind <ip:port> to serve RPC on another address than
+    // 0.0.0.0:8080, or just --port <port> to change the port, e.g. to run
+    // two nodes side by side. --port overrides the port --bind names.
+    let mut bind = match args.iter().position(|a| a == "--bind") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<std::net::SocketAddr>().ok()) {
+            Some(addr) => addr,
+            None => {
+                eprintln!("error: --bind expects an address like 127.0.0.1:8080");
+                std::process::exit(1);
+            }
+        },
+        None => runtime::rpc::DEFAULT_BIND_ADDR,
+    };
+    if let Some(i) = args.iter().position(|a| a == "--port") {
+        match args.get(i + 1).and_then(|v| v.parse::<u16>().ok()) {
+            Some(port) => bind.set_port(port),
+            None => {
+                eprintln!("error: --port expects a port number");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Pass --slots-per-epoch <n> to change how many slots make an epoch.
+    // Defaults to 32, short enough to watch getEpochInfo advance.
+    let epoch_schedule = match args.iter().position(|a| a == "--slots-per-epoch") {
+        Some(i) => match args.get(i + 1).and_then(|v| v.parse::<u64>().ok()).and_then(|n| EpochSchedule::new(n).ok()) {
+            Some(schedule) => schedule,
+            None => {
+                eprintln!("error: --slots-per-epoch expects a positive number");
+                std::process::exit(1);
+            }
+        },
+        None => EpochSchedule::default(),
+    };
+
+    // Pass --genesis <path> to start from the accounts and PoH settings in
+    // a genesis JSON file (see runtime::genesis) instead of the five
+    // development accounts. --hashes-per-tick still overrides the file.
+    let mut genesis = match args.iter().position(|a| a == "--genesis") {
+        Some(i) => match args.get(i + 1).map(|path| GenesisConfig::load(std::path::Path::new(path))) {
+            Some(Ok(genesis)) => genesis,
+            Some(Err(e)) => {
+                eprintln!("error: --genesis: {:?}", e);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("error: --genesis expects a file path");
+                std::process::exit(1);
+            }
+        },
+        None => GenesisConfig::new(cluster_type, runtime::rpc::DEFAULT_HASHES_PER_TICK),
+    };
+    if let Some(n) = hashes_per_tick {
+        genesis.hashes_per_tick = n;
+    }
+
+    // Custom transaction filters (see runtime::rpc::Middleware) go here.
+    let middleware = Vec::new();
+
+    let config = runtime::rpc::NodeConfig {
+        log_entries, cluster_type, skip_sig_verify, airdrop_cap, middleware, creation_time,
+        ledger_path, halt_on_divergence, account_hash, genesis, tick_ms, bind,
+        epoch_schedule,
+    };
+    match runtime::rpc::start(config) {
+        // Runs until something calls NodeState::shutdown().
+        Ok(node) => {
+            let _ = node.handle.join();
+        }
+        Err(e) => {
+            eprintln!("error: {:?}", e);
+            std::process::exit(1);
+        }
+    }
 }
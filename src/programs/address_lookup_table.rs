@@ -0,0 +1,81 @@
+// ---------------------------------------------------------------------------
+// Address Lookup Table program — on-chain lists of addresses.
+//
+// Lives at AddressLookupTab1e1111111111111111111111111. A lookup table is
+// an ordinary account owned by this program whose data holds a list of
+// pubkeys. A v0 message can then reference "table T, entries 3 and 7"
+// with one byte per entry instead of spelling out 32-byte keys, which is
+// how v0 transactions fit far more accounts than legacy ones.
+//
+// Account data layout:
+//   [0..56]  metadata (deactivation slot, authority, ...) — ignored here
+//   [56..]   addresses, 32 bytes each
+//
+// Only the table state is modeled. The program's own instructions
+// (CreateLookupTable, ExtendLookupTable, ...) are not implemented — tables
+// are written straight into AccountsDB.
+//
+// Reference: https://github.com/anza-xyz/agave/tree/master/programs/address-lookup-table
+// ---------------------------------------------------------------------------
+
+use crate::types::account::{AccountSharedData, Pubkey};
+
+// AddressLookupTab1e1111111111111111111111111 decoded from base58.
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM_ID: Pubkey = Pubkey([
+    0x02, 0x77, 0xa6, 0xaf, 0x97, 0x33, 0x9b, 0x7a, 0xc8, 0x8d, 0x18, 0x92, 0xc9, 0x04, 0x46, 0xf5,
+    0x00, 0x02, 0x30, 0x92, 0x66, 0xf6, 0x2e, 0x53, 0xc1, 0x18, 0x24, 0x49, 0x82, 0x00, 0x00, 0x00,
+]);
+
+// Size of the metadata header that precedes the address list.
+pub const LOOKUP_TABLE_META_SIZE: usize = 56;
+
+// ---------------------------------------------------------------------------
+// Error — reasons a lookup against a table can fail.
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum AddressLookupError {
+    /// The table account referenced by the message does not exist.
+    LookupTableAccountNotFound,
+    /// The table account is not owned by the Address Lookup Table program.
+    InvalidAccountOwner,
+    /// The table account data is not a metadata header plus whole addresses.
+    InvalidAccountData,
+    /// A lookup index points past the end of the table.
+    InvalidLookupIndex,
+    /// The static and loaded keys together are more than a message's
+    /// one-byte indexes can address.
+    TooManyAccountKeys { count: usize },
+}
+
+// ---------------------------------------------------------------------------
+// addresses — the address list stored in a lookup table account.
+// ---------------------------------------------------------------------------
+pub fn addresses(table: &AccountSharedData) -> Result<Vec<Pubkey>, AddressLookupError> {
+    if table.owner() != &ADDRESS_LOOKUP_TABLE_PROGRAM_ID {
+        return Err(AddressLookupError::InvalidAccountOwner);
+    }
+
+    let raw = table
+        .data()
+        .get(LOOKUP_TABLE_META_SIZE..)
+        .ok_or(AddressLookupError::InvalidAccountData)?;
+    if raw.len() % 32 != 0 {
+        return Err(AddressLookupError::InvalidAccountData);
+    }
+
+    Ok(raw
+        .chunks_exact(32)
+        .map(|chunk| Pubkey(chunk.try_into().unwrap()))
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// lookup_table_data — build the account data for a table holding `keys`.
+// ---------------------------------------------------------------------------
+pub fn lookup_table_data(keys: &[Pubkey]) -> Vec<u8> {
+    let mut data = vec![0u8; LOOKUP_TABLE_META_SIZE];
+    for key in keys {
+        data.extend_from_slice(&key.0);
+    }
+    data
+}
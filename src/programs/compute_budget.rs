@@ -0,0 +1,198 @@
+// ---------------------------------------------------------------------------
+// ComputeBudget program — per-transaction fee and compute settings.
+//
+// Lives at ComputeBudget111111111111111111111111111111. Unlike the
+// SystemProgram, its instructions never touch accounts. The Bank reads them
+// out of the message BEFORE execution to work out the fee; when the SVM
+// later reaches them it only checks that they decode, then moves on.
+//
+// Priority fee (Solana spec):
+//   priority_fee = ceil( compute_unit_price * compute_unit_limit / 1_000_000 )
+//   where compute_unit_price is in micro-lamports per compute unit.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/compute-budget-interface/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use crate::types::account::Pubkey;
+use crate::types::transaction::{Instruction, Message};
+
+// ComputeBudget111111111111111111111111111111 decoded from base58.
+pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey = Pubkey([
+    0x03, 0x06, 0x46, 0x6f, 0xe5, 0x21, 0x17, 0x32, 0xff, 0xec, 0xad, 0xba, 0x72, 0xc3, 0x9b, 0xe7,
+    0xbc, 0x8c, 0xe5, 0xbb, 0xc5, 0xf7, 0x12, 0x6b, 0x2c, 0x43, 0x9b, 0x3a, 0x40, 0x00, 0x00, 0x00,
+]);
+
+// Compute units a transaction is granted per (non-ComputeBudget)
+// instruction when it does not set an explicit limit.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+// Hard cap on the compute unit limit a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+// Compute units every ComputeBudget instruction costs. Matches
+// DEFAULT_COMPUTE_UNITS of the real builtin.
+pub const DEFAULT_COMPUTE_UNITS: u64 = 150;
+
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+// ---------------------------------------------------------------------------
+// Instruction — the subset of ComputeBudget instructions we support.
+//
+// The discriminator is the first byte (Borsh enum tag), matching real
+// Solana for SetComputeUnitLimit (2) and SetComputeUnitPrice (3).
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)] // names match the real program
+pub enum ComputeBudgetInstruction {
+    /// Set the compute unit limit for the whole transaction.
+    ///
+    /// Data layout (5 bytes):
+    ///   [0]    discriminator = 2
+    ///   [1..5] units (u32 LE)
+    SetComputeUnitLimit(u32),
+
+    /// Set the price per compute unit, in micro-lamports.
+    ///
+    /// Data layout (9 bytes):
+    ///   [0]    discriminator = 3
+    ///   [1..9] micro_lamports (u64 LE)
+    SetComputeUnitPrice(u64),
+
+    /// Refuse to pay more than this many lamports in total fees
+    /// (base + priority). Not part of real Solana — a client-side safety
+    /// net against accidentally huge priority fees.
+    ///
+    /// Data layout (9 bytes):
+    ///   [0]    discriminator = 5
+    ///   [1..9] lamports (u64 LE)
+    SetMaxFee(u64),
+}
+
+// ---------------------------------------------------------------------------
+// Error
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComputeBudgetError {
+    /// Instruction data is too short or malformed.
+    InvalidInstructionData,
+    /// The discriminator does not match any known instruction.
+    UnknownInstruction(u8),
+    /// The same kind of ComputeBudget instruction appears twice.
+    DuplicateInstruction,
+}
+
+// ---------------------------------------------------------------------------
+// Decode — parse raw instruction bytes into a ComputeBudgetInstruction.
+// ---------------------------------------------------------------------------
+pub fn decode(data: &[u8]) -> Result<ComputeBudgetInstruction, ComputeBudgetError> {
+    let (&discriminator, args) = data
+        .split_first()
+        .ok_or(ComputeBudgetError::InvalidInstructionData)?;
+
+    match discriminator {
+        2 => {
+            let bytes = args.get(0..4).ok_or(ComputeBudgetError::InvalidInstructionData)?;
+            Ok(ComputeBudgetInstruction::SetComputeUnitLimit(u32::from_le_bytes(
+                bytes.try_into().unwrap(),
+            )))
+        }
+        3 => {
+            let bytes = args.get(0..8).ok_or(ComputeBudgetError::InvalidInstructionData)?;
+            Ok(ComputeBudgetInstruction::SetComputeUnitPrice(u64::from_le_bytes(
+                bytes.try_into().unwrap(),
+            )))
+        }
+        5 => {
+            let bytes = args.get(0..8).ok_or(ComputeBudgetError::InvalidInstructionData)?;
+            Ok(ComputeBudgetInstruction::SetMaxFee(u64::from_le_bytes(
+                bytes.try_into().unwrap(),
+            )))
+        }
+        other => Err(ComputeBudgetError::UnknownInstruction(other)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ComputeBudgetLimits — the settings a message asks for, after defaults.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComputeBudgetLimits {
+    /// Compute units the transaction may consume.
+    pub compute_unit_limit: u32,
+    /// Price per compute unit in micro-lamports.
+    pub compute_unit_price: u64,
+    /// Client-specified ceiling on the total fee, if any.
+    pub max_fee: Option<u64>,
+}
+
+impl ComputeBudgetLimits {
+    /// The priority fee in lamports, rounded up.
+    pub fn priority_fee(&self) -> u64 {
+        let micro_lamports = self.compute_unit_price as u128 * self.compute_unit_limit as u128;
+        micro_lamports
+            .div_ceil(MICRO_LAMPORTS_PER_LAMPORT)
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// process_message — collect every ComputeBudget instruction in a message.
+//
+// Called by the Bank before execution. Each instruction kind may appear at
+// most once. Without SetComputeUnitLimit the limit defaults to
+// DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT per non-ComputeBudget instruction.
+// ---------------------------------------------------------------------------
+pub fn process_message(message: &Message) -> Result<ComputeBudgetLimits, ComputeBudgetError> {
+    let mut unit_limit = None;
+    let mut unit_price = None;
+    let mut max_fee    = None;
+    let mut num_other  = 0u32;
+
+    for ix in &message.instructions {
+        let program_id = message.account_keys.get(ix.program_id_index as usize);
+        if program_id != Some(&COMPUTE_BUDGET_PROGRAM_ID) {
+            num_other += 1;
+            continue;
+        }
+
+        let duplicate = match decode(&ix.data)? {
+            ComputeBudgetInstruction::SetComputeUnitLimit(units) => unit_limit.replace(units).is_some(),
+            ComputeBudgetInstruction::SetComputeUnitPrice(price) => unit_price.replace(price).is_some(),
+            ComputeBudgetInstruction::SetMaxFee(lamports)        => max_fee.replace(lamports).is_some(),
+        };
+        if duplicate {
+            return Err(ComputeBudgetError::DuplicateInstruction);
+        }
+    }
+
+    let compute_unit_limit = unit_limit
+        .unwrap_or(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT.saturating_mul(num_other))
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+
+    Ok(ComputeBudgetLimits {
+        compute_unit_limit,
+        compute_unit_price: unit_price.unwrap_or(0),
+        max_fee,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Instruction constructors — encoded as decode() expects. None of them
+// names an account.
+// ---------------------------------------------------------------------------
+
+/// SetComputeUnitPrice: pay `micro_lamports` per compute unit on top of
+/// the base fee.
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    let mut data = vec![3u8];
+    data.extend_from_slice(&micro_lamports.to_le_bytes());
+    Instruction::new(COMPUTE_BUDGET_PROGRAM_ID, vec![], data)
+}
+
+/// SetMaxFee: refuse to pay more than `lamports` in total.
+pub fn set_max_fee(lamports: u64) -> Instruction {
+    let mut data = vec![5u8];
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction::new(COMPUTE_BUDGET_PROGRAM_ID, vec![], data)
+}
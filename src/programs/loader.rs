@@ -0,0 +1,136 @@
+// ---------------------------------------------------------------------------
+// Loader — deploys programs for the interpreter.
+//
+// Lives at BPFLoader2111111111111111111111111111111111, the address of
+// Solana's (non-upgradeable) BPF loader. On Solana a program is deployed by
+// writing its bytecode into an account in chunks and then finalizing it,
+// which marks the account executable. Here the bytecode is small enough to
+// fit in one instruction, so DeployProgram does both at once.
+//
+// A deployed program account is owned by the loader and executable. When
+// an instruction names it as its program, the SVM runs its data through
+// runtime::interpreter. Deployment is final: there is no upgrade or close.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/loader-v2-interface/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use crate::programs::system::SYSTEM_PROGRAM_ID;
+use crate::runtime::interpreter::{self, InterpreterError};
+use crate::types::account::{AccountSharedData, Pubkey};
+
+// BPFLoader2111111111111111111111111111111111 decoded from base58.
+pub const LOADER_ID: Pubkey = Pubkey([
+    0x02, 0xa8, 0xf6, 0x91, 0x4e, 0x88, 0xa1, 0x6e, 0x39, 0x5a, 0xe1, 0x28, 0x94, 0x8f, 0xfa, 0x69,
+    0x56, 0x93, 0x37, 0x68, 0x18, 0xdd, 0x47, 0x43, 0x52, 0x21, 0xf3, 0xc6, 0x00, 0x00, 0x00, 0x00,
+]);
+
+// Compute units every loader instruction costs.
+pub const DEFAULT_COMPUTE_UNITS: u64 = 570;
+
+// ---------------------------------------------------------------------------
+// Instruction
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum LoaderInstruction {
+    /// Write `bytecode` into an account and mark it executable.
+    ///
+    /// Accounts expected:
+    ///   [0] program account — writable, signer. Either owned by the loader
+    ///       and not yet deployed, or a system-owned account with no data
+    ///       (the loader takes ownership; the SVM allows this one claim, see
+    ///       svm::check_owner_change).
+    ///
+    /// The bytecode is checked with interpreter::verify first, so a
+    /// deployed program never holds an unknown opcode.
+    ///
+    /// Data layout (12 + len bytes, bincode):
+    ///   [0..4]   discriminator = 0  (u32 LE)
+    ///   [4..12]  len                (u64 LE)
+    ///   [12..]   bytecode           (len bytes)
+    DeployProgram { bytecode: Vec<u8> },
+}
+
+// ---------------------------------------------------------------------------
+// Error
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoaderError {
+    /// Instruction data is too short or malformed.
+    InvalidInstructionData,
+    /// The discriminator does not match any known instruction.
+    UnknownInstruction(u32),
+    /// Wrong number of accounts passed to this instruction.
+    NotEnoughAccounts,
+    /// The program account did not sign.
+    MissingRequiredSignature,
+    /// The account is already a deployed program.
+    AlreadyDeployed,
+    /// The account holds data, or is owned by some other program.
+    AccountAlreadyInUse,
+    /// The bytecode failed interpreter::verify.
+    InvalidProgram(InterpreterError),
+}
+
+// ---------------------------------------------------------------------------
+// Decode — parse raw instruction bytes into a LoaderInstruction.
+// ---------------------------------------------------------------------------
+pub fn decode(data: &[u8]) -> Result<LoaderInstruction, LoaderError> {
+    if data.len() < 4 {
+        return Err(LoaderError::InvalidInstructionData);
+    }
+
+    let discriminator = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    match discriminator {
+        // DeployProgram — discriminator 0
+        0 => {
+            let len = data
+                .get(4..12)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(LoaderError::InvalidInstructionData)?;
+            let bytecode = usize::try_from(len)
+                .ok()
+                .and_then(|len| data.get(12..12usize.checked_add(len)?))
+                .ok_or(LoaderError::InvalidInstructionData)?;
+            Ok(LoaderInstruction::DeployProgram { bytecode: bytecode.to_vec() })
+        }
+        other => Err(LoaderError::UnknownInstruction(other)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Process — execute a decoded instruction.
+//
+// `accounts` and `is_signer` are parallel to the instruction's account
+// list, as for system::process.
+// ---------------------------------------------------------------------------
+pub fn process(
+    instruction: &LoaderInstruction,
+    accounts: &mut [AccountSharedData],
+    is_signer: &[bool],
+) -> Result<(), LoaderError> {
+    match instruction {
+        LoaderInstruction::DeployProgram { bytecode } => {
+            let program = accounts.first_mut().ok_or(LoaderError::NotEnoughAccounts)?;
+
+            // The program account signs, so nobody can deploy into an
+            // address whose key they don't hold.
+            if !is_signer.first().copied().unwrap_or(false) {
+                return Err(LoaderError::MissingRequiredSignature);
+            }
+            if program.executable() {
+                return Err(LoaderError::AlreadyDeployed);
+            }
+            let fresh = program.owner() == &SYSTEM_PROGRAM_ID && program.data().is_empty();
+            if program.owner() != &LOADER_ID && !fresh {
+                return Err(LoaderError::AccountAlreadyInUse);
+            }
+
+            interpreter::verify(bytecode).map_err(LoaderError::InvalidProgram)?;
+
+            *program.data_mut() = bytecode.clone();
+            program.set_owner(LOADER_ID);
+            program.set_executable(true);
+            Ok(())
+        }
+    }
+}
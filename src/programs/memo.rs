@@ -0,0 +1,56 @@
+// ---------------------------------------------------------------------------
+// Memo — attach a UTF-8 note to a transaction, after SPL Memo.
+//
+// Lives at MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr, SPL Memo v2's
+// address. The whole instruction data is the memo. The program checks that
+// it is valid UTF-8 and writes it to the program log; it never changes an
+// account. Any accounts the instruction lists must have signed the
+// transaction, so a memo can prove who attached it.
+//
+// Log line, as SPL Memo writes it:
+//   Program log: Memo (len 5): "hello"
+//
+// Reference: https://github.com/solana-program/memo/blob/main/program/src/processor.rs
+// ---------------------------------------------------------------------------
+
+use crate::runtime::log_collector::LogCollector;
+use crate::types::account::Pubkey;
+
+// MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr decoded from base58.
+pub const MEMO_PROGRAM_ID: Pubkey = Pubkey([
+    0x05, 0x4a, 0x53, 0x5a, 0x99, 0x29, 0x21, 0x06, 0x4d, 0x24, 0xe8, 0x71, 0x60, 0xda, 0x38, 0x7c,
+    0x7c, 0x35, 0xb5, 0xdd, 0xbc, 0x92, 0xbb, 0x81, 0xe4, 0x1f, 0xa8, 0x40, 0x41, 0x05, 0x44, 0x8d,
+]);
+
+// Compute units every memo instruction costs. SPL Memo's cost grows with
+// the memo's length; this is roughly what a short one consumes.
+pub const DEFAULT_COMPUTE_UNITS: u64 = 1_000;
+
+// ---------------------------------------------------------------------------
+// Error
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum MemoError {
+    /// The memo is not valid UTF-8; the bytes before `valid_up_to` are.
+    InvalidUtf8 { valid_up_to: usize },
+    /// The instruction's account at this position did not sign.
+    MissingRequiredSignature { account: usize },
+}
+
+// ---------------------------------------------------------------------------
+// Process — validate and log the memo in `data`.
+//
+// `is_signer` is parallel to the instruction's account list, as for
+// system::process. The memo program takes no accounts of its own, so
+// nothing is mutated.
+// ---------------------------------------------------------------------------
+pub fn process(data: &[u8], is_signer: &[bool], logs: &mut LogCollector) -> Result<(), MemoError> {
+    if let Some(account) = is_signer.iter().position(|signed| !signed) {
+        return Err(MemoError::MissingRequiredSignature { account });
+    }
+
+    let memo = std::str::from_utf8(data)
+        .map_err(|e| MemoError::InvalidUtf8 { valid_up_to: e.valid_up_to() })?;
+    logs.log(&format!("Program log: Memo (len {}): {:?}", memo.len(), memo));
+    Ok(())
+}
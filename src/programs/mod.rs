@@ -1 +1,7 @@
+pub mod address_lookup_table;
+pub mod compute_budget;
+pub mod loader;
+pub mod memo;
+pub mod nonce;
 pub mod system;
+pub mod token;
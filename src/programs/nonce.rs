@@ -0,0 +1,94 @@
+// ---------------------------------------------------------------------------
+// Nonce accounts — durable transaction nonces.
+//
+// A transaction normally names a recent blockhash and expires with it,
+// about a minute later. That rules out signing offline or collecting
+// signatures slowly. A nonce account fixes this: it stores a value (the
+// "durable nonce") that a transaction may name instead of a recent
+// blockhash, for as long as the value stays stored. The transaction's
+// first instruction must be AdvanceNonceAccount, which replaces the stored
+// value, so the same transaction can never be accepted twice.
+//
+// A nonce account is an ordinary account owned by the SystemProgram, with
+// this data (80 bytes, the real layout):
+//   [0..4]   version                (u32 LE) — always 1 (current)
+//   [4..8]   state                  (u32 LE) — 0 uninitialized, 1 initialized
+//   [8..40]  authority              ([u8;32]) — who may advance or withdraw
+//   [40..72] durable nonce          ([u8;32])
+//   [72..80] lamports per signature (u64 LE) — unused here, zero
+//
+// The instructions that manage it live in the SystemProgram.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/nonce/src/state.rs
+// ---------------------------------------------------------------------------
+
+use sha2::{Digest, Sha256};
+use crate::types::account::Pubkey;
+use crate::types::transaction::Hash;
+
+// Size of a nonce account's data.
+pub const NONCE_STATE_SIZE: usize = 80;
+
+// Layout version written into every nonce account.
+const NONCE_VERSION_CURRENT: u32 = 1;
+
+// ---------------------------------------------------------------------------
+// NonceState — what a nonce account's data holds.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NonceState {
+    /// Allocated but not yet initialized.
+    Uninitialized,
+    /// In use: `durable_nonce` may be named as a transaction's blockhash.
+    Initialized { authority: Pubkey, durable_nonce: Hash },
+}
+
+impl NonceState {
+    /// Parse a nonce account's data. None if it is not NONCE_STATE_SIZE
+    /// bytes or does not hold a known version and state.
+    pub fn from_data(data: &[u8]) -> Option<Self> {
+        if data.len() != NONCE_STATE_SIZE {
+            return None;
+        }
+        let version = u32::from_le_bytes(data[0..4].try_into().unwrap());
+        let state   = u32::from_le_bytes(data[4..8].try_into().unwrap());
+
+        match (version, state) {
+            // A freshly allocated account is all zeros.
+            (0, 0) | (NONCE_VERSION_CURRENT, 0) => Some(NonceState::Uninitialized),
+            (NONCE_VERSION_CURRENT, 1) => Some(NonceState::Initialized {
+                authority:     Pubkey(data[8..40].try_into().unwrap()),
+                durable_nonce: Hash::new(data[40..72].try_into().unwrap()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Serialize into exactly NONCE_STATE_SIZE bytes of account data.
+    pub fn to_data(self) -> Vec<u8> {
+        let mut data = vec![0u8; NONCE_STATE_SIZE];
+        data[0..4].copy_from_slice(&NONCE_VERSION_CURRENT.to_le_bytes());
+        if let NonceState::Initialized { authority, durable_nonce } = self {
+            data[4..8].copy_from_slice(&1u32.to_le_bytes());
+            data[8..40].copy_from_slice(&authority.0);
+            data[40..72].copy_from_slice(&durable_nonce.0);
+        }
+        data
+    }
+}
+
+// ---------------------------------------------------------------------------
+// durable_nonce — the value a nonce account stores for a blockhash.
+//
+//   durable_nonce = SHA-256( "DURABLE_NONCE" || blockhash )
+//
+// Storing the blockhash itself would let a nonce transaction also pass as
+// an ordinary recent-blockhash transaction while that blockhash is recent,
+// i.e. be accepted twice. The hash can never be a real blockhash.
+// ---------------------------------------------------------------------------
+pub fn durable_nonce(blockhash: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(b"DURABLE_NONCE");
+    hasher.update(blockhash.0);
+    Hash::new(hasher.finalize().into())
+}
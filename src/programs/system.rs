@@ -9,6 +9,7 @@
 // It is the ONLY program that can:
 //   1. Create new accounts (allocate space, assign an owner)
 //   2. Transfer lamports out of accounts it owns (plain wallets)
+//   3. Manage durable nonce accounts (see programs::nonce)
 //
 // All other programs must own the accounts they modify. SystemProgram
 // owns all plain wallets (accounts whose owner field == SYSTEM_PROGRAM_ID).
@@ -16,12 +17,19 @@
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/system-program/src/lib.rs
 // ---------------------------------------------------------------------------
 
-use crate::types::account::{AccountSharedData, Pubkey};
+use crate::programs::nonce::{self, NonceState, NONCE_STATE_SIZE};
+use crate::types::account::{AccountSharedData, Pubkey, MAX_PERMITTED_DATA_LENGTH};
+use crate::types::rent::Rent;
+use crate::types::transaction::{AccountMeta, Hash, Instruction};
 
 // The canonical address of the SystemProgram: all 32 bytes are zero.
 // On real Solana this displays as "11111111111111111111111111111111" in base58.
 pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey([0u8; 32]);
 
+// Compute units every SystemProgram instruction costs, whatever it does.
+// Matches DEFAULT_COMPUTE_UNITS of the real builtin.
+pub const DEFAULT_COMPUTE_UNITS: u64 = 150;
+
 // ---------------------------------------------------------------------------
 // Instruction — the set of operations SystemProgram supports.
 //
@@ -29,8 +37,15 @@ pub const SYSTEM_PROGRAM_ID: Pubkey = Pubkey([0u8; 32]);
 // into this enum before calling process(). The discriminator is the first
 // 4 bytes, little-endian u32.
 //
-// We implement only the three most essential instructions:
-//   CreateAccount (0), Transfer (2), Assign (8)
+// We implement the essential account instructions and the durable nonce
+// ones:
+//   CreateAccount (0), Transfer (2), AdvanceNonceAccount (4),
+//   WithdrawNonceAccount (5), InitializeNonceAccount (6), Assign (8)
+//
+// The real nonce instructions also take the RecentBlockhashes and Rent
+// sysvar accounts. There are no sysvar accounts here — the SVM passes the
+// current blockhash in and the default Rent applies — so those are left out
+// of their account lists.
 //
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/system-interface/src/instruction.rs
 // ---------------------------------------------------------------------------
@@ -45,7 +60,8 @@ pub enum SystemInstruction {
     /// Data layout (52 bytes):
     ///   [0..4]   discriminator = 0  (u32 LE)
     ///   [4..12]  lamports           (u64 LE) — initial balance, must cover rent-exemption
-    ///   [12..20] space              (u64 LE) — bytes to allocate for account data
+    ///   [12..20] space              (u64 LE) — bytes to allocate for account data,
+    ///                                            at most MAX_PERMITTED_DATA_LENGTH
     ///   [20..52] owner              ([u8;32]) — program that will own the new account
     CreateAccount {
         lamports: u64,
@@ -59,6 +75,8 @@ pub enum SystemInstruction {
     ///   [0] from — writable, signer. Must be owned by SystemProgram.
     ///   [1] to   — writable.
     ///
+    /// A transfer of 0 lamports succeeds without modifying either account.
+    ///
     /// Data layout (12 bytes):
     ///   [0..4]  discriminator = 2  (u32 LE)
     ///   [4..12] lamports           (u64 LE)
@@ -73,12 +91,48 @@ pub enum SystemInstruction {
     ///   [0..4]  discriminator = 8  (u32 LE)
     ///   [4..36] owner              ([u8;32]) — the new owner program
     Assign { owner: Pubkey },
+
+    /// Replace a nonce account's stored nonce with one derived from the
+    /// current blockhash. A durable-nonce transaction must start with this.
+    ///
+    /// Accounts expected:
+    ///   [0] nonce account — writable. Must be initialized.
+    ///   [1] authority     — signer. The nonce's authority.
+    ///
+    /// Data layout (4 bytes):
+    ///   [0..4]  discriminator = 4  (u32 LE)
+    AdvanceNonceAccount,
+
+    /// Withdraw lamports from a nonce account. Withdrawing everything
+    /// closes it; otherwise it must stay rent-exempt.
+    ///
+    /// Accounts expected:
+    ///   [0] nonce account — writable.
+    ///   [1] to            — writable.
+    ///   [2] authority     — signer. The nonce's authority, or the nonce
+    ///                       account itself while uninitialized.
+    ///
+    /// Data layout (12 bytes):
+    ///   [0..4]  discriminator = 5  (u32 LE)
+    ///   [4..12] lamports           (u64 LE)
+    WithdrawNonceAccount { lamports: u64 },
+
+    /// Turn an allocated, rent-exempt system account into a nonce account
+    /// storing a nonce derived from the current blockhash.
+    ///
+    /// Accounts expected:
+    ///   [0] nonce account — writable. NONCE_STATE_SIZE bytes, uninitialized.
+    ///
+    /// Data layout (36 bytes):
+    ///   [0..4]  discriminator = 6  (u32 LE)
+    ///   [4..36] authority          ([u8;32])
+    InitializeNonceAccount { authority: Pubkey },
 }
 
 // ---------------------------------------------------------------------------
 // Error — reasons an instruction can fail.
 // ---------------------------------------------------------------------------
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SystemProgramError {
     /// Instruction data is too short or malformed.
     InvalidInstructionData,
@@ -93,46 +147,65 @@ pub enum SystemProgramError {
     AccountNotOwnedBySystem,
     /// Wrong number of accounts passed to this instruction.
     NotEnoughAccounts,
+    /// An account that must authorize this instruction did not sign.
+    MissingRequiredSignature,
+    /// The nonce account is not in the state this instruction needs, or
+    /// its data is not a nonce state.
+    InvalidNonceState,
+    /// The stored nonce was derived from the current blockhash already;
+    /// it cannot be advanced (or closed) until the blockhash moves on.
+    NonceBlockhashNotExpired,
+    /// The account would be left below its rent-exempt minimum.
+    InsufficientFundsForRent,
+    /// The space asked for exceeds MAX_PERMITTED_DATA_LENGTH.
+    InvalidAccountDataLength,
 }
 
 // ---------------------------------------------------------------------------
 // Decode — parse raw instruction bytes into a SystemInstruction.
+//
+// Instruction data comes straight from clients, so every field is read
+// with a bounds-checked read (read_bytes): data too short for a field is
+// InvalidInstructionData, never a panic. Bytes past the last field are
+// ignored.
 // ---------------------------------------------------------------------------
 pub fn decode(data: &[u8]) -> Result<SystemInstruction, SystemProgramError> {
-    if data.len() < 4 {
-        return Err(SystemProgramError::InvalidInstructionData);
-    }
-
     // First 4 bytes are the discriminator (little-endian u32).
-    let discriminator = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    let discriminator = u32::from_le_bytes(read_bytes(data, 0)?);
 
     match discriminator {
         // CreateAccount — discriminator 0
         0 => {
-            if data.len() < 52 {
-                return Err(SystemProgramError::InvalidInstructionData);
-            }
-            let lamports = u64::from_le_bytes(data[4..12].try_into().unwrap());
-            let space    = u64::from_le_bytes(data[12..20].try_into().unwrap());
-            let owner    = Pubkey(data[20..52].try_into().unwrap());
+            let lamports = u64::from_le_bytes(read_bytes(data, 4)?);
+            let space    = u64::from_le_bytes(read_bytes(data, 12)?);
+            let owner    = Pubkey(read_bytes(data, 20)?);
             Ok(SystemInstruction::CreateAccount { lamports, space, owner })
         }
 
         // Transfer — discriminator 2
         2 => {
-            if data.len() < 12 {
-                return Err(SystemProgramError::InvalidInstructionData);
-            }
-            let lamports = u64::from_le_bytes(data[4..12].try_into().unwrap());
+            let lamports = u64::from_le_bytes(read_bytes(data, 4)?);
             Ok(SystemInstruction::Transfer { lamports })
         }
 
+        // AdvanceNonceAccount — discriminator 4
+        4 => Ok(SystemInstruction::AdvanceNonceAccount),
+
+        // WithdrawNonceAccount — discriminator 5
+        5 => {
+            let lamports = u64::from_le_bytes(read_bytes(data, 4)?);
+            Ok(SystemInstruction::WithdrawNonceAccount { lamports })
+        }
+
+        // InitializeNonceAccount — discriminator 6
+        6 => {
+            let authority = Pubkey(read_bytes(data, 4)?);
+            Ok(SystemInstruction::InitializeNonceAccount { authority })
+        }
+
         // Assign — discriminator 8
         8 => {
-            if data.len() < 36 {
-                return Err(SystemProgramError::InvalidInstructionData);
-            }
-            let owner = Pubkey(data[4..36].try_into().unwrap());
+            let owner = Pubkey(read_bytes(data, 4)?);
             Ok(SystemInstruction::Assign { owner })
         }
 
@@ -140,6 +213,48 @@ pub fn decode(data: &[u8]) -> Result<SystemInstruction, SystemProgramError> {
     }
 }
 
+// The N bytes of `data` starting at `offset`, or InvalidInstructionData if
+// the data ends first.
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N], SystemProgramError> {
+    offset
+        .checked_add(N)
+        .and_then(|end| data.get(offset..end))
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(SystemProgramError::InvalidInstructionData)
+}
+
+// ---------------------------------------------------------------------------
+// Instruction constructors — the Instruction a client sends for each
+// operation, with the data encoded as decode() expects and the accounts
+// in the order process() expects.
+// ---------------------------------------------------------------------------
+
+/// CreateAccount: `from` funds a new account at `to` with `lamports`,
+/// `space` bytes of data and `owner` as its owner. Both must sign.
+pub fn create_account(from: &Pubkey, to: &Pubkey, lamports: u64, space: u64, owner: &Pubkey) -> Instruction {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&lamports.to_le_bytes());
+    data.extend_from_slice(&space.to_le_bytes());
+    data.extend_from_slice(&owner.0);
+    Instruction::new(SYSTEM_PROGRAM_ID, vec![AccountMeta::new(*from, true), AccountMeta::new(*to, true)], data)
+}
+
+/// Transfer: move `lamports` from `from`, which signs, to `to`.
+pub fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    let mut data = Vec::with_capacity(12);
+    data.extend_from_slice(&2u32.to_le_bytes());
+    data.extend_from_slice(&lamports.to_le_bytes());
+    Instruction::new(SYSTEM_PROGRAM_ID, vec![AccountMeta::new(*from, true), AccountMeta::new(*to, false)], data)
+}
+
+/// AdvanceNonceAccount: replace the nonce stored in `nonce`, signed for by
+/// its `authority`. The first instruction of a durable-nonce transaction.
+pub fn advance_nonce_account(nonce: &Pubkey, authority: &Pubkey) -> Instruction {
+    let accounts = vec![AccountMeta::new(*nonce, false), AccountMeta::new_readonly(*authority, true)];
+    Instruction::new(SYSTEM_PROGRAM_ID, accounts, 4u32.to_le_bytes().to_vec())
+}
+
 // ---------------------------------------------------------------------------
 // Process — execute a decoded instruction against a slice of loaded accounts.
 //
@@ -148,13 +263,22 @@ pub fn decode(data: &[u8]) -> Result<SystemInstruction, SystemProgramError> {
 //   - Loaded accounts from AccountsDB into `accounts`
 //
 // `accounts` is a mutable slice of the accounts listed in the instruction,
-// in the same order as instruction.accounts[]. The SVM will write any
-// mutations back to AccountsDB after this returns Ok.
+// in the same order as instruction.accounts[]. `keys` and `is_signer` are
+// parallel to it: keys[i] is the address of accounts[i], and is_signer[i]
+// is true if accounts[i] signed the transaction. `blockhash` is the
+// current blockhash, which the nonce instructions derive nonces from. The
+// SVM will write any mutations back to AccountsDB after this returns Ok.
 // ---------------------------------------------------------------------------
 pub fn process(
     instruction: &SystemInstruction,
     accounts: &mut [AccountSharedData],
+    keys: &[Pubkey],
+    is_signer: &[bool],
+    blockhash: &Hash,
 ) -> Result<(), SystemProgramError> {
+    // An account index with no signer flag is treated as unsigned.
+    let signed = |i: usize| is_signer.get(i).copied().unwrap_or(false);
+
     match instruction {
         // -------------------------------------------------------------------
         // CreateAccount
@@ -164,6 +288,13 @@ pub fn process(
                 return Err(SystemProgramError::NotEnoughAccounts);
             }
 
+            // Both the funder and the new address must sign: the funder
+            // because it pays, the new account so nobody can squat on an
+            // address whose private key they don't hold.
+            if !signed(0) || !signed(1) {
+                return Err(SystemProgramError::MissingRequiredSignature);
+            }
+
             // The new account must not already be in use.
             // An account is "in use" if it already has lamports or data.
             let new_account = &accounts[1];
@@ -171,6 +302,12 @@ pub fn process(
                 return Err(SystemProgramError::AccountAlreadyInUse);
             }
 
+            // Account data is capped at 10 MiB, as in Solana. Checked
+            // before anything is allocated.
+            if *space > MAX_PERMITTED_DATA_LENGTH as u64 {
+                return Err(SystemProgramError::InvalidAccountDataLength);
+            }
+
             // Funder must be owned by SystemProgram (only owner can debit).
             let funder = &accounts[0];
             if funder.owner() != &SYSTEM_PROGRAM_ID {
@@ -189,7 +326,9 @@ pub fn process(
             // Initialize the new account.
             accounts[1].set_lamports(*lamports);
             accounts[1].set_owner(*owner);
-            accounts[1].data_mut().resize(*space as usize, 0);
+            accounts[1]
+                .resize_data(*space as usize)
+                .map_err(|_| SystemProgramError::InvalidAccountDataLength)?;
 
             Ok(())
         }
@@ -207,11 +346,32 @@ pub fn process(
                 return Err(SystemProgramError::AccountNotOwnedBySystem);
             }
 
+            // Source must authorize the debit.
+            if !signed(0) {
+                return Err(SystemProgramError::MissingRequiredSignature);
+            }
+
+            // A zero-lamport transfer is valid (Solana accepts it), but there
+            // is nothing to move — leave both accounts untouched. The checks
+            // above still apply, so it cannot be used to probe accounts the
+            // caller does not control.
+            if *lamports == 0 {
+                return Ok(());
+            }
+
             // Source must have enough lamports.
             if accounts[0].lamports() < *lamports {
                 return Err(SystemProgramError::InsufficientFunds);
             }
 
+            // Source and destination are separate copies even when they
+            // are the same account, and crediting the copy would mint
+            // what was debited from the other. A transfer to itself
+            // changes nothing.
+            if keys[0] == keys[1] {
+                return Ok(());
+            }
+
             let from_lamports = accounts[0].lamports() - lamports;
             let to_lamports   = accounts[1].lamports() + lamports;
 
@@ -225,7 +385,7 @@ pub fn process(
         // Assign
         // -------------------------------------------------------------------
         SystemInstruction::Assign { owner } => {
-            if accounts.len() < 1 {
+            if accounts.is_empty() {
                 return Err(SystemProgramError::NotEnoughAccounts);
             }
 
@@ -234,9 +394,201 @@ pub fn process(
                 return Err(SystemProgramError::AccountNotOwnedBySystem);
             }
 
+            // The account holder must authorize handing it to a new owner.
+            if !signed(0) {
+                return Err(SystemProgramError::MissingRequiredSignature);
+            }
+
             accounts[0].set_owner(*owner);
 
             Ok(())
         }
+
+        // -------------------------------------------------------------------
+        // AdvanceNonceAccount
+        // -------------------------------------------------------------------
+        SystemInstruction::AdvanceNonceAccount => {
+            if accounts.len() < 2 || keys.len() < 2 {
+                return Err(SystemProgramError::NotEnoughAccounts);
+            }
+            if accounts[0].owner() != &SYSTEM_PROGRAM_ID {
+                return Err(SystemProgramError::AccountNotOwnedBySystem);
+            }
+
+            let Some(NonceState::Initialized { authority, durable_nonce }) =
+                NonceState::from_data(accounts[0].data())
+            else {
+                return Err(SystemProgramError::InvalidNonceState);
+            };
+
+            if keys[1] != authority || !signed(1) {
+                return Err(SystemProgramError::MissingRequiredSignature);
+            }
+
+            let next = nonce::durable_nonce(blockhash);
+            if next == durable_nonce {
+                return Err(SystemProgramError::NonceBlockhashNotExpired);
+            }
+
+            let state = NonceState::Initialized { authority, durable_nonce: next };
+            accounts[0].data_mut().copy_from_slice(&state.to_data());
+
+            Ok(())
+        }
+
+        // -------------------------------------------------------------------
+        // WithdrawNonceAccount
+        // -------------------------------------------------------------------
+        SystemInstruction::WithdrawNonceAccount { lamports } => {
+            if accounts.len() < 3 || keys.len() < 3 {
+                return Err(SystemProgramError::NotEnoughAccounts);
+            }
+            if accounts[0].owner() != &SYSTEM_PROGRAM_ID {
+                return Err(SystemProgramError::AccountNotOwnedBySystem);
+            }
+
+            let state = NonceState::from_data(accounts[0].data())
+                .ok_or(SystemProgramError::InvalidNonceState)?;
+
+            // Until it is initialized, the nonce account is its own authority.
+            let authority = match state {
+                NonceState::Uninitialized => keys[0],
+                NonceState::Initialized { authority, .. } => authority,
+            };
+            if keys[2] != authority || !signed(2) {
+                return Err(SystemProgramError::MissingRequiredSignature);
+            }
+
+            let balance = accounts[0].lamports();
+            if *lamports > balance {
+                return Err(SystemProgramError::InsufficientFunds);
+            }
+
+            if *lamports == balance {
+                // Closing it. A nonce stored in this very blockhash could
+                // still be in flight, so closing must wait as advancing does.
+                if let NonceState::Initialized { durable_nonce, .. } = state {
+                    if nonce::durable_nonce(blockhash) == durable_nonce {
+                        return Err(SystemProgramError::NonceBlockhashNotExpired);
+                    }
+                    accounts[0].data_mut().copy_from_slice(&NonceState::Uninitialized.to_data());
+                }
+            } else if balance - lamports < Rent::default().minimum_balance(NONCE_STATE_SIZE) {
+                return Err(SystemProgramError::InsufficientFundsForRent);
+            }
+
+            accounts[0].set_lamports(balance - lamports);
+            let to_lamports = accounts[1].lamports() + lamports;
+            accounts[1].set_lamports(to_lamports);
+
+            Ok(())
+        }
+
+        // -------------------------------------------------------------------
+        // InitializeNonceAccount
+        // -------------------------------------------------------------------
+        SystemInstruction::InitializeNonceAccount { authority } => {
+            if accounts.is_empty() {
+                return Err(SystemProgramError::NotEnoughAccounts);
+            }
+            if accounts[0].owner() != &SYSTEM_PROGRAM_ID {
+                return Err(SystemProgramError::AccountNotOwnedBySystem);
+            }
+            if NonceState::from_data(accounts[0].data()) != Some(NonceState::Uninitialized) {
+                return Err(SystemProgramError::InvalidNonceState);
+            }
+            if !accounts[0].is_rent_exempt(&Rent::default()) {
+                return Err(SystemProgramError::InsufficientFundsForRent);
+            }
+
+            let state = NonceState::Initialized {
+                authority:     *authority,
+                durable_nonce: nonce::durable_nonce(blockhash),
+            };
+            accounts[0].data_mut().copy_from_slice(&state.to_data());
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FUNDER: Pubkey = Pubkey([1; 32]);
+    const NEW:    Pubkey = Pubkey([2; 32]);
+
+    fn create(space: u64) -> (Result<(), SystemProgramError>, Vec<AccountSharedData>) {
+        let mut accounts = vec![
+            AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID),
+            AccountSharedData::new(0, 0, SYSTEM_PROGRAM_ID),
+        ];
+        let instruction = SystemInstruction::CreateAccount { lamports: 100, space, owner: FUNDER };
+        let result = process(&instruction, &mut accounts, &[FUNDER, NEW], &[true, true], &Hash::default());
+        (result, accounts)
+    }
+
+    #[test]
+    fn create_account_space_is_capped_at_ten_mib() {
+        let (result, accounts) = create(MAX_PERMITTED_DATA_LENGTH as u64);
+        assert_eq!(result, Ok(()));
+        assert_eq!(accounts[1].data().len(), MAX_PERMITTED_DATA_LENGTH);
+
+        // One byte over, or a length that wraps as usize, charges nothing.
+        for space in [MAX_PERMITTED_DATA_LENGTH as u64 + 1, u64::MAX] {
+            let (result, accounts) = create(space);
+            assert_eq!(result, Err(SystemProgramError::InvalidAccountDataLength));
+            assert_eq!((accounts[0].lamports(), accounts[1].lamports()), (1_000, 0));
+            assert!(accounts[1].data().is_empty());
+        }
+    }
+
+    // Each instruction's discriminator and the length of its full encoding.
+    const LAYOUTS: [(u32, usize); 6] = [(0, 52), (2, 12), (4, 4), (5, 12), (6, 36), (8, 36)];
+
+    #[test]
+    fn decode_rejects_every_truncated_instruction() {
+        for (discriminator, len) in LAYOUTS {
+            let mut data = vec![0xff; len];
+            data[..4].copy_from_slice(&discriminator.to_le_bytes());
+            assert!(decode(&data).is_ok(), "discriminator {discriminator}");
+            for end in 0..len {
+                assert_eq!(
+                    decode(&data[..end]),
+                    Err(SystemProgramError::InvalidInstructionData),
+                    "discriminator {discriminator} cut to {end} bytes"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decode_never_panics_on_random_bytes() {
+        use sha2::{Digest, Sha256};
+
+        // Hash-chained bytes of varying length, with the discriminator
+        // forced onto a known one half of the time.
+        let mut seed = [0u8; 32];
+        for round in 0..2_000usize {
+            seed = Sha256::digest(seed).into();
+            let mut data: Vec<u8> = seed.iter().chain(Sha256::digest(seed).iter()).copied().collect();
+            data.truncate(round % 64);
+            if round % 2 == 0 && data.len() >= 4 {
+                data[..4].copy_from_slice(&LAYOUTS[round % LAYOUTS.len()].0.to_le_bytes());
+            }
+
+            let full = data.get(..4).and_then(|d| {
+                let discriminator = u32::from_le_bytes(d.try_into().unwrap());
+                LAYOUTS.iter().find(|(known, _)| *known == discriminator)
+            });
+            match (decode(&data), full) {
+                (Ok(_), Some((_, len))) => assert!(data.len() >= *len),
+                (Err(SystemProgramError::InvalidInstructionData), Some((_, len))) => assert!(data.len() < *len),
+                (Err(SystemProgramError::InvalidInstructionData), None) => assert!(data.len() < 4),
+                (Err(SystemProgramError::UnknownInstruction(_)), None) => {}
+                (result, _) => panic!("{data:?} decoded to {result:?}"),
+            }
+        }
     }
 }
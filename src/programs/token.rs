@@ -0,0 +1,531 @@
+// ---------------------------------------------------------------------------
+// Token — fungible token balances, after SPL Token.
+//
+// Lives at TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA, SPL Token's
+// address. A token is defined by a mint account (its supply, decimals and
+// who may mint more); each holder's balance of it lives in a separate
+// token account recording the mint, the wallet that owns the balance and
+// the amount. Both kinds of account are owned by this program, and it
+// refuses to touch an account it does not own. Nothing else can change
+// their data either: SystemProgram only writes system-owned accounts, and
+// an interpreted program only its own.
+//
+// The accounts are created by SystemProgram::CreateAccount with this
+// program as owner and MINT_SIZE or TOKEN_ACCOUNT_SIZE bytes of space,
+// then initialized here. Instruction tags are SPL Token's; the account
+// layouts are a fixed-size subset of its, Borsh-encoded (see AccountState).
+//
+// Reference: https://github.com/solana-program/token/blob/main/program/src/processor.rs
+// ---------------------------------------------------------------------------
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::types::account::{AccountSharedData, Pubkey};
+use crate::types::account_state::AccountState;
+
+// TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA decoded from base58.
+pub const TOKEN_PROGRAM_ID: Pubkey = Pubkey([
+    0x06, 0xdd, 0xf6, 0xe1, 0xd7, 0x65, 0xa1, 0x93, 0xd9, 0xcb, 0xe1, 0x46, 0xce, 0xeb, 0x79, 0xac,
+    0x1c, 0xb4, 0x85, 0xed, 0x5f, 0x5b, 0x37, 0x91, 0x3a, 0x8c, 0xf5, 0x85, 0x7e, 0xff, 0x00, 0xa9,
+]);
+
+// Compute units every token instruction costs. Roughly what SPL Token's
+// Transfer consumes.
+pub const DEFAULT_COMPUTE_UNITS: u64 = 4_645;
+
+// Bytes of data a mint account holds.
+pub const MINT_SIZE: usize = 42;
+
+// Bytes of data a token account holds.
+pub const TOKEN_ACCOUNT_SIZE: usize = 73;
+
+// ---------------------------------------------------------------------------
+// Mint — what a mint account's data holds.
+//
+// Layout (MINT_SIZE bytes):
+//   [0..32]  mint_authority
+//   [32..40] supply          (u64 LE)
+//   [40]     decimals
+//   [41]     is_initialized  (0 or 1)
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct Mint {
+    /// The only key that may sign MintTo.
+    pub mint_authority: Pubkey,
+    /// Tokens in existence: minted minus burned.
+    pub supply: u64,
+    /// Where clients put the decimal point. The program never uses it.
+    pub decimals: u8,
+    pub is_initialized: bool,
+}
+
+impl AccountState for Mint {
+    const SIZE: usize = MINT_SIZE;
+}
+
+// ---------------------------------------------------------------------------
+// TokenAccount — what a token account's data holds.
+//
+// Layout (TOKEN_ACCOUNT_SIZE bytes):
+//   [0..32]  mint
+//   [32..64] owner           — the wallet that may move the balance
+//   [64..72] amount          (u64 LE)
+//   [72]     is_initialized  (0 or 1)
+//
+// `owner` is not the account's owner field, which is always the token
+// program; it is whose tokens these are.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub is_initialized: bool,
+}
+
+impl AccountState for TokenAccount {
+    const SIZE: usize = TOKEN_ACCOUNT_SIZE;
+}
+
+// ---------------------------------------------------------------------------
+// Instruction
+//
+// The first byte of the data is the tag, as in SPL Token:
+//   InitializeMint (0), InitializeAccount (1), Transfer (3), MintTo (7),
+//   Burn (8)
+// SPL Token's InitializeMint and InitializeAccount also take the Rent
+// sysvar; there are no sysvar accounts here, so it is left out.
+//
+// Reference: https://github.com/solana-program/token/blob/main/interface/src/instruction.rs
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum TokenInstruction {
+    /// Make an account a mint with no supply.
+    ///
+    /// Accounts expected:
+    ///   [0] mint — writable. Owned by the token program, MINT_SIZE bytes.
+    ///
+    /// Data layout (34 bytes):
+    ///   [0]      tag = 0
+    ///   [1]      decimals
+    ///   [2..34]  mint_authority
+    InitializeMint { decimals: u8, mint_authority: Pubkey },
+
+    /// Make an account hold a balance of a mint, starting at zero.
+    ///
+    /// Accounts expected:
+    ///   [0] account — writable. Owned by the token program,
+    ///                 TOKEN_ACCOUNT_SIZE bytes.
+    ///   [1] mint    — an initialized mint.
+    ///   [2] owner   — the wallet the balance belongs to. Need not sign.
+    ///
+    /// Data layout (1 byte):
+    ///   [0]      tag = 1
+    InitializeAccount,
+
+    /// Move tokens between two accounts of the same mint.
+    ///
+    /// Accounts expected:
+    ///   [0] source      — writable.
+    ///   [1] destination — writable.
+    ///   [2] owner       — signer. The source's owner.
+    ///
+    /// Data layout (9 bytes):
+    ///   [0]      tag = 3
+    ///   [1..9]   amount (u64 LE)
+    Transfer { amount: u64 },
+
+    /// Create tokens in an account, raising the supply.
+    ///
+    /// Accounts expected:
+    ///   [0] mint           — writable.
+    ///   [1] destination    — writable. A token account of the mint.
+    ///   [2] mint authority — signer.
+    ///
+    /// Data layout (9 bytes):
+    ///   [0]      tag = 7
+    ///   [1..9]   amount (u64 LE)
+    MintTo { amount: u64 },
+
+    /// Destroy tokens in an account, lowering the supply.
+    ///
+    /// Accounts expected:
+    ///   [0] account — writable.
+    ///   [1] mint    — writable. The account's mint.
+    ///   [2] owner   — signer. The account's owner.
+    ///
+    /// Data layout (9 bytes):
+    ///   [0]      tag = 8
+    ///   [1..9]   amount (u64 LE)
+    Burn { amount: u64 },
+}
+
+// ---------------------------------------------------------------------------
+// Error
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum TokenError {
+    /// Instruction data is too short or malformed.
+    InvalidInstructionData,
+    /// The tag does not match any known instruction.
+    UnknownInstruction(u8),
+    /// Wrong number of accounts passed to this instruction.
+    NotEnoughAccounts,
+    /// The owner or mint authority did not sign.
+    MissingRequiredSignature,
+    /// A mint or token account is not owned by the token program.
+    IncorrectProgramId,
+    /// An account's data does not decode as a mint or token account.
+    InvalidAccountData,
+    /// The account is already initialized.
+    AlreadyInUse,
+    /// A mint or token account has not been initialized yet.
+    UninitializedState,
+    /// The mint passed to InitializeAccount is not an initialized mint.
+    InvalidMint,
+    /// The token account belongs to a different mint.
+    MintMismatch,
+    /// The signer is not the account's owner or the mint's authority.
+    OwnerMismatch,
+    /// The source holds fewer tokens than the amount.
+    InsufficientFunds,
+    /// A balance or the supply would overflow a u64.
+    Overflow,
+}
+
+// ---------------------------------------------------------------------------
+// Decode — parse raw instruction bytes into a TokenInstruction.
+// ---------------------------------------------------------------------------
+pub fn decode(data: &[u8]) -> Result<TokenInstruction, TokenError> {
+    let (&tag, rest) = data.split_first().ok_or(TokenError::InvalidInstructionData)?;
+    let amount = || {
+        rest.get(0..8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(TokenError::InvalidInstructionData)
+    };
+
+    match tag {
+        0 => {
+            if rest.len() < 33 {
+                return Err(TokenError::InvalidInstructionData);
+            }
+            let decimals       = rest[0];
+            let mint_authority = Pubkey(rest[1..33].try_into().unwrap());
+            Ok(TokenInstruction::InitializeMint { decimals, mint_authority })
+        }
+        1 => Ok(TokenInstruction::InitializeAccount),
+        3 => Ok(TokenInstruction::Transfer { amount: amount()? }),
+        7 => Ok(TokenInstruction::MintTo { amount: amount()? }),
+        8 => Ok(TokenInstruction::Burn { amount: amount()? }),
+        other => Err(TokenError::UnknownInstruction(other)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Process — execute a decoded instruction.
+//
+// `accounts`, `keys` and `is_signer` are parallel to the instruction's
+// account list, as for system::process. Every mint and token account read
+// or written must be owned by the token program.
+// ---------------------------------------------------------------------------
+pub fn process(
+    instruction: &TokenInstruction,
+    accounts: &mut [AccountSharedData],
+    keys: &[Pubkey],
+    is_signer: &[bool],
+) -> Result<(), TokenError> {
+    let needed = match instruction {
+        TokenInstruction::InitializeMint { .. } => 1,
+        _ => 3,
+    };
+    if accounts.len() < needed || keys.len() < needed {
+        return Err(TokenError::NotEnoughAccounts);
+    }
+    let signed = |i: usize| is_signer.get(i).copied().unwrap_or(false);
+
+    match instruction {
+        // -------------------------------------------------------------------
+        // InitializeMint
+        // -------------------------------------------------------------------
+        TokenInstruction::InitializeMint { decimals, mint_authority } => {
+            let mint = load_mint(&accounts[0])?;
+            if mint.is_initialized {
+                return Err(TokenError::AlreadyInUse);
+            }
+            let mint = Mint { mint_authority: *mint_authority, supply: 0, decimals: *decimals, is_initialized: true };
+            *accounts[0].data_mut() = mint.to_data();
+            Ok(())
+        }
+
+        // -------------------------------------------------------------------
+        // InitializeAccount
+        // -------------------------------------------------------------------
+        TokenInstruction::InitializeAccount => {
+            let account = load_token_account(&accounts[0])?;
+            if account.is_initialized {
+                return Err(TokenError::AlreadyInUse);
+            }
+            let mint = load_mint(&accounts[1]).map_err(|_| TokenError::InvalidMint)?;
+            if !mint.is_initialized {
+                return Err(TokenError::InvalidMint);
+            }
+            let account = TokenAccount { mint: keys[1], owner: keys[2], amount: 0, is_initialized: true };
+            *accounts[0].data_mut() = account.to_data();
+            Ok(())
+        }
+
+        // -------------------------------------------------------------------
+        // Transfer
+        // -------------------------------------------------------------------
+        TokenInstruction::Transfer { amount } => {
+            let mut source      = load_initialized_account(&accounts[0])?;
+            let mut destination = load_initialized_account(&accounts[1])?;
+            if source.mint != destination.mint {
+                return Err(TokenError::MintMismatch);
+            }
+            if source.owner != keys[2] {
+                return Err(TokenError::OwnerMismatch);
+            }
+            if !signed(2) {
+                return Err(TokenError::MissingRequiredSignature);
+            }
+            if source.amount < *amount {
+                return Err(TokenError::InsufficientFunds);
+            }
+
+            // Source and destination are separate copies even when they
+            // are the same account, and the later one would win on
+            // write-back. A transfer to itself changes nothing.
+            if keys[0] == keys[1] {
+                return Ok(());
+            }
+
+            source.amount      -= amount;
+            destination.amount  = destination.amount.checked_add(*amount).ok_or(TokenError::Overflow)?;
+            *accounts[0].data_mut() = source.to_data();
+            *accounts[1].data_mut() = destination.to_data();
+            Ok(())
+        }
+
+        // -------------------------------------------------------------------
+        // MintTo
+        // -------------------------------------------------------------------
+        TokenInstruction::MintTo { amount } => {
+            let mut mint        = load_initialized_mint(&accounts[0])?;
+            let mut destination = load_initialized_account(&accounts[1])?;
+            if destination.mint != keys[0] {
+                return Err(TokenError::MintMismatch);
+            }
+            if mint.mint_authority != keys[2] {
+                return Err(TokenError::OwnerMismatch);
+            }
+            if !signed(2) {
+                return Err(TokenError::MissingRequiredSignature);
+            }
+
+            mint.supply        = mint.supply.checked_add(*amount).ok_or(TokenError::Overflow)?;
+            destination.amount = destination.amount.checked_add(*amount).ok_or(TokenError::Overflow)?;
+            *accounts[0].data_mut() = mint.to_data();
+            *accounts[1].data_mut() = destination.to_data();
+            Ok(())
+        }
+
+        // -------------------------------------------------------------------
+        // Burn
+        // -------------------------------------------------------------------
+        TokenInstruction::Burn { amount } => {
+            let mut account = load_initialized_account(&accounts[0])?;
+            let mut mint    = load_initialized_mint(&accounts[1])?;
+            if account.mint != keys[1] {
+                return Err(TokenError::MintMismatch);
+            }
+            if account.owner != keys[2] {
+                return Err(TokenError::OwnerMismatch);
+            }
+            if !signed(2) {
+                return Err(TokenError::MissingRequiredSignature);
+            }
+            if account.amount < *amount {
+                return Err(TokenError::InsufficientFunds);
+            }
+
+            // The supply is the sum of every balance, so it cannot be less
+            // than this one.
+            account.amount -= amount;
+            mint.supply    -= amount;
+            *accounts[0].data_mut() = account.to_data();
+            *accounts[1].data_mut() = mint.to_data();
+            Ok(())
+        }
+    }
+}
+
+// A mint account's state. The account must be owned by the token program.
+fn load_mint(account: &AccountSharedData) -> Result<Mint, TokenError> {
+    if account.owner() != &TOKEN_PROGRAM_ID {
+        return Err(TokenError::IncorrectProgramId);
+    }
+    Mint::from_data(account.data()).ok_or(TokenError::InvalidAccountData)
+}
+
+// A token account's state. The account must be owned by the token program.
+fn load_token_account(account: &AccountSharedData) -> Result<TokenAccount, TokenError> {
+    if account.owner() != &TOKEN_PROGRAM_ID {
+        return Err(TokenError::IncorrectProgramId);
+    }
+    TokenAccount::from_data(account.data()).ok_or(TokenError::InvalidAccountData)
+}
+
+// Like load_mint, but the mint must also be initialized.
+fn load_initialized_mint(account: &AccountSharedData) -> Result<Mint, TokenError> {
+    let mint = load_mint(account)?;
+    if !mint.is_initialized {
+        return Err(TokenError::UninitializedState);
+    }
+    Ok(mint)
+}
+
+// Like load_token_account, but the account must also be initialized.
+fn load_initialized_account(account: &AccountSharedData) -> Result<TokenAccount, TokenError> {
+    let token_account = load_token_account(account)?;
+    if !token_account.is_initialized {
+        return Err(TokenError::UninitializedState);
+    }
+    Ok(token_account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const MINT: Pubkey       = Pubkey([1; 32]);
+    const ALICE_ACCT: Pubkey = Pubkey([2; 32]);
+    const BOB_ACCT: Pubkey   = Pubkey([3; 32]);
+    const ALICE: Pubkey      = Pubkey([4; 32]);
+    const BOB: Pubkey        = Pubkey([5; 32]);
+    const AUTHORITY: Pubkey  = Pubkey([6; 32]);
+
+    // The token program's view of the world: MINT and two token accounts,
+    // all uninitialized. Wallets hold nothing the program looks at.
+    fn accounts() -> HashMap<Pubkey, AccountSharedData> {
+        HashMap::from([
+            (MINT, AccountSharedData::new(1, MINT_SIZE, TOKEN_PROGRAM_ID)),
+            (ALICE_ACCT, AccountSharedData::new(1, TOKEN_ACCOUNT_SIZE, TOKEN_PROGRAM_ID)),
+            (BOB_ACCT, AccountSharedData::new(1, TOKEN_ACCOUNT_SIZE, TOKEN_PROGRAM_ID)),
+        ])
+    }
+
+    // Run `instruction` over `keys`, the last one signing if `signed`, and
+    // keep its changes only if it succeeds, as the SVM would.
+    fn apply(
+        state: &mut HashMap<Pubkey, AccountSharedData>,
+        instruction: TokenInstruction,
+        keys: &[Pubkey],
+        signed: bool,
+    ) -> Result<(), TokenError> {
+        let mut loaded: Vec<AccountSharedData> =
+            keys.iter().map(|key| state.get(key).cloned().unwrap_or_default()).collect();
+        let mut is_signer = vec![false; keys.len()];
+        *is_signer.last_mut().unwrap() = signed;
+        process(&instruction, &mut loaded, keys, &is_signer)?;
+        for (key, account) in keys.iter().zip(loaded) {
+            if state.contains_key(key) {
+                state.insert(*key, account);
+            }
+        }
+        Ok(())
+    }
+
+    fn amount(state: &HashMap<Pubkey, AccountSharedData>, key: &Pubkey) -> u64 {
+        TokenAccount::from_data(state[key].data()).unwrap().amount
+    }
+
+    // MINT initialized with AUTHORITY, and a token account of it for
+    // ALICE holding 100 tokens and one for BOB holding none.
+    fn funded() -> HashMap<Pubkey, AccountSharedData> {
+        let mut state = accounts();
+        let init_mint = TokenInstruction::InitializeMint { decimals: 6, mint_authority: AUTHORITY };
+        apply(&mut state, init_mint, &[MINT], false).unwrap();
+        for (account, owner) in [(ALICE_ACCT, ALICE), (BOB_ACCT, BOB)] {
+            apply(&mut state, TokenInstruction::InitializeAccount, &[account, MINT, owner], false).unwrap();
+        }
+        apply(&mut state, TokenInstruction::MintTo { amount: 100 }, &[MINT, ALICE_ACCT, AUTHORITY], true).unwrap();
+        state
+    }
+
+    #[test]
+    fn initialize_mint_once() {
+        let mut state = accounts();
+        let init_mint = || TokenInstruction::InitializeMint { decimals: 9, mint_authority: AUTHORITY };
+
+        assert_eq!(apply(&mut state, init_mint(), &[MINT], false), Ok(()));
+        assert_eq!(
+            Mint::from_data(state[&MINT].data()),
+            Some(Mint { mint_authority: AUTHORITY, supply: 0, decimals: 9, is_initialized: true })
+        );
+        assert_eq!(apply(&mut state, init_mint(), &[MINT], false), Err(TokenError::AlreadyInUse));
+
+        // A token account needs an initialized mint.
+        let mut fresh = accounts();
+        assert_eq!(
+            apply(&mut fresh, TokenInstruction::InitializeAccount, &[ALICE_ACCT, MINT, ALICE], false),
+            Err(TokenError::InvalidMint)
+        );
+    }
+
+    #[test]
+    fn mint_to_needs_the_mint_authority() {
+        let mut state = funded();
+        assert_eq!(amount(&state, &ALICE_ACCT), 100);
+        assert_eq!(Mint::from_data(state[&MINT].data()).unwrap().supply, 100);
+
+        let mint_to = || TokenInstruction::MintTo { amount: 5 };
+        assert_eq!(apply(&mut state, mint_to(), &[MINT, BOB_ACCT, BOB], true), Err(TokenError::OwnerMismatch));
+        assert_eq!(
+            apply(&mut state, mint_to(), &[MINT, BOB_ACCT, AUTHORITY], false),
+            Err(TokenError::MissingRequiredSignature)
+        );
+        assert_eq!(amount(&state, &BOB_ACCT), 0);
+    }
+
+    #[test]
+    fn transfer_needs_the_owner_and_the_balance() {
+        let mut state    = funded();
+        let transfer     = |amount| TokenInstruction::Transfer { amount };
+        let alice_to_bob = [ALICE_ACCT, BOB_ACCT, ALICE];
+
+        assert_eq!(apply(&mut state, transfer(101), &alice_to_bob, true), Err(TokenError::InsufficientFunds));
+        assert_eq!(
+            apply(&mut state, transfer(40), &[ALICE_ACCT, BOB_ACCT, BOB], true),
+            Err(TokenError::OwnerMismatch)
+        );
+        assert_eq!(apply(&mut state, transfer(40), &alice_to_bob, false), Err(TokenError::MissingRequiredSignature));
+        assert_eq!((amount(&state, &ALICE_ACCT), amount(&state, &BOB_ACCT)), (100, 0));
+
+        assert_eq!(apply(&mut state, transfer(40), &alice_to_bob, true), Ok(()));
+        assert_eq!((amount(&state, &ALICE_ACCT), amount(&state, &BOB_ACCT)), (60, 40));
+    }
+
+    #[test]
+    fn state_encodes_to_the_documented_layout() {
+        let mint = Mint { mint_authority: AUTHORITY, supply: 0x0102, decimals: 6, is_initialized: true };
+        let data = mint.to_data();
+        assert_eq!(data.len(), MINT_SIZE);
+        assert_eq!((&data[..32], &data[32..40], data[40], data[41]), (&AUTHORITY.0[..], &[2, 1, 0, 0, 0, 0, 0, 0][..], 6, 1));
+        assert_eq!(Mint::from_data(&data), Some(mint));
+
+        let account = TokenAccount { mint: MINT, owner: ALICE, amount: 7, is_initialized: true };
+        let data    = account.to_data();
+        assert_eq!(data.len(), TOKEN_ACCOUNT_SIZE);
+        assert_eq!((&data[..32], &data[32..64], data[64], data[72]), (&MINT.0[..], &ALICE.0[..], 7, 1));
+        assert_eq!(TokenAccount::from_data(&data), Some(account));
+
+        // Not exactly SIZE bytes, or a bool that is neither 0 nor 1.
+        assert_eq!(TokenAccount::from_data(&data[..TOKEN_ACCOUNT_SIZE - 1]), None);
+        assert_eq!(TokenAccount::from_data(&[data.clone(), vec![0]].concat()), None);
+        let mut bad_bool = data;
+        bad_bool[72] = 2;
+        assert_eq!(TokenAccount::from_data(&bad_bool), None);
+    }
+}
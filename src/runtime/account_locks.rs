@@ -0,0 +1,159 @@
+// ---------------------------------------------------------------------------
+// AccountLocks — which accounts in-flight transactions are using.
+//
+// Transactions that touch disjoint accounts can run at the same time; ones
+// that share an account may not, unless they only read it. Before a
+// transaction executes it takes a lock on every account it references:
+//
+//   writable account → write lock (exclusive)
+//   readonly account → read lock  (shared with other readers)
+//
+// and releases them once it is committed. A transaction that cannot take
+// all its locks is not scheduled now; it takes none of them.
+//
+// A transaction never conflicts with itself. An account it references
+// several times — from several instructions, or listed more than once in
+// account_keys — is locked once, and if any reference is writable that one
+// lock is a write lock (Message::get_account_locks).
+//
+// Sharing a read lock is safe because the SVM fails any instruction that
+// changes an account the message marks readonly (ReadonlyLamportChange,
+// ReadonlyDataModified), so readers never see one another's writes.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/accounts-db/src/account_locks.rs
+// ---------------------------------------------------------------------------
+
+use std::collections::HashMap;
+
+use crate::types::account::Pubkey;
+use crate::types::transaction::Message;
+
+// ---------------------------------------------------------------------------
+// LockState — how an account is currently locked.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LockState {
+    /// Held by one transaction that writes it.
+    Write,
+    /// Held by this many transactions that only read it.
+    Read(u64),
+}
+
+// ---------------------------------------------------------------------------
+// AccountLockError
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum AccountLockError {
+    /// Another transaction holds a conflicting lock on this account.
+    AccountInUse(Pubkey),
+}
+
+// ---------------------------------------------------------------------------
+// AccountLocks
+// ---------------------------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct AccountLocks {
+    locks: HashMap<Pubkey, LockState>,
+}
+
+impl AccountLocks {
+    /// No accounts locked.
+    pub fn new() -> Self {
+        AccountLocks::default()
+    }
+
+    /// Lock every account `message` references, or none of them.
+    ///
+    /// Fails with the first account another transaction holds in a
+    /// conflicting way: a write lock conflicts with any lock, a read lock
+    /// only with a write lock.
+    pub fn try_lock(&mut self, message: &Message) -> Result<(), AccountLockError> {
+        let (writable, readonly) = message.get_account_locks();
+
+        for key in &writable {
+            if self.locks.contains_key(key) {
+                return Err(AccountLockError::AccountInUse(*key));
+            }
+        }
+        for key in &readonly {
+            if self.locks.get(key) == Some(&LockState::Write) {
+                return Err(AccountLockError::AccountInUse(*key));
+            }
+        }
+
+        for key in writable {
+            self.locks.insert(key, LockState::Write);
+        }
+        for key in readonly {
+            match self.locks.entry(key).or_insert(LockState::Read(0)) {
+                LockState::Read(readers) => *readers += 1,
+                LockState::Write => unreachable!("checked above"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Release the locks a successful try_lock(message) took.
+    pub fn unlock(&mut self, message: &Message) {
+        let (writable, readonly) = message.get_account_locks();
+
+        for key in writable {
+            self.locks.remove(&key);
+        }
+        for key in readonly {
+            if let Some(LockState::Read(readers)) = self.locks.get_mut(&key) {
+                *readers -= 1;
+                if *readers == 0 {
+                    self.locks.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// How `key` is locked right now, if at all.
+    pub fn lock_state(&self, key: &Pubkey) -> Option<LockState> {
+        self.locks.get(key).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::system;
+
+    fn transfer(from: u8, to: u8) -> Message {
+        let (from, to) = (Pubkey([from; 32]), Pubkey([to; 32]));
+        Message::compile(Some(&from), &[system::transfer(&from, &to, 1)]).unwrap()
+    }
+
+    #[test]
+    fn transfers_sharing_a_writable_account_conflict() {
+        let mut locks = AccountLocks::new();
+        assert_eq!(locks.try_lock(&transfer(1, 2)), Ok(()));
+        assert_eq!(locks.try_lock(&transfer(3, 2)), Err(AccountLockError::AccountInUse(Pubkey([2; 32]))));
+
+        // The failed attempt took nothing: 3 is still free.
+        assert_eq!(locks.lock_state(&Pubkey([3; 32])), None);
+    }
+
+    #[test]
+    fn disjoint_transfers_do_not_conflict() {
+        let mut locks = AccountLocks::new();
+        assert_eq!(locks.try_lock(&transfer(1, 2)), Ok(()));
+        assert_eq!(locks.try_lock(&transfer(3, 4)), Ok(()));
+
+        // Both only read SystemProgram.
+        assert_eq!(locks.lock_state(&system::SYSTEM_PROGRAM_ID), Some(LockState::Read(2)));
+    }
+
+    #[test]
+    fn unlock_frees_the_accounts_for_the_next_transaction() {
+        let mut locks = AccountLocks::new();
+        let first     = transfer(1, 2);
+        locks.try_lock(&first).unwrap();
+        locks.unlock(&first);
+
+        assert_eq!(locks.try_lock(&transfer(3, 2)), Ok(()));
+        assert_eq!(locks.lock_state(&Pubkey([1; 32])), None);
+    }
+}
@@ -7,13 +7,148 @@
 //
 // In real Solana, AccountsDB is extremely complex — it uses memory-mapped
 // files, multiple storage tiers, snapshots, and append-only writes for
-// performance. Here we model the essential interface: load and store.
+// performance. Here we model the essential interface: load and store,
+// plus a small append-only version history keyed by slot.
 //
 // Reference: https://github.com/anza-xyz/agave/blob/master/accounts-db/src/accounts_db.rs
 // ---------------------------------------------------------------------------
 
-use std::collections::HashMap;
-use crate::types::account::{AccountSharedData, Pubkey};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use crate::programs::system::SYSTEM_PROGRAM_ID;
+use crate::runtime::poh::{Blake3Hasher, Hasher, Sha256Hasher};
+use crate::types::account::{Account, AccountSharedData, Pubkey, Slot, MAX_PERMITTED_DATA_LENGTH};
+use crate::types::rent::Rent;
+
+// How many slots of version history the node keeps around. Matches the
+// recent-blockhash window (~150 slots): nothing older than this can be
+// referenced by an in-flight transaction, so it can be compacted away.
+pub const MAX_RETAINED_SLOTS: Slot = 150;
+
+// How many recent slots of reads and writes account_stats() adds up. The
+// same window as the version history.
+pub const ACCOUNT_STATS_SLOTS: Slot = MAX_RETAINED_SLOTS;
+
+// ---------------------------------------------------------------------------
+// AccountHashAlgorithm — the hash function behind hash() and state_root().
+//
+// SHA-256 is what Solana uses and the default. BLAKE3 is faster; a node
+// started with it reports different state roots, so every node compared
+// against must use the same algorithm. A snapshot records which one built
+// it, so its root is checked with the matching function.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AccountHashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl AccountHashAlgorithm {
+    /// Parse the name used on the command line: sha256 or blake3.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(AccountHashAlgorithm::Sha256),
+            "blake3" => Some(AccountHashAlgorithm::Blake3),
+            _        => None,
+        }
+    }
+
+    /// The 32-byte digest of `data`.
+    pub fn hash32(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            AccountHashAlgorithm::Sha256 => Sha256Hasher::hash32(data),
+            AccountHashAlgorithm::Blake3 => Blake3Hasher::hash32(data),
+        }
+    }
+}
+
+impl fmt::Display for AccountHashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountHashAlgorithm::Sha256 => write!(f, "sha256"),
+            AccountHashAlgorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ImportError — why import() refused an account.
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum ImportError {
+    /// The account holds fewer lamports than the rent-exempt minimum for
+    /// its data. This includes an account with no lamports at all.
+    NotRentExempt { lamports: u64, minimum: u64 },
+    /// The data is longer than MAX_PERMITTED_DATA_LENGTH.
+    DataTooLarge { len: usize, max: usize },
+    /// The account names itself as its owner.
+    OwnedBySelf,
+    /// The account is executable but owned by the SystemProgram, which
+    /// never owns programs.
+    ExecutableOwnedBySystem,
+}
+
+// ---------------------------------------------------------------------------
+// DataIndexKey — which accounts a secondary data index covers.
+//
+// An index on { program_id, offset, len } maps the `len` bytes found at
+// `offset` in the data of every account owned by `program_id` to the
+// accounts holding them. It answers the same question as a getProgramAccounts
+// memcmp filter at that offset, without scanning every account.
+//
+// The motivating case is token accounts: an SPL Token account stores its
+// wallet owner at offset 32, so an index on
+//   { program_id: TokenProgram, offset: 32, len: 32 }
+// finds all token accounts of a wallet by its pubkey.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DataIndexKey {
+    pub program_id: Pubkey,
+    pub offset:     usize,
+    pub len:        usize,
+}
+
+impl DataIndexKey {
+    /// The indexed bytes of `account`, or None if the index does not cover
+    /// it: another program owns it, or its data ends before offset + len.
+    fn bytes<'a>(&self, account: &'a AccountSharedData) -> Option<&'a [u8]> {
+        if account.owner() != &self.program_id {
+            return None;
+        }
+        account.data().get(self.offset..self.offset.checked_add(self.len)?)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// AccountStats — how often an account was read and written.
+//
+// A read is a transaction loading the account; a write is any store or
+// delete. An account every transaction writes is a hot account: with
+// parallel execution, transactions that write it cannot run side by side.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AccountStats {
+    pub reads:  u64,
+    pub writes: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Checkpoint — some accounts as they were, for AccountsDB::rollback.
+// ---------------------------------------------------------------------------
+pub struct Checkpoint {
+    accounts: Vec<CheckpointAccount>,
+}
+
+// One account's live state, the length and newest entry of its history
+// (push_version may overwrite the newest in place), and its write version.
+struct CheckpointAccount {
+    pubkey:        Pubkey,
+    account:       Option<AccountSharedData>,
+    history_len:   usize,
+    last_version:  Option<(Slot, Option<AccountSharedData>)>,
+    write_version: Option<u64>,
+}
 
 // ---------------------------------------------------------------------------
 // AccountsDB — the state store.
@@ -22,19 +157,71 @@ use crate::types::account::{AccountSharedData, Pubkey};
 // wallets, programs, token accounts, sysvars — lives in this one map.
 // There is no separate "program store" or "wallet store"; everything is
 // an account identified by its Pubkey.
+//
+// Alongside the live map we keep an append-only history of every version
+// written, stamped with the slot it was written in. A `None` version is a
+// tombstone left by delete(). At most one version is kept per slot — a
+// second store in the same slot replaces the first.
+//
+// Secondary data indexes (see DataIndexKey) are opt-in: none exist until
+// add_data_index() creates one, after which store() and delete() keep it
+// in step with the live map.
+//
+// Reads and writes are counted per slot (see AccountStats). Counts older
+// than ACCOUNT_STATS_SLOTS are dropped as the slot advances.
+//
+// Every store() and delete() is stamped with a write version from one
+// DB-wide counter, so later writes always carry higher versions, as
+// Solana's write_version does. The latest stamp of each account is kept
+// alongside it and never compacted away, so a caller that saw version N
+// can tell cheaply whether the account has changed since (see
+// accountSubscribe). The counter starts over in every AccountsDB; a
+// snapshot does not keep it.
+//
+// Cloning is cheap enough to do per request: account data sits behind an
+// Arc, so a clone copies the maps but shares every data buffer. That is
+// how a transaction is simulated without touching the real state.
+// checkpoint() is cheaper still: it captures only the accounts a
+// transaction names, which are all it can write.
 // ---------------------------------------------------------------------------
+#[derive(Clone, Default)]
 pub struct AccountsDB {
     accounts: HashMap<Pubkey, AccountSharedData>,
+    history:  HashMap<Pubkey, Vec<(Slot, Option<AccountSharedData>)>>,
+    slot:     Slot,
+    indexes:  HashMap<DataIndexKey, HashMap<Vec<u8>, HashSet<Pubkey>>>,
+    hash_algorithm: AccountHashAlgorithm,
+    access:   BTreeMap<Slot, HashMap<Pubkey, AccountStats>>,
+    write_versions: HashMap<Pubkey, u64>,
+    write_version:  u64,
 }
 
 impl AccountsDB {
-    /// Create an empty AccountsDB.
+    /// Create an empty AccountsDB that hashes with SHA-256.
     pub fn new() -> Self {
+        AccountsDB::with_hash_algorithm(AccountHashAlgorithm::default())
+    }
+
+    /// Create an empty AccountsDB whose hash() and state_root() use
+    /// `hash_algorithm`.
+    pub fn with_hash_algorithm(hash_algorithm: AccountHashAlgorithm) -> Self {
         AccountsDB {
             accounts: HashMap::new(),
+            history:  HashMap::new(),
+            slot:     0,
+            indexes:  HashMap::new(),
+            hash_algorithm,
+            access:   BTreeMap::new(),
+            write_versions: HashMap::new(),
+            write_version:  0,
         }
     }
 
+    /// The algorithm hash() and state_root() use.
+    pub fn hash_algorithm(&self) -> AccountHashAlgorithm {
+        self.hash_algorithm
+    }
+
     // -----------------------------------------------------------------------
     // Core operations
     // -----------------------------------------------------------------------
@@ -50,20 +237,367 @@ impl AccountsDB {
 
     /// Store an account at the given address, replacing any existing state.
     ///
-    /// This is the only way state changes enter the DB. In real Solana,
-    /// stores are append-only (old versions are kept for snapshots and
-    /// historical queries). Here we overwrite for simplicity.
+    /// This is the only way state changes enter the DB. The live map is
+    /// overwritten; the previous version stays in the history until
+    /// compact() discards it.
     pub fn store(&mut self, pubkey: Pubkey, account: AccountSharedData) {
-        self.accounts.insert(pubkey, account);
+        self.count_access(pubkey).writes += 1;
+        self.stamp_write_version(pubkey);
+        self.push_version(pubkey, Some(account.clone()));
+        self.index_account(pubkey, &account);
+        if let Some(previous) = self.accounts.insert(pubkey, account) {
+            self.unindex_account(pubkey, &previous);
+        }
     }
 
     /// Remove an account from the DB.
     ///
     /// In Solana, an account is "deleted" by zeroing its lamports and data.
     /// The runtime then garbage-collects it. We expose an explicit delete
-    /// here for clarity.
+    /// here for clarity. A tombstone is appended to the history so
+    /// load_at_slot() sees the account as missing from this slot on.
     pub fn delete(&mut self, pubkey: &Pubkey) {
-        self.accounts.remove(pubkey);
+        if let Some(previous) = self.accounts.remove(pubkey) {
+            self.count_access(*pubkey).writes += 1;
+            self.stamp_write_version(*pubkey);
+            self.unindex_account(*pubkey, &previous);
+            self.push_version(*pubkey, None);
+        }
+    }
+
+    /// Store a batch of accounts from outside the chain — e.g. a state
+    /// dump used to seed a local test network.
+    ///
+    /// With `verify`, each account is checked first (see check_import) and
+    /// the ones that fail are skipped; otherwise every account is stored as
+    /// is. Returns the skipped accounts and why, in input order. Accounts
+    /// that pass are stored whatever happens to the others.
+    pub fn import(&mut self, accounts: Vec<(Pubkey, Account)>, verify: bool) -> Vec<(Pubkey, ImportError)> {
+        let mut rejected = Vec::new();
+        for (pubkey, account) in accounts {
+            if verify && let Err(e) = check_import(&pubkey, &account) {
+                rejected.push((pubkey, e));
+                continue;
+            }
+            self.store(pubkey, account.into_shared());
+        }
+        rejected
+    }
+
+    /// The write version of the last store() or delete() of `pubkey`, or
+    /// None if it was never written. Each write gets a higher version than
+    /// every write before it, to any account.
+    pub fn version(&self, pubkey: &Pubkey) -> Option<u64> {
+        self.write_versions.get(pubkey).copied()
+    }
+
+    fn stamp_write_version(&mut self, pubkey: Pubkey) {
+        self.write_version += 1;
+        self.write_versions.insert(pubkey, self.write_version);
+    }
+
+    // -----------------------------------------------------------------------
+    // Checkpoints
+    // -----------------------------------------------------------------------
+
+    /// The state of the accounts at `pubkeys` as it is now, to hand to
+    /// rollback() later. Nothing else is captured, so only writes to these
+    /// accounts can be undone. Account data is shared with the live DB, not
+    /// copied.
+    pub fn checkpoint(&self, pubkeys: &[Pubkey]) -> Checkpoint {
+        let mut accounts: Vec<CheckpointAccount> = Vec::with_capacity(pubkeys.len());
+        for pubkey in pubkeys {
+            if accounts.iter().any(|saved| &saved.pubkey == pubkey) {
+                continue;
+            }
+            let history = self.history.get(pubkey);
+            accounts.push(CheckpointAccount {
+                pubkey:        *pubkey,
+                account:       self.accounts.get(pubkey).cloned(),
+                history_len:   history.map_or(0, Vec::len),
+                last_version:  history.and_then(|versions| versions.last().cloned()),
+                write_version: self.write_versions.get(pubkey).copied(),
+            });
+        }
+        Checkpoint { accounts }
+    }
+
+    /// Return the accounts `checkpoint` captured to their state then,
+    /// undoing every store() and delete() of them since, history, indexes
+    /// and write versions included. The read and write counts are kept:
+    /// those accesses happened even if their effects did not stay. So is
+    /// the write version counter, so versions handed out before the
+    /// rollback are never handed out again.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        for saved in checkpoint.accounts {
+            let pubkey   = saved.pubkey;
+            let previous = match saved.account {
+                Some(account) => {
+                    self.index_account(pubkey, &account);
+                    self.accounts.insert(pubkey, account)
+                }
+                None => self.accounts.remove(&pubkey),
+            };
+            if let Some(previous) = previous {
+                self.unindex_account(pubkey, &previous);
+            }
+
+            match saved.last_version {
+                Some(last) => {
+                    let versions = self.history.entry(pubkey).or_default();
+                    versions.truncate(saved.history_len);
+                    if let Some(newest) = versions.last_mut() {
+                        *newest = last;
+                    }
+                }
+                None => {
+                    self.history.remove(&pubkey);
+                }
+            }
+
+            match saved.write_version {
+                Some(version) => self.write_versions.insert(pubkey, version),
+                None => self.write_versions.remove(&pubkey),
+            };
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Version history
+    // -----------------------------------------------------------------------
+
+    /// The slot that subsequent stores are stamped with.
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+
+    /// Advance the slot that subsequent stores are stamped with.
+    /// Called by the node as PoH crosses slot boundaries. Access counts
+    /// that fall out of the ACCOUNT_STATS_SLOTS window are dropped.
+    pub fn set_slot(&mut self, slot: Slot) {
+        self.slot = slot;
+        let oldest = slot.saturating_sub(ACCOUNT_STATS_SLOTS - 1);
+        self.access = self.access.split_off(&oldest);
+    }
+
+    /// Load an account as it was at the end of `slot`.
+    ///
+    /// Returns the newest version written at or before `slot`, or None if
+    /// the account did not exist then (or that part of its history has
+    /// been compacted away).
+    pub fn load_at_slot(&self, pubkey: &Pubkey, slot: Slot) -> Option<&AccountSharedData> {
+        self.history
+            .get(pubkey)?
+            .iter()
+            .rev()
+            .find(|(version_slot, _)| *version_slot <= slot)
+            .and_then(|(_, account)| account.as_ref())
+    }
+
+    /// Discard versions that can no longer be observed by load_at_slot()
+    /// for any slot >= `min_slot`. Returns the number of versions dropped.
+    ///
+    /// For each account, the newest version at or before `min_slot` is the
+    /// one load_at_slot(min_slot) returns, so it is kept; everything older
+    /// is dropped. An account whose only remaining version is a tombstone
+    /// at or before `min_slot` is forgotten entirely.
+    pub fn compact(&mut self, min_slot: Slot) -> usize {
+        let mut reclaimed = 0;
+
+        self.history.retain(|_, versions| {
+            // Index of the newest version visible at min_slot, if any.
+            let keep_from = versions
+                .iter()
+                .rposition(|(version_slot, _)| *version_slot <= min_slot);
+
+            if let Some(keep_from) = keep_from {
+                reclaimed += keep_from;
+                versions.drain(..keep_from);
+            }
+
+            let only_tombstone = versions.len() == 1
+                && versions[0].1.is_none()
+                && versions[0].0 <= min_slot;
+            if only_tombstone {
+                reclaimed += 1;
+            }
+            !only_tombstone
+        });
+
+        reclaimed
+    }
+
+    /// Append a version for `pubkey` at the current slot, replacing the
+    /// previous version if it was written in the same slot.
+    fn push_version(&mut self, pubkey: Pubkey, account: Option<AccountSharedData>) {
+        let slot     = self.slot;
+        let versions = self.history.entry(pubkey).or_default();
+        match versions.last_mut() {
+            Some((last_slot, last)) if *last_slot == slot => *last = account,
+            _ => versions.push((slot, account)),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Access statistics
+    // -----------------------------------------------------------------------
+
+    /// Count a read of `pubkey` in the current slot. The SVM calls this
+    /// for every account a transaction loads; load() itself counts nothing,
+    /// so RPC queries do not show up as reads.
+    pub fn record_read(&mut self, pubkey: &Pubkey) {
+        self.count_access(*pubkey).reads += 1;
+    }
+
+    /// Reads and writes of `pubkey` over the last ACCOUNT_STATS_SLOTS slots.
+    pub fn account_stats(&self, pubkey: &Pubkey) -> AccountStats {
+        self.access
+            .values()
+            .filter_map(|counts| counts.get(pubkey))
+            .fold(AccountStats::default(), |total, stats| AccountStats {
+                reads:  total.reads + stats.reads,
+                writes: total.writes + stats.writes,
+            })
+    }
+
+    /// The `limit` most-written accounts over the last ACCOUNT_STATS_SLOTS
+    /// slots, with their stats: most writes first, then most reads.
+    pub fn hottest_accounts(&self, limit: usize) -> Vec<(Pubkey, AccountStats)> {
+        let mut totals: HashMap<Pubkey, AccountStats> = HashMap::new();
+        for counts in self.access.values() {
+            for (pubkey, stats) in counts {
+                let total = totals.entry(*pubkey).or_default();
+                total.reads  += stats.reads;
+                total.writes += stats.writes;
+            }
+        }
+
+        let mut hottest: Vec<(Pubkey, AccountStats)> = totals.into_iter().collect();
+        hottest.sort_by(|(a_key, a), (b_key, b)| {
+            (b.writes, b.reads).cmp(&(a.writes, a.reads)).then(a_key.cmp(b_key))
+        });
+        hottest.truncate(limit);
+        hottest
+    }
+
+    // This slot's counters for `pubkey`.
+    fn count_access(&mut self, pubkey: Pubkey) -> &mut AccountStats {
+        self.access.entry(self.slot).or_default().entry(pubkey).or_default()
+    }
+
+    // -----------------------------------------------------------------------
+    // Secondary data indexes
+    // -----------------------------------------------------------------------
+
+    /// Start indexing accounts by the bytes `key` covers. Accounts already
+    /// stored are indexed now; later stores and deletes keep it current.
+    /// Adding an index that already exists does nothing.
+    pub fn add_data_index(&mut self, key: DataIndexKey) {
+        if self.indexes.contains_key(&key) {
+            return;
+        }
+        let mut index: HashMap<Vec<u8>, HashSet<Pubkey>> = HashMap::new();
+        for (pubkey, account) in &self.accounts {
+            if let Some(bytes) = key.bytes(account) {
+                index.entry(bytes.to_vec()).or_default().insert(*pubkey);
+            }
+        }
+        self.indexes.insert(key, index);
+    }
+
+    /// The accounts owned by `program_id` whose data holds `bytes` at
+    /// `offset`, in ascending pubkey order.
+    ///
+    /// None if no index on { program_id, offset, bytes.len() } exists —
+    /// the caller has to scan instead. An index with no match is Some of an
+    /// empty Vec.
+    pub fn lookup_data_index(&self, program_id: &Pubkey, offset: usize, bytes: &[u8]) -> Option<Vec<Pubkey>> {
+        let key   = DataIndexKey { program_id: *program_id, offset, len: bytes.len() };
+        let index = self.indexes.get(&key)?;
+        let mut pubkeys: Vec<Pubkey> = index.get(bytes).into_iter().flatten().copied().collect();
+        pubkeys.sort();
+        Some(pubkeys)
+    }
+
+    // Add `account` to every index that covers it.
+    fn index_account(&mut self, pubkey: Pubkey, account: &AccountSharedData) {
+        for (key, index) in self.indexes.iter_mut() {
+            if let Some(bytes) = key.bytes(account) {
+                index.entry(bytes.to_vec()).or_default().insert(pubkey);
+            }
+        }
+    }
+
+    // Remove the entries a replaced or deleted version put in the indexes,
+    // except those the live version (already indexed) still has.
+    fn unindex_account(&mut self, pubkey: Pubkey, previous: &AccountSharedData) {
+        let current = self.accounts.get(&pubkey);
+        for (key, index) in self.indexes.iter_mut() {
+            let Some(bytes) = key.bytes(previous) else { continue };
+            if current.and_then(|account| key.bytes(account)) == Some(bytes) {
+                continue;
+            }
+            if let Some(pubkeys) = index.get_mut(bytes) {
+                pubkeys.remove(&pubkey);
+                if pubkeys.is_empty() {
+                    index.remove(bytes);
+                }
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Bank hash
+    // -----------------------------------------------------------------------
+
+    /// A single 32-byte commitment to the state of every account.
+    ///
+    /// Two nodes that hold the same accounts produce the same hash, so it
+    /// can be used to compare state across nodes. Each account is hashed as
+    ///   H( lamports (u64 LE) || owner || data || pubkey )
+    /// in ascending pubkey order (so HashMap iteration order never leaks in),
+    /// and the leaves are folded pairwise into a Merkle root. An odd node at
+    /// the end of a level is carried up unchanged. An empty DB hashes to zero.
+    /// H is the DB's AccountHashAlgorithm, SHA-256 unless chosen otherwise.
+    ///
+    /// Real Solana uses a 16-ary Merkle tree and also hashes the executable
+    /// flag and rent epoch; the binary tree here keeps the idea visible.
+    ///
+    /// Reference: https://github.com/anza-xyz/agave/blob/master/accounts-db/src/accounts_hash.rs
+    pub fn hash(&self) -> [u8; 32] {
+        let mut pubkeys: Vec<&Pubkey> = self.accounts.keys().collect();
+        pubkeys.sort();
+
+        let mut level: Vec<[u8; 32]> = pubkeys
+            .into_iter()
+            .map(|pubkey| hash_account(self.hash_algorithm, pubkey, &self.accounts[pubkey]))
+            .collect();
+
+        if level.is_empty() {
+            return [0u8; 32];
+        }
+
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => self.hash_algorithm.hash32(&[*left, *right].concat()),
+                    [odd] => *odd,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+
+        level[0]
+    }
+
+    /// The state root two nodes compare to check they agree on state.
+    ///
+    /// Identical to hash(): nodes that executed the same transactions from
+    /// the same genesis hold the same accounts, so their roots match; a
+    /// single divergent lamport anywhere changes it.
+    pub fn state_root(&self) -> [u8; 32] {
+        self.hash()
     }
 
     // -----------------------------------------------------------------------
@@ -83,4 +617,280 @@ impl AccountsDB {
     pub fn is_empty(&self) -> bool {
         self.accounts.is_empty()
     }
+
+    /// Every live account, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Pubkey, &AccountSharedData)> {
+        self.accounts.iter()
+    }
+
+    /// Total lamports held across all accounts — the chain's supply.
+    ///
+    /// Summed as u128 so it cannot overflow however many accounts there
+    /// are. Transfers leave it unchanged; collecting a fee lowers it by the
+    /// fee, since fees are burned; airdrops raise it.
+    pub fn capitalization(&self) -> u128 {
+        self.accounts.values().map(|account| account.lamports() as u128).sum()
+    }
+
+    /// Total number of versions held in the history across all accounts.
+    pub fn num_versions(&self) -> usize {
+        self.history.values().map(Vec::len).sum()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// check_import — whether an imported account could exist on chain.
+//
+// The account must be rent-exempt under the default Rent, fit in
+// MAX_PERMITTED_DATA_LENGTH, and have a plausible owner: not itself, and
+// not the SystemProgram if it is executable.
+// ---------------------------------------------------------------------------
+fn check_import(pubkey: &Pubkey, account: &Account) -> Result<(), ImportError> {
+    if account.data.len() > MAX_PERMITTED_DATA_LENGTH {
+        return Err(ImportError::DataTooLarge { len: account.data.len(), max: MAX_PERMITTED_DATA_LENGTH });
+    }
+    let minimum = Rent::default().minimum_balance(account.data.len());
+    if account.lamports < minimum {
+        return Err(ImportError::NotRentExempt { lamports: account.lamports, minimum });
+    }
+    if account.owner == *pubkey {
+        return Err(ImportError::OwnedBySelf);
+    }
+    if account.executable && account.owner == SYSTEM_PROGRAM_ID {
+        return Err(ImportError::ExecutableOwnedBySystem);
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// hash_account — the Merkle leaf for a single account.
+// ---------------------------------------------------------------------------
+fn hash_account(algorithm: AccountHashAlgorithm, pubkey: &Pubkey, account: &AccountSharedData) -> [u8; 32] {
+    let mut bytes = Vec::with_capacity(8 + 32 + account.data().len() + 32);
+    bytes.extend_from_slice(&account.lamports().to_le_bytes());
+    bytes.extend_from_slice(&account.owner().0);
+    bytes.extend_from_slice(account.data());
+    bytes.extend_from_slice(&pubkey.0);
+    algorithm.hash32(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROGRAM: Pubkey = Pubkey([9; 32]);
+
+    // An account owned by PROGRAM whose 8 bytes of data are `value`.
+    fn account(value: u64) -> AccountSharedData {
+        let mut account = AccountSharedData::new(10, 8, PROGRAM);
+        account.data_mut().copy_from_slice(&value.to_le_bytes());
+        account
+    }
+
+    #[test]
+    fn rollback_restores_history_indexes_and_versions() {
+        let (a, b) = (Pubkey([1; 32]), Pubkey([2; 32]));
+        let mut db = AccountsDB::new();
+        db.add_data_index(DataIndexKey { program_id: PROGRAM, offset: 0, len: 8 });
+        db.set_slot(1);
+        db.store(a, account(1));
+        db.set_slot(2);
+        db.store(a, account(2));
+
+        let hash       = db.hash();
+        let versions   = db.num_versions();
+        let version    = db.version(&a);
+        let checkpoint = db.checkpoint(&[a, b, a]);
+
+        // Overwrite A's newest version in place, then move on a slot,
+        // create B and delete A.
+        db.store(a, account(3));
+        db.set_slot(3);
+        db.store(b, account(4));
+        db.delete(&a);
+        db.rollback(checkpoint);
+
+        assert_eq!(db.load(&a), Some(&account(2)));
+        assert_eq!(db.load(&b), None);
+        assert_eq!(db.load_at_slot(&a, 1), Some(&account(1)));
+        assert_eq!(db.load_at_slot(&a, 3), Some(&account(2)));
+        assert_eq!(db.num_versions(), versions);
+        assert_eq!((db.version(&a), db.version(&b)), (version, None));
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &2u64.to_le_bytes()), Some(vec![a]));
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &3u64.to_le_bytes()), Some(vec![]));
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &4u64.to_le_bytes()), Some(vec![]));
+        assert_eq!(db.hash(), hash);
+    }
+
+    #[test]
+    fn compact_keeps_the_newest_version_at_or_before_min_slot() {
+        let (a, b, c) = (Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32]));
+        let mut db = AccountsDB::new();
+        for slot in [1, 2, 4, 6] {
+            db.set_slot(slot);
+            db.store(a, account(slot));
+        }
+        db.set_slot(1);
+        db.store(b, account(1));
+        db.set_slot(2);
+        db.delete(&b);
+        db.set_slot(5);
+        db.store(c, account(5));
+        assert_eq!(db.num_versions(), 7);
+
+        // A loses slots 1 and 2; B, deleted by then, is forgotten with its
+        // tombstone; C has nothing at or before slot 4.
+        assert_eq!(db.compact(4), 4);
+        assert_eq!(db.num_versions(), 3);
+
+        assert_eq!(db.load_at_slot(&a, 3), None);
+        assert_eq!(db.load_at_slot(&a, 4), Some(&account(4)));
+        assert_eq!(db.load_at_slot(&a, 5), Some(&account(4)));
+        assert_eq!(db.load_at_slot(&a, 6), Some(&account(6)));
+        assert_eq!(db.load_at_slot(&b, 4), None);
+        assert_eq!(db.load_at_slot(&c, 5), Some(&account(5)));
+        assert_eq!(db.load(&a), Some(&account(6)));
+
+        // Nothing more to reclaim at the same slot.
+        assert_eq!(db.compact(4), 0);
+    }
+
+    #[test]
+    fn hash_is_a_merkle_root_independent_of_insertion_order() {
+        let keys = [Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32])];
+        let mut forward  = AccountsDB::new();
+        let mut backward = AccountsDB::new();
+        for (i, key) in keys.iter().enumerate() {
+            forward.store(*key, account(i as u64));
+        }
+        for (i, key) in keys.iter().enumerate().rev() {
+            backward.store(*key, account(i as u64));
+        }
+        assert_eq!(forward.hash(), backward.hash());
+
+        // Three leaves: the first two pair up, the odd one is carried up.
+        let leaf = |i: usize| hash_account(AccountHashAlgorithm::Sha256, &keys[i], &account(i as u64));
+        let pair = AccountHashAlgorithm::Sha256.hash32(&[leaf(0), leaf(1)].concat());
+        assert_eq!(forward.hash(), AccountHashAlgorithm::Sha256.hash32(&[pair, leaf(2)].concat()));
+
+        let mut lamport = forward.clone();
+        let mut changed = account(0);
+        changed.set_lamports(11);
+        lamport.store(keys[0], changed);
+        assert_ne!(lamport.hash(), forward.hash());
+
+        let mut data = forward.clone();
+        let mut changed = account(2);
+        changed.data_mut()[7] ^= 1;
+        data.store(keys[2], changed);
+        assert_ne!(data.hash(), forward.hash());
+
+        assert_eq!(AccountsDB::new().hash(), [0; 32]);
+        let mut single = AccountsDB::new();
+        single.store(keys[0], account(0));
+        assert_eq!(single.hash(), leaf(0));
+    }
+
+    #[test]
+    fn iter_and_capitalization_see_only_live_accounts() {
+        let (a, b, c) = (Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32]));
+        let mut db = AccountsDB::new();
+        db.store(a, AccountSharedData::new(u64::MAX, 0, PROGRAM));
+        db.store(b, AccountSharedData::new(u64::MAX, 0, PROGRAM));
+        db.store(c, account(1));
+        assert_eq!(db.capitalization(), 2 * u64::MAX as u128 + 10);
+
+        // An overwritten version and a deleted account no longer count.
+        db.store(a, AccountSharedData::new(5, 0, PROGRAM));
+        db.delete(&b);
+        assert_eq!(db.capitalization(), 15);
+
+        let mut live: Vec<_> = db.iter().map(|(pubkey, account)| (*pubkey, account.lamports())).collect();
+        live.sort();
+        assert_eq!(live, vec![(a, 5), (c, 10)]);
+        assert_eq!(db.iter().count(), db.len());
+    }
+
+    #[test]
+    fn data_index_follows_stores_and_deletes() {
+        let (a, b, c, d) = (Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32]), Pubkey([4; 32]));
+        let key_of = |value: u64| value.to_le_bytes();
+        let mut db = AccountsDB::new();
+        db.store(a, account(7));
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &key_of(7)), None);
+
+        // A is indexed when the index is added, B when it is stored; C is
+        // too short to hold the bytes and D belongs to another program.
+        db.add_data_index(DataIndexKey { program_id: PROGRAM, offset: 0, len: 8 });
+        db.store(b, account(7));
+        db.store(c, AccountSharedData::new(10, 4, PROGRAM));
+        let mut other = account(7);
+        other.set_owner(Pubkey([8; 32]));
+        db.store(d, other);
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &key_of(7)), Some(vec![a, b]));
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &[0; 4]), None);
+
+        // Overwriting moves A to its new bytes; deleting drops B.
+        db.store(a, account(8));
+        db.delete(&b);
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &key_of(7)), Some(vec![]));
+        assert_eq!(db.lookup_data_index(&PROGRAM, 0, &key_of(8)), Some(vec![a]));
+    }
+
+    #[test]
+    fn import_with_verify_skips_only_implausible_accounts() {
+        let keys: Vec<Pubkey> = (1..=5).map(|i| Pubkey([i; 32])).collect();
+        let rent_exempt = Rent::default().minimum_balance(0);
+        let wallet      = Account::new(rent_exempt, SYSTEM_PROGRAM_ID);
+        let too_large   = Account { data: vec![0; MAX_PERMITTED_DATA_LENGTH + 1], ..wallet.clone() };
+        let self_owned  = Account::new(rent_exempt, keys[3]);
+        let system_program = Account { executable: true, ..wallet.clone() };
+        let accounts = vec![
+            (keys[0], wallet.clone()),
+            (keys[1], Account::new(rent_exempt - 1, SYSTEM_PROGRAM_ID)),
+            (keys[2], too_large),
+            (keys[3], self_owned),
+            (keys[4], system_program),
+        ];
+
+        let mut db = AccountsDB::new();
+        assert_eq!(db.import(accounts.clone(), true), vec![
+            (keys[1], ImportError::NotRentExempt { lamports: rent_exempt - 1, minimum: rent_exempt }),
+            (keys[2], ImportError::DataTooLarge { len: MAX_PERMITTED_DATA_LENGTH + 1, max: MAX_PERMITTED_DATA_LENGTH }),
+            (keys[3], ImportError::OwnedBySelf),
+            (keys[4], ImportError::ExecutableOwnedBySystem),
+        ]);
+        assert_eq!(db.len(), 1);
+        assert_eq!(db.load(&keys[0]), Some(&wallet.into_shared()));
+
+        let mut unchecked = AccountsDB::new();
+        assert_eq!(unchecked.import(accounts, false), vec![]);
+        assert_eq!(unchecked.len(), 5);
+    }
+
+    #[test]
+    fn every_write_gets_a_higher_version_even_after_a_rollback() {
+        let (a, b) = (Pubkey([1; 32]), Pubkey([2; 32]));
+        let mut db = AccountsDB::new();
+        assert_eq!(db.version(&a), None);
+
+        db.store(a, account(1));
+        let first = db.version(&a).unwrap();
+        db.store(b, account(2));
+        db.store(a, account(3));
+        let (between, second) = (db.version(&b).unwrap(), db.version(&a).unwrap());
+        assert!(first < between && between < second);
+
+        // Rolling back restores the old version, but the counter goes on.
+        let checkpoint = db.checkpoint(&[a]);
+        db.store(a, account(4));
+        let rolled_back = db.version(&a).unwrap();
+        db.rollback(checkpoint);
+        assert_eq!(db.version(&a), Some(second));
+        db.store(a, account(5));
+        assert!(db.version(&a).unwrap() > rolled_back);
+
+        db.delete(&b);
+        assert!(db.version(&b).unwrap() > between);
+    }
 }
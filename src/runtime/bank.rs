@@ -5,19 +5,67 @@
 // rejects invalid transactions BEFORE any execution begins.
 //
 // Responsibilities (subset implemented here):
+//   0. Sanitize the message (reject shapes nothing downstream can handle)
 //   1. Serialize the message into canonical bytes
 //   2. Verify every required Ed25519 signature against those bytes
+//   3. Compute the fee (base + priority) and enforce the client's max fee
+//   4. Resolve v0 messages against their address lookup tables, after
+//      checking their signatures on the v0 bytes
+//   5. Check recent_blockhash against the queue of recent PoH hashes, or
+//      against a nonce account for durable-nonce transactions
+//   6. Collect the fee from the fee payer before execution
+//   7. Cap how many accounts a transaction may reference
+//   8. Reject a transaction whose signature was already processed
+//   9. Lock a transaction's accounts so that only transactions with
+//      disjoint writable sets run at the same time
+//  10. Execute a batch of transactions in non-conflicting groups, each
+//      group in parallel
+//  11. Roll AccountsDB back to a checkpoint when a transaction fails, so
+//      none of its changes survive whatever the SVM did
 //
 // Not yet implemented (future sessions):
-//   - recent_blockhash validity (must appear in last ~150 slots)
-//   - fee payer balance check  (must cover base fee before execution)
 //   - account deduplication    (no duplicate keys in account_keys)
 //
 // Reference: https://github.com/anza-xyz/agave/blob/master/runtime/src/bank.rs
 // ---------------------------------------------------------------------------
 
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
 use ed25519_dalek::{Verifier, VerifyingKey};
-use crate::types::transaction::{Message, Transaction};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use crate::programs::address_lookup_table::{self, AddressLookupError};
+use crate::programs::compute_budget::{self, ComputeBudgetError};
+use crate::programs::nonce::NonceState;
+use crate::programs::system::{self, SystemInstruction, SYSTEM_PROGRAM_ID};
+use crate::runtime::account_locks::{AccountLockError, AccountLocks};
+use crate::runtime::accounts_db::AccountsDB;
+use crate::runtime::fee::FeeCalculator;
+use crate::runtime::log_collector::LogCollector;
+use crate::runtime::svm;
+use crate::runtime::transaction_error::TransactionError;
+use crate::types::account::Pubkey;
+use crate::types::rent::Rent;
+use crate::types::transaction::{
+    CompiledInstruction, Hash, Message, MessageHeader, MessageV0, SanitizeError, Signature, Transaction,
+    VersionedMessage, VersionedTransaction,
+};
+
+// Default base fee charged per required signature.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5000;
+
+// How many of the most recent blockhashes a transaction may reference.
+// Real Solana accepts the last 150 slots' blockhashes (~60–90 seconds).
+pub const MAX_RECENT_BLOCKHASHES: usize = 150;
+
+// Default cap on the accounts one transaction may reference (and so lock).
+// Matches MAX_TX_ACCOUNT_LOCKS in real Solana.
+pub const MAX_TX_ACCOUNT_LOCKS: usize = 64;
+
+// Most keys a resolved v0 message can have: instructions index account_keys
+// with a single byte.
+pub const MAX_RESOLVED_ACCOUNT_KEYS: usize = 256;
 
 // ---------------------------------------------------------------------------
 // BankError
@@ -27,100 +75,970 @@ pub enum BankError {
     /// The transaction declares N required signatures but fewer were provided.
     NotEnoughSignatures { expected: usize, got: usize },
 
+    /// More signatures were provided than the transaction's N required
+    /// signers. Solana rejects the extra ones as malformed rather than
+    /// ignoring them.
+    TooManySignatures { expected: usize, got: usize },
+
     /// The public key at `index` is not a valid Ed25519 verifying key.
     InvalidPublicKey { index: usize },
 
     /// The signature at `index` did not verify against the message bytes.
     SignatureVerificationFailed { index: usize },
+
+    /// A ComputeBudget instruction in the message is malformed.
+    InvalidComputeBudget(ComputeBudgetError),
+
+    /// The computed fee (base + priority) exceeds the max fee the client
+    /// set with ComputeBudget SetMaxFee.
+    FeeExceedsMax { fee: u64, max_fee: u64 },
+
+    /// recent_blockhash is unknown or too old to be accepted.
+    BlockhashNotFound,
+
+    /// The message has no account keys, so there is no fee payer.
+    NoFeePayer,
+
+    /// The message's header or instruction indices do not fit its
+    /// account_keys (see Message::sanitize).
+    InvalidMessage(SanitizeError),
+
+    /// The fee payer cannot cover the fee.
+    InsufficientFundsForFee { fee: u64, balance: u64 },
+
+    /// account_keys is longer than the node allows.
+    TooManyAccounts { count: usize, max: usize },
+
+    /// A transaction with this signature and recent_blockhash has already
+    /// been processed.
+    AlreadyProcessed,
+
+    /// Another in-flight transaction holds a conflicting lock on this
+    /// account.
+    AccountInUse(Pubkey),
+
+    /// A v0 message's lookup tables could not be resolved.
+    AddressLookup(AddressLookupError),
+}
+
+// ---------------------------------------------------------------------------
+// BlockhashQueue — the recent PoH hashes a transaction may reference.
+//
+// A transaction names a recent_blockhash; the Bank only accepts it if that
+// hash is among the last `max_age` registered. This bounds how long a signed
+// transaction stays valid and, with it, how long a replay could succeed.
+//
+// As in Solana, one blockhash is registered per slot: the hash of the tick
+// that ends it (and the genesis hash before the first). The queue therefore
+// spans max_age slots whatever the traffic, which is what lets
+// getLatestBlockhash say at which block height a hash expires.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/runtime/src/blockhash_queue.rs
+// ---------------------------------------------------------------------------
+pub struct BlockhashQueue {
+    hashes:  VecDeque<Hash>,
+    max_age: usize,
+}
+
+impl BlockhashQueue {
+    /// An empty queue that keeps the last `max_age` hashes.
+    pub fn new(max_age: usize) -> Self {
+        BlockhashQueue {
+            hashes: VecDeque::with_capacity(max_age),
+            max_age,
+        }
+    }
+
+    /// Register a new blockhash, evicting the oldest once the queue is full.
+    pub fn register_hash(&mut self, hash: Hash) {
+        if self.hashes.len() == self.max_age {
+            self.hashes.pop_front();
+        }
+        self.hashes.push_back(hash);
+    }
+
+    /// Whether `hash` is still recent enough to be referenced.
+    pub fn is_valid(&self, hash: &Hash) -> bool {
+        self.hashes.contains(hash)
+    }
+
+    /// The most recently registered blockhash.
+    pub fn last_hash(&self) -> Option<Hash> {
+        self.hashes.back().copied()
+    }
+
+    /// How many hashes the queue keeps.
+    pub fn max_age(&self) -> usize {
+        self.max_age
+    }
+
+    /// The registered hashes, oldest first.
+    pub fn hashes(&self) -> impl Iterator<Item = &Hash> {
+        self.hashes.iter()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// StatusCache — the signatures of recently processed transactions.
+//
+// A signed transaction is valid for as long as its recent_blockhash stays
+// in the BlockhashQueue, so without this the same bytes could be submitted
+// again and again inside that window. Every transaction that paid its fee
+// is recorded under its recent_blockhash; once that hash leaves the queue
+// the transaction can no longer pass check_blockhash anyway, and purge()
+// drops the entries. The cache therefore never holds more than the
+// blockhash window's worth of signatures.
+//
+// A durable-nonce transaction is also recorded under its recent_blockhash
+// (the nonce). That hash is never in the queue, so the next purge drops
+// it; advancing the nonce is what keeps it from being replayed.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/runtime/src/status_cache.rs
+// ---------------------------------------------------------------------------
+#[derive(Default)]
+pub struct StatusCache {
+    signatures: HashMap<Hash, HashSet<Signature>>,
+}
+
+impl StatusCache {
+    /// An empty cache.
+    pub fn new() -> Self {
+        StatusCache::default()
+    }
+
+    /// Reject `tx` if a transaction with the same signature and
+    /// recent_blockhash has been processed.
+    pub fn check(&self, tx: &Transaction) -> Result<(), BankError> {
+        let seen = tx.signatures.first().is_some_and(|signature| {
+            self.signatures
+                .get(&tx.message.recent_blockhash)
+                .is_some_and(|signatures| signatures.contains(signature))
+        });
+        if seen { Err(BankError::AlreadyProcessed) } else { Ok(()) }
+    }
+
+    /// Record `tx` as processed. Its first signature identifies it.
+    pub fn insert(&mut self, tx: &Transaction) {
+        if let Some(signature) = tx.signatures.first() {
+            self.signatures.entry(tx.message.recent_blockhash).or_default().insert(*signature);
+        }
+    }
+
+    /// Drop every entry whose blockhash is no longer in `queue`.
+    pub fn purge(&mut self, queue: &BlockhashQueue) {
+        self.signatures.retain(|blockhash, _| queue.is_valid(blockhash));
+    }
+
+    /// How many signatures the cache holds.
+    pub fn len(&self) -> usize {
+        self.signatures.values().map(HashSet::len).sum()
+    }
+
+    /// Whether the cache holds no signatures.
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// check_blockhash — reject transactions whose recent_blockhash has expired.
+//
+// A durable-nonce transaction is the exception: its recent_blockhash is the
+// nonce stored in a nonce account rather than a recent PoH hash, and it is
+// accepted for as long as that nonce is still stored. It must start with
+// AdvanceNonceAccount on that nonce account, so executing it replaces the
+// nonce and the same transaction cannot pass again. The nonce authority's
+// signature is checked when AdvanceNonceAccount executes.
+// ---------------------------------------------------------------------------
+pub fn check_blockhash(message: &Message, queue: &BlockhashQueue, db: &AccountsDB) -> Result<(), BankError> {
+    if queue.is_valid(&message.recent_blockhash) || uses_stored_nonce(message, db) {
+        Ok(())
+    } else {
+        Err(BankError::BlockhashNotFound)
+    }
+}
+
+// Whether `message` is a durable-nonce transaction whose recent_blockhash
+// is the nonce currently stored in the nonce account it advances.
+fn uses_stored_nonce(message: &Message, db: &AccountsDB) -> bool {
+    let Some(ix) = advance_nonce_instruction(message) else {
+        return false;
+    };
+    let Some(&nonce_index) = ix.accounts.first() else {
+        return false;
+    };
+    let nonce_index = nonce_index as usize;
+    let Some(nonce_account) = message.account_keys.get(nonce_index).and_then(|key| db.load(key)) else {
+        return false;
+    };
+
+    message.is_writable(nonce_index)
+        && nonce_account.owner() == &SYSTEM_PROGRAM_ID
+        && matches!(
+            NonceState::from_data(nonce_account.data()),
+            Some(NonceState::Initialized { durable_nonce, .. }) if durable_nonce == message.recent_blockhash
+        )
+}
+
+// The first instruction of `message` if it is a SystemProgram
+// AdvanceNonceAccount, as a durable-nonce transaction's must be.
+fn advance_nonce_instruction(message: &Message) -> Option<&CompiledInstruction> {
+    let ix = message.instructions.first()?;
+    let program_id = message.account_keys.get(ix.program_id_index as usize);
+    (program_id == Some(&SYSTEM_PROGRAM_ID) && system::decode(&ix.data) == Ok(SystemInstruction::AdvanceNonceAccount))
+        .then_some(ix)
+}
+
+// ---------------------------------------------------------------------------
+// sanitize_transaction — structural checks run before anything else.
+//
+// Every later stage (signature checks, fee collection, execution) may
+// assume a sanitized transaction, so malformed input is rejected here with
+// an error rather than reaching code that would index past the end of it.
+//
+// Checks:
+//   - account_keys is non-empty (account_keys[0] is the fee payer)
+//   - the message itself is well formed (Message::sanitize): the header
+//     fits account_keys with the fee payer a writable signer, so at least
+//     one signature is required, and every instruction's program and
+//     account indices point into account_keys
+//   - exactly num_required_signatures signatures are attached
+//
+// The counts are checked here rather than only in verify_signatures so
+// they hold even with signature verification skipped: a transaction is
+// known by its first signature (the StatusCache, getTransaction), so every
+// transaction the node records must carry one, and no more than its
+// signers could have made.
+//
+// The smallest message that passes is a single account key — the fee
+// payer — with header (1, 0, 0). It can do nothing but pay: an instruction
+// needs its program among account_keys too, so even an Assign of the payer
+// to itself takes two keys, [payer, SystemProgram] with header (1, 0, 1).
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/message/src/legacy.rs (sanitize)
+// ---------------------------------------------------------------------------
+pub fn sanitize_transaction(tx: &Transaction) -> Result<(), BankError> {
+    if tx.message.fee_payer().is_none() {
+        return Err(BankError::NoFeePayer);
+    }
+    tx.message.sanitize().map_err(BankError::InvalidMessage)?;
+
+    let (expected, got) = (tx.message.header.num_required_signatures as usize, tx.signatures.len());
+    if got < expected {
+        return Err(BankError::NotEnoughSignatures { expected, got });
+    }
+    if got > expected {
+        return Err(BankError::TooManySignatures { expected, got });
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// check_account_limit — reject transactions that reference too many accounts.
+//
+// Every account a transaction names is loaded into the SVM's working set
+// and locked while it runs, so the cap bounds both. At exactly `max`
+// accounts a transaction is still accepted.
+// ---------------------------------------------------------------------------
+pub fn check_account_limit(message: &Message, max: usize) -> Result<(), BankError> {
+    let count = message.account_keys.len();
+    if count > max {
+        return Err(BankError::TooManyAccounts { count, max });
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// try_lock_accounts / unlock_accounts — hold a transaction's accounts while
+// it executes.
+//
+// try_lock_accounts takes a write lock on every account the message writes
+// and a read lock on every one it only reads, or none if another
+// transaction holds a conflicting lock. Two transactions whose writable
+// sets are disjoint from each other's accounts can both hold their locks
+// and so execute concurrently; overlapping ones must wait for
+// unlock_accounts, which releases what a successful try_lock_accounts took.
+// ---------------------------------------------------------------------------
+pub fn try_lock_accounts(locks: &mut AccountLocks, message: &Message) -> Result<(), BankError> {
+    locks.try_lock(message).map_err(|AccountLockError::AccountInUse(key)| BankError::AccountInUse(key))
+}
+
+pub fn unlock_accounts(locks: &mut AccountLocks, message: &Message) {
+    locks.unlock(message);
+}
+
+// ---------------------------------------------------------------------------
+// execute_transaction — run a transaction all-or-nothing.
+//
+// Takes a checkpoint of the accounts `tx` names, runs svm::execute against
+// `db` and, if the transaction fails, rolls them back to the checkpoint.
+// svm::execute already commits nothing on failure; the checkpoint makes
+// that a property of the DB rather than of the SVM returning early, so a
+// failing transaction leaves the accounts as they were however far it
+// got. Only the named accounts are captured, since svm::execute writes no
+// others. Call after collect_fee: the fee is taken before the checkpoint
+// and is kept.
+//
+// A failing durable-nonce transaction keeps its nonce advanced, as in
+// Solana: its AdvanceNonceAccount is run again on its own after the
+// rollback. Otherwise the nonce it names would still be stored, and the
+// same signed transaction could be submitted again once its failure
+// dropped out of the StatusCache. If advancing the nonce is what failed,
+// it fails the same way again and nothing changes.
+// ---------------------------------------------------------------------------
+pub fn execute_transaction(
+    tx: &Transaction,
+    db: &mut AccountsDB,
+    blockhash: &Hash,
+    logs: &mut LogCollector,
+) -> Result<(), svm::SvmError> {
+    let checkpoint = db.checkpoint(&tx.message.account_keys);
+    let result = svm::execute(tx, db, blockhash, logs);
+    if result.is_err() {
+        db.rollback(checkpoint);
+        if let Some(ix) = advance_nonce_instruction(&tx.message) {
+            let mut advance = tx.clone();
+            advance.message.instructions = vec![ix.clone()];
+            let _ = svm::execute(&advance, db, blockhash, logs);
+        }
+    }
+    result
+}
+
+// ---------------------------------------------------------------------------
+// process_entry — execute a batch of transactions, several at a time.
+//
+// The transactions are split, in order, into groups none of whose members
+// conflict: each one joins the current group if try_lock_accounts takes its
+// locks there, and starts the next group otherwise. A group runs on the
+// rayon pool with every transaction committed as soon as it finishes; the
+// next group starts once the whole group is done. A transaction therefore
+// sees the effects of every earlier transaction it conflicts with, and the
+// final state is the one running them one by one in order would leave.
+//
+// `db` is locked only to load a transaction's accounts and to commit them
+// (svm::load_accounts, svm::commit); execution itself runs unlocked.
+// `blockhash` is the one svm::execute takes, for nonce instructions.
+//
+// Like svm::execute this only executes: sanitizing, signatures, the
+// blockhash check and fees are the caller's. Returns one result per
+// transaction, in the order given.
+// ---------------------------------------------------------------------------
+pub fn process_entry(
+    txs: &[Transaction],
+    db: &Mutex<AccountsDB>,
+    blockhash: &Hash,
+) -> Vec<Result<(), TransactionError>> {
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut locks = AccountLocks::new();
+    for (i, tx) in txs.iter().enumerate() {
+        if groups.is_empty() || try_lock_accounts(&mut locks, &tx.message).is_err() {
+            locks = AccountLocks::new();
+            try_lock_accounts(&mut locks, &tx.message).expect("a transaction never conflicts with itself");
+            groups.push(Vec::new());
+        }
+        groups.last_mut().expect("pushed above").push(i);
+    }
+
+    let mut results: Vec<Result<(), TransactionError>> = vec![Ok(()); txs.len()];
+    for group in groups {
+        let group_results: Vec<(usize, Result<(), TransactionError>)> = group
+            .par_iter()
+            .map(|&i| {
+                let tx = &txs[i];
+                let loaded = svm::load_accounts(tx, &mut db.lock().unwrap());
+                let result = svm::execute_loaded(tx, loaded, blockhash, &mut LogCollector::new())
+                    .map(|changed| svm::commit(&mut db.lock().unwrap(), changed))
+                    .map_err(TransactionError::from);
+                (i, result)
+            })
+            .collect();
+        for (i, result) in group_results {
+            results[i] = result;
+        }
+    }
+    results
 }
 
 // ---------------------------------------------------------------------------
 // verify_signatures — the main entry point called by the RPC before SVM.
 //
-// For each signer i in 0..num_required_signatures:
+// First, exactly num_required_signatures signatures must be attached, and
+// that must be at least one: a message requiring none has no signing fee
+// payer, the header Message::sanitize rejects. Then for each signer i in
+// 0..num_required_signatures:
 //   - pubkey    = message.account_keys[i]
 //   - signature = transaction.signatures[i]
 //   - verify that signature is a valid Ed25519 signature of
 //     serialize_message(&transaction.message) under pubkey
 // ---------------------------------------------------------------------------
 pub fn verify_signatures(tx: &Transaction) -> Result<(), BankError> {
-    let num_required = tx.message.header.num_required_signatures as usize;
+    tx.verify_and_hash().map(|_| ())
+}
 
-    if tx.signatures.len() < num_required {
+// ---------------------------------------------------------------------------
+// verify_versioned — Transaction::verify_and_hash for a message of either
+// format.
+//
+// A v0 transaction's signatures cover its v0 bytes, prefix and lookups
+// included, so they must be checked before resolve_transaction turns it
+// into a legacy message. Its signers are all static keys.
+// ---------------------------------------------------------------------------
+pub fn verify_versioned(tx: &VersionedTransaction) -> Result<Hash, BankError> {
+    verify_signed_bytes(tx.message.header(), tx.message.static_account_keys(), &tx.message.serialize(), &tx.signatures)
+}
+
+// The checks Transaction::verify_and_hash and verify_versioned share, on
+// the signed bytes of a message with this header and these (static)
+// account keys. Returns the SHA-256 of those bytes.
+pub(crate) fn verify_signed_bytes(
+    header: &MessageHeader,
+    account_keys: &[Pubkey],
+    message_bytes: &[u8],
+    signatures: &[Signature],
+) -> Result<Hash, BankError> {
+    let num_required = header.num_required_signatures as usize;
+
+    if num_required == 0 || num_required > account_keys.len() {
+        return Err(BankError::InvalidMessage(SanitizeError::InvalidHeader));
+    }
+    if signatures.len() < num_required {
         return Err(BankError::NotEnoughSignatures {
             expected: num_required,
-            got:      tx.signatures.len(),
+            got:      signatures.len(),
+        });
+    }
+    if signatures.len() > num_required {
+        return Err(BankError::TooManySignatures {
+            expected: num_required,
+            got:      signatures.len(),
         });
     }
-
-    let message_bytes = serialize_message(&tx.message);
 
     for i in 0..num_required {
-        let pubkey_bytes = &tx.message.account_keys[i].0;
-        let sig_bytes    = &tx.signatures[i].0;
+        let pubkey = &account_keys[i];
 
-        let verifying_key = VerifyingKey::from_bytes(pubkey_bytes)
-            .map_err(|_| BankError::InvalidPublicKey { index: i })?;
+        if VerifyingKey::from_bytes(&pubkey.0).is_err() {
+            return Err(BankError::InvalidPublicKey { index: i });
+        }
+        if !verify_message_signature(message_bytes, pubkey, &signatures[i]) {
+            return Err(BankError::SignatureVerificationFailed { index: i });
+        }
+    }
 
-        let signature = ed25519_dalek::Signature::from_bytes(sig_bytes);
+    Ok(Hash::new(Sha256::digest(message_bytes).into()))
+}
 
-        verifying_key
-            .verify(&message_bytes, &signature)
-            .map_err(|_| BankError::SignatureVerificationFailed { index: i })?;
+// ---------------------------------------------------------------------------
+// verify_message_signature — check one Ed25519 signature over raw bytes.
+//
+// The check verify_signatures() runs for each signer, usable on its own
+// for anything signed off-chain (e.g. a login challenge) without building
+// a Transaction. False if the signature does not verify or `pubkey` is not
+// a valid Ed25519 key.
+// ---------------------------------------------------------------------------
+pub fn verify_message_signature(message_bytes: &[u8], pubkey: &Pubkey, signature: &Signature) -> bool {
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&pubkey.0) else {
+        return false;
+    };
+    let signature = ed25519_dalek::Signature::from_bytes(&signature.0);
+    verifying_key.verify(message_bytes, &signature).is_ok()
+}
+
+// ---------------------------------------------------------------------------
+// calculate_fee — the lamports a transaction pays, checked against its cap.
+//
+//   fee = base fee (see FeeCalculator) + priority_fee
+//
+// The priority fee comes from the message's ComputeBudget instructions
+// (compute_unit_price * compute_unit_limit). If the message also carries a
+// SetMaxFee instruction and the fee is above it, the transaction is
+// rejected here, before anything executes.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/fee/src/lib.rs
+// ---------------------------------------------------------------------------
+pub fn calculate_fee(message: &Message, fee_calculator: &FeeCalculator) -> Result<u64, BankError> {
+    let limits = compute_budget::process_message(message)
+        .map_err(BankError::InvalidComputeBudget)?;
+
+    let base_fee = fee_calculator.calculate_fee(message);
+    let fee      = base_fee.saturating_add(limits.priority_fee());
+
+    if let Some(max_fee) = limits.max_fee
+        && fee > max_fee
+    {
+        return Err(BankError::FeeExceedsMax { fee, max_fee });
     }
 
-    Ok(())
+    Ok(fee)
+}
+
+// ---------------------------------------------------------------------------
+// total_required_balance — everything a transaction will cost its signers.
+//
+//   total = fee + sum of rent.minimum_balance(space)
+//           over every SystemProgram CreateAccount instruction
+//
+// A new account must be funded to at least its rent-exempt minimum, so a
+// client can't know the real cost of a transaction from the fee alone.
+// Instructions that don't decode are skipped here; the SVM rejects them.
+// ---------------------------------------------------------------------------
+pub fn total_required_balance(
+    message: &Message,
+    fee_calculator: &FeeCalculator,
+    rent: &Rent,
+) -> Result<u64, BankError> {
+    let fee = calculate_fee(message, fee_calculator)?;
+
+    let rent_minimums = message
+        .instructions
+        .iter()
+        .filter(|ix| message.account_keys.get(ix.program_id_index as usize) == Some(&SYSTEM_PROGRAM_ID))
+        .filter_map(|ix| match system::decode(&ix.data) {
+            Ok(SystemInstruction::CreateAccount { space, .. }) => {
+                Some(rent.minimum_balance(usize::try_from(space).unwrap_or(usize::MAX)))
+            }
+            _ => None,
+        })
+        .fold(0u64, u64::saturating_add);
+
+    Ok(fee.saturating_add(rent_minimums))
+}
+
+// ---------------------------------------------------------------------------
+// collect_fee — debit the fee from the fee payer, account_keys[0].
+//
+// Called after sanitize, the blockhash and signature checks, and BEFORE
+// execution. The fee is kept even if execution later fails: the validator
+// did the work of verifying and running the transaction either way.
+// Collected fees are burned — there are no validators to pay here.
+// Returns the fee payer.
+// ---------------------------------------------------------------------------
+pub fn collect_fee(message: &Message, fee: u64, db: &mut AccountsDB) -> Result<Pubkey, BankError> {
+    let payer = *message.fee_payer().ok_or(BankError::NoFeePayer)?;
+
+    let mut account = db.load(&payer).cloned().unwrap_or_default();
+    let balance     = account.lamports();
+    if balance < fee {
+        return Err(BankError::InsufficientFundsForFee { fee, balance });
+    }
+
+    if fee > 0 {
+        account.set_lamports(balance - fee);
+        db.store(payer, account);
+    }
+    Ok(payer)
+}
+
+// ---------------------------------------------------------------------------
+// resolve_transaction — the legacy Transaction the Bank and SVM execute.
+//
+// A legacy message is taken as it is; a v0 one is expanded with
+// resolve_v0 against the lookup tables in `db`. The signatures are carried
+// over unchanged, so the result keeps the transaction's id, but they cover
+// the v0 bytes: check them with verify_versioned first, as
+// verify_signatures would reject the resolved form.
+// ---------------------------------------------------------------------------
+pub fn resolve_transaction(tx: &VersionedTransaction, db: &AccountsDB) -> Result<Transaction, BankError> {
+    let message = match &tx.message {
+        VersionedMessage::Legacy(message) => message.clone(),
+        VersionedMessage::V0(message)     => resolve_v0(message, db).map_err(BankError::AddressLookup)?,
+    };
+    Ok(Transaction::new(message, tx.signatures.clone()))
+}
+
+// ---------------------------------------------------------------------------
+// resolve_v0 — expand a v0 message into the equivalent legacy Message.
+//
+// Each lookup table is loaded from AccountsDB and the referenced entries
+// are spliced into account_keys in legacy order:
+//
+//   [static writable | loaded writable | static readonly non-signers | loaded readonly]
+//    (signers first)
+//
+// Legacy messages mark readonly non-signers by position from the END of
+// account_keys, so loaded writable keys must land before the static
+// readonly ones. Instruction indexes are remapped to match, and
+// num_readonly_unsigned_accounts grows by the number of loaded readonly keys.
+//
+// Instruction indexes are single bytes, so a message that resolves to more
+// than 256 keys is rejected before anything is remapped.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/accounts-db/src/accounts.rs
+// ---------------------------------------------------------------------------
+pub fn resolve_v0(msg: &MessageV0, db: &AccountsDB) -> Result<Message, AddressLookupError> {
+    // --- Load every referenced table entry ---
+    let mut loaded_writable = Vec::new();
+    let mut loaded_readonly = Vec::new();
+
+    for lookup in &msg.address_table_lookups {
+        let table = db
+            .load(&lookup.account_key)
+            .ok_or(AddressLookupError::LookupTableAccountNotFound)?;
+        let addresses = address_lookup_table::addresses(table)?;
+
+        let entry = |index: &u8| {
+            addresses
+                .get(*index as usize)
+                .copied()
+                .ok_or(AddressLookupError::InvalidLookupIndex)
+        };
+        for index in &lookup.writable_indexes {
+            loaded_writable.push(entry(index)?);
+        }
+        for index in &lookup.readonly_indexes {
+            loaded_readonly.push(entry(index)?);
+        }
+    }
+
+    // --- Splice them into a legacy account_keys list ---
+    let num_static          = msg.account_keys.len();
+    let num_static_readonly = (msg.header.num_readonly_unsigned_accounts as usize).min(num_static);
+    let split               = num_static - num_static_readonly;
+    let num_writable        = loaded_writable.len();
+
+    let count = num_static + num_writable + loaded_readonly.len();
+    if count > MAX_RESOLVED_ACCOUNT_KEYS {
+        return Err(AddressLookupError::TooManyAccountKeys { count });
+    }
+
+    let mut account_keys = Vec::with_capacity(num_static + num_writable + loaded_readonly.len());
+    account_keys.extend_from_slice(&msg.account_keys[..split]);
+    account_keys.extend(loaded_writable);
+    account_keys.extend_from_slice(&msg.account_keys[split..]);
+    account_keys.extend(loaded_readonly.iter().copied());
+
+    // v0 index space:     [static | loaded writable | loaded readonly]
+    // legacy index space: [static writable | loaded writable | static readonly | loaded readonly]
+    let remap = |index: u8| -> u8 {
+        let i = index as usize;
+        let remapped = if i < split {
+            i
+        } else if i < num_static {
+            i + num_writable
+        } else if i < num_static + num_writable {
+            i - num_static_readonly
+        } else {
+            i
+        };
+        remapped as u8
+    };
+
+    let instructions = msg
+        .instructions
+        .iter()
+        .map(|ix| {
+            let mut ix = ix.clone();
+            ix.program_id_index = remap(ix.program_id_index);
+            ix.accounts = ix.accounts.iter().map(|&index| remap(index)).collect();
+            ix
+        })
+        .collect();
+
+    // At most 256 keys, so this only overflows for a header with no
+    // signers at all, which Message::sanitize rejects anyway.
+    let num_readonly_unsigned = u8::try_from(num_static_readonly + loaded_readonly.len())
+        .map_err(|_| AddressLookupError::TooManyAccountKeys { count })?;
+    let header = MessageHeader {
+        num_readonly_unsigned_accounts: num_readonly_unsigned,
+        ..msg.header
+    };
+
+    Ok(Message::new(header, account_keys, msg.recent_blockhash, instructions))
 }
 
 // ---------------------------------------------------------------------------
 // serialize_message — canonical byte encoding of a Message.
 //
-// This is what gets signed by the sender and verified by the Bank.
-// Real Solana uses a compact binary encoding; we use a simple but
-// deterministic layout:
-//
-//   [num_required_signatures:        u8]
-//   [num_readonly_signed_accounts:   u8]
-//   [num_readonly_unsigned_accounts: u8]
-//   [num_account_keys:               u8]
-//   [account_key_0:                  32 bytes]
-//   ...
-//   [recent_blockhash:               32 bytes]
-//   [num_instructions:               u8]
-//   for each instruction:
-//     [program_id_index:   u8]
-//     [num_accounts:       u8]
-//     [accounts:           num_accounts bytes]
-//     [data_len:           u16 LE]
-//     [data:               data_len bytes]
+// This is what gets signed by the sender and verified by the Bank: the
+// legacy message wire format (see Message::serialize for the layout).
 // ---------------------------------------------------------------------------
 pub fn serialize_message(msg: &Message) -> Vec<u8> {
-    let mut buf = Vec::new();
+    msg.serialize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::address_lookup_table::{lookup_table_data, ADDRESS_LOOKUP_TABLE_PROGRAM_ID};
+    use crate::programs::memo::MEMO_PROGRAM_ID;
+    use crate::runtime::genesis::ClusterType;
+    use crate::runtime::transaction_error::InstructionError;
+    use crate::types::account::AccountSharedData;
+    use crate::types::keys::genesis_keypair;
+    use crate::types::transaction::{AccountMeta, Instruction, MessageAddressTableLookup};
 
-    // Header
-    buf.push(msg.header.num_required_signatures);
-    buf.push(msg.header.num_readonly_signed_accounts);
-    buf.push(msg.header.num_readonly_unsigned_accounts);
+    const TABLE: Pubkey = Pubkey([0xab; 32]);
 
-    // Account keys
-    buf.push(msg.account_keys.len() as u8);
-    for key in &msg.account_keys {
-        buf.extend_from_slice(&key.0);
+    fn db_with_table(entries: &[Pubkey]) -> AccountsDB {
+        let mut db    = AccountsDB::new();
+        let mut table = AccountSharedData::new(1, 0, ADDRESS_LOOKUP_TABLE_PROGRAM_ID);
+        *table.data_mut() = lookup_table_data(entries);
+        db.store(TABLE, table);
+        db
     }
 
-    // Recent blockhash
-    buf.extend_from_slice(&msg.recent_blockhash.0);
+    // A v0 transfer from the payer to each of `writable`, loaded from
+    // TABLE, passing `readonly` along as a readonly account.
+    fn v0_message(payer: Pubkey, writable: &[u8], readonly: &[u8]) -> MessageV0 {
+        // v0 index space: [payer, SystemProgram | loaded writable | loaded readonly]
+        let num_loaded   = (writable.len() + readonly.len()) as u8;
+        let readonly_at  = 2 + writable.len() as u8;
+        let instructions = (0..writable.len() as u8)
+            .map(|i| {
+                let mut accounts = vec![0, 2 + i];
+                accounts.extend(readonly_at..2 + num_loaded);
+                CompiledInstruction::new(1, accounts, system::transfer(&payer, &payer, 100).data)
+            })
+            .collect();
+        MessageV0 {
+            header: MessageHeader {
+                num_required_signatures:        1,
+                num_readonly_signed_accounts:   0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, SYSTEM_PROGRAM_ID],
+            recent_blockhash: Hash([7; 32]),
+            instructions,
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key:      TABLE,
+                writable_indexes: writable.to_vec(),
+                readonly_indexes: readonly.to_vec(),
+            }],
+        }
+    }
+
+    #[test]
+    fn resolve_v0_splices_two_writable_and_one_readonly_entry() {
+        let (payer, _) = genesis_keypair(1);
+        let entries    = [Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32])];
+        let db         = db_with_table(&entries);
+
+        let message = resolve_v0(&v0_message(payer, &[0, 2], &[1]), &db).unwrap();
 
-    // Instructions
-    buf.push(msg.instructions.len() as u8);
-    for ix in &msg.instructions {
-        buf.push(ix.program_id_index);
-        buf.push(ix.accounts.len() as u8);
-        buf.extend_from_slice(&ix.accounts);
-        let data_len = ix.data.len() as u16;
-        buf.extend_from_slice(&data_len.to_le_bytes());
-        buf.extend_from_slice(&ix.data);
+        // [static writable | loaded writable | static readonly | loaded readonly]
+        assert_eq!(message.account_keys, vec![payer, entries[0], entries[2], SYSTEM_PROGRAM_ID, entries[1]]);
+        assert_eq!(message.header.num_readonly_unsigned_accounts, 2);
+        let writable: Vec<bool> = (0..5).map(|i| message.is_writable(i)).collect();
+        assert_eq!(writable, vec![true, true, true, false, false]);
+
+        // Each instruction still names the same keys.
+        assert_eq!(message.instructions[0].program_id_index, 3);
+        assert_eq!(message.instructions[0].accounts, vec![0, 1, 4]);
+        assert_eq!(message.instructions[1].accounts, vec![0, 2, 4]);
+        assert_eq!(message.sanitize(), Ok(()));
+    }
+
+    #[test]
+    fn resolve_v0_rejects_a_bad_index_or_missing_table() {
+        let (payer, _) = genesis_keypair(1);
+        let db         = db_with_table(&[Pubkey([1; 32])]);
+        assert_eq!(
+            resolve_v0(&v0_message(payer, &[1], &[]), &db),
+            Err(AddressLookupError::InvalidLookupIndex)
+        );
+        assert_eq!(
+            resolve_v0(&v0_message(payer, &[0], &[]), &AccountsDB::new()),
+            Err(AddressLookupError::LookupTableAccountNotFound)
+        );
+    }
+
+    #[test]
+    fn resolve_v0_rejects_more_than_256_keys() {
+        let (payer, _) = genesis_keypair(1);
+        let entries: Vec<Pubkey> = (0..=255).map(|i| Pubkey([i; 32])).collect();
+        let db         = db_with_table(&entries);
+
+        // 2 static + 254 loaded is the most that fits; one more would wrap
+        // the remapped indexes.
+        let fits: Vec<u8> = (0..254).collect();
+        let mut message = v0_message(payer, &[], &fits);
+        assert_eq!(resolve_v0(&message, &db).unwrap().account_keys.len(), 256);
+
+        message.address_table_lookups[0].readonly_indexes.push(254);
+        assert_eq!(resolve_v0(&message, &db), Err(AddressLookupError::TooManyAccountKeys { count: 257 }));
     }
 
-    buf
+    #[test]
+    fn signed_v0_transfer_verifies_resolves_and_executes() {
+        let (payer, key) = genesis_keypair(1);
+        let (to, _)      = genesis_keypair(2);
+        let mut db       = db_with_table(&[to]);
+        db.store(payer, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+
+        let message = v0_message(payer, &[0], &[]);
+        let signature = Signature(ed25519_dalek::Signer::sign(&key, &message.serialize()).to_bytes());
+        let tx = VersionedTransaction { signatures: vec![signature], message: VersionedMessage::V0(message) };
+
+        // Through the wire format and back, as sendTransaction receives it.
+        let tx = VersionedTransaction::deserialize(&tx.serialize()).unwrap();
+        assert!(verify_versioned(&tx).is_ok());
+
+        let resolved = resolve_transaction(&tx, &db).unwrap();
+        assert_eq!(resolved.signatures, tx.signatures);
+        // The v0 signature does not cover the resolved legacy bytes.
+        assert!(verify_signatures(&resolved).is_err());
+
+        execute_transaction(&resolved, &mut db, &Hash([7; 32]), &mut LogCollector::new()).unwrap();
+        assert_eq!(db.load(&payer).unwrap().lamports(), 900);
+        assert_eq!(db.load(&to).unwrap().lamports(), 100);
+    }
+
+    // An unsigned transfer of `lamports` from `from` to `to`; process_entry
+    // does not check signatures.
+    fn transfer_tx(from: Pubkey, to: Pubkey, lamports: u64) -> Transaction {
+        let message = Message::compile(Some(&from), &[system::transfer(&from, &to, lamports)]).unwrap();
+        Transaction::new(message, vec![Default::default()])
+    }
+
+    #[test]
+    fn process_entry_matches_running_the_transfers_in_order() {
+        let [a, b, c, d, e] = [1, 2, 3, 4, 5].map(|i| Pubkey([i; 32]));
+        let mut db = AccountsDB::new();
+        for (key, lamports) in [(a, 1_000), (b, 100), (c, 500), (d, 0), (e, 0)] {
+            db.store(key, AccountSharedData::new(lamports, 0, SYSTEM_PROGRAM_ID));
+        }
+        let db = Mutex::new(db);
+
+        let txs = [
+            transfer_tx(a, b, 100),   // 0
+            transfer_tx(c, d, 50),    // 1: independent of 0
+            transfer_tx(b, e, 150),   // 2: needs 0's credit to B
+            transfer_tx(a, c, 10),    // 3: shares A with 0 and C with 1
+            transfer_tx(e, a, 1_000), // 4: E only ever holds 150
+        ];
+        let results = process_entry(&txs, &db, &Hash::default());
+
+        assert_eq!(results[..4], [Ok(()), Ok(()), Ok(()), Ok(())]);
+        assert_eq!(results[4], Err(TransactionError::InstructionError(0, InstructionError::Custom(1))));
+        let db = db.into_inner().unwrap();
+        let balances: Vec<u64> = [a, b, c, d, e].iter().map(|key| db.load(key).unwrap().lamports()).collect();
+        assert_eq!(balances, vec![890, 50, 460, 50, 150]);
+    }
+
+    #[test]
+    fn failed_multi_instruction_transaction_leaves_the_db_as_it_was() {
+        let [a, b, c] = [1, 2, 3].map(|i| Pubkey([i; 32]));
+        let mut db = AccountsDB::new();
+        for key in [a, b, c] {
+            db.store(key, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        }
+        let (hash, versions) = (db.hash(), db.num_versions());
+        let write_versions: Vec<Option<u64>> = [a, b, c].iter().map(|key| db.version(key)).collect();
+
+        // The first transfer succeeds; the second overdraws A.
+        let message = Message::compile(Some(&a), &[system::transfer(&a, &b, 100), system::transfer(&a, &c, 10_000)]).unwrap();
+        let tx      = Transaction::new(message, vec![Default::default()]);
+        let result  = execute_transaction(&tx, &mut db, &Hash::default(), &mut LogCollector::new());
+
+        assert!(matches!(result, Err(svm::SvmError::SystemProgram { instruction: 1, .. })));
+        assert_eq!(db.hash(), hash);
+        assert_eq!(db.num_versions(), versions);
+        assert_eq!([a, b, c].iter().map(|key| db.version(key)).collect::<Vec<_>>(), write_versions);
+        assert_eq!(db.load(&b).unwrap().lamports(), 1_000);
+    }
+
+    // A transfer from the payer to itself, with `signatures` attached and
+    // the header asking for `num_required`.
+    fn self_transfer_tx(num_required: u8, signatures: usize) -> Transaction {
+        let (payer, _)  = genesis_keypair(1);
+        let mut message = Message::compile(Some(&payer), &[system::transfer(&payer, &payer, 1)]).unwrap();
+        message.header.num_required_signatures = num_required;
+        Transaction::new(message, vec![Default::default(); signatures])
+    }
+
+    #[test]
+    fn extra_signature_is_rejected_before_verification() {
+        assert!(sanitize_transaction(&self_transfer_tx(1, 1)).is_ok());
+        assert!(matches!(
+            sanitize_transaction(&self_transfer_tx(1, 2)),
+            Err(BankError::TooManySignatures { expected: 1, got: 2 })
+        ));
+    }
+
+    #[test]
+    fn zero_required_signatures_is_rejected_before_verification() {
+        for signatures in [0, 1] {
+            assert!(matches!(
+                sanitize_transaction(&self_transfer_tx(0, signatures)),
+                Err(BankError::InvalidMessage(SanitizeError::InvalidHeader))
+            ));
+        }
+    }
+
+    #[test]
+    fn missing_signature_is_rejected_before_verification() {
+        assert!(matches!(
+            sanitize_transaction(&self_transfer_tx(1, 0)),
+            Err(BankError::NotEnoughSignatures { expected: 1, got: 0 })
+        ));
+    }
+
+    #[test]
+    fn fee_at_max_fee_is_accepted_and_one_lamport_over_is_not() {
+        let (payer, _)  = genesis_keypair(1);
+        let calculator  = FeeCalculator::new(LAMPORTS_PER_SIGNATURE);
+        // 1_000 micro-lamports per unit over the default 200_000 units of one
+        // instruction: a 200 lamport priority fee.
+        let message = |max_fee| {
+            Message::compile(Some(&payer), &[
+                system::transfer(&payer, &payer, 1),
+                compute_budget::set_compute_unit_price(1_000),
+                compute_budget::set_max_fee(max_fee),
+            ])
+            .unwrap()
+        };
+        let fee = LAMPORTS_PER_SIGNATURE + 200;
+
+        assert!(matches!(calculate_fee(&message(fee), &calculator), Ok(f) if f == fee));
+        assert!(matches!(
+            calculate_fee(&message(fee - 1), &calculator),
+            Err(BankError::FeeExceedsMax { fee: f, max_fee }) if f == fee && max_fee == fee - 1
+        ));
+    }
+
+    #[test]
+    fn account_limit_is_inclusive() {
+        // The payer, the memo program and `count - 2` readonly accounts.
+        let payer   = Pubkey([1; 32]);
+        let message = |count: usize| {
+            let accounts = (0..count - 2).map(|i| AccountMeta::new_readonly(Pubkey([i as u8 + 2; 32]), false)).collect();
+            let memo     = Instruction::new(MEMO_PROGRAM_ID, accounts, b"many".to_vec());
+            Message::compile(Some(&payer), &[memo]).unwrap()
+        };
+        let max = ClusterType::Development.max_accounts_per_transaction();
+        assert_eq!(max, MAX_TX_ACCOUNT_LOCKS);
+
+        assert!(check_account_limit(&message(max), max).is_ok());
+        let error = check_account_limit(&message(max + 1), max).unwrap_err();
+        assert!(matches!(error, BankError::TooManyAccounts { count, max: cap } if count == max + 1 && cap == max));
+        assert_eq!(TransactionError::from(error), TransactionError::TooManyAccountLocks);
+    }
+
+    #[test]
+    fn status_cache_holds_a_signature_until_its_blockhash_expires() {
+        let (old, new) = (Hash([1; 32]), Hash([2; 32]));
+        let mut queue  = BlockhashQueue::new(1);
+        queue.register_hash(old);
+
+        let mut tx = Transaction { signatures: vec![Signature([5; 64])], ..Default::default() };
+        tx.message.recent_blockhash = old;
+
+        let mut cache = StatusCache::new();
+        assert!(cache.check(&tx).is_ok());
+        cache.insert(&tx);
+        assert!(matches!(cache.check(&tx), Err(BankError::AlreadyProcessed)));
+
+        // The same signature under another blockhash is a different transaction.
+        let mut other = tx.clone();
+        other.message.recent_blockhash = new;
+        assert!(cache.check(&other).is_ok());
+
+        cache.purge(&queue);
+        assert_eq!(cache.len(), 1);
+        queue.register_hash(new);
+        cache.purge(&queue);
+        assert!(cache.is_empty());
+        assert!(cache.check(&tx).is_ok());
+    }
 }
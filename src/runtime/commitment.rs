@@ -0,0 +1,67 @@
+// ---------------------------------------------------------------------------
+// Commitment — how settled the state a client reads must be.
+//
+// A Solana node holds several versions of the chain at once: the slot it
+// is still processing, slots the cluster has voted on, and slots so deeply
+// buried they can never be rolled back. A client picks which one it reads:
+//
+//   processed — the live state, including the slot still in progress
+//   confirmed — the last completed slot
+//   finalized — a slot 32 deep, past the point of no return
+//
+// With a single node and no votes, "confirmed" and "finalized" are
+// modelled purely by depth: the state as it was at the end of a slot that
+// many slots behind the current one, read from the AccountsDB history.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/commitment-config/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use crate::types::account::Slot;
+
+// Slots behind the current one a confirmed read looks: the last completed
+// slot.
+pub const CONFIRMED_DEPTH: Slot = 1;
+
+// Slots behind the current one a finalized read looks. Matches
+// MAX_LOCKOUT_HISTORY + 1 in real Solana: once 32 slots are stacked on top
+// of a vote, its lockout can no longer expire.
+pub const FINALIZED_DEPTH: Slot = 32;
+
+// ---------------------------------------------------------------------------
+// CommitmentLevel
+//
+// Real Solana RPC defaults to finalized. Here the default is processed, so
+// a balance read right after a transfer shows it.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CommitmentLevel {
+    #[default]
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl CommitmentLevel {
+    /// Parse the name clients send: processed, confirmed or finalized.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "processed" => Some(CommitmentLevel::Processed),
+            "confirmed" => Some(CommitmentLevel::Confirmed),
+            "finalized" => Some(CommitmentLevel::Finalized),
+            _           => None,
+        }
+    }
+
+    /// The slot whose end state a read at this level sees, given the slot
+    /// currently in progress. None for processed, which reads live state.
+    ///
+    /// Until the chain is deep enough, slot 0 stands in: the genesis
+    /// accounts are stored there, so it is the oldest state there is.
+    pub fn slot(&self, current_slot: Slot) -> Option<Slot> {
+        match self {
+            CommitmentLevel::Processed => None,
+            CommitmentLevel::Confirmed => Some(current_slot.saturating_sub(CONFIRMED_DEPTH)),
+            CommitmentLevel::Finalized => Some(current_slot.saturating_sub(FINALIZED_DEPTH)),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// ---------------------------------------------------------------------------
+// Epoch schedule — which epoch each slot falls in.
+//
+// Slots are grouped into epochs of a fixed number of slots: slot N is in
+// epoch N / slots_per_epoch, at index N % slots_per_epoch within it. In
+// Solana the epoch is when stake changes take effect, rent is collected
+// and the next leader schedule is fixed; here it is only a coarser clock
+// clients can read with getEpochInfo.
+//
+// Solana's epochs are 432,000 slots (about two days), after a warmup of
+// shorter ones. There is no warmup here, and the default epoch is short
+// enough to watch several go by.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/epoch-schedule/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use crate::types::account::{Epoch, Slot};
+
+// Slots per epoch unless --slots-per-epoch says otherwise.
+pub const DEFAULT_SLOTS_PER_EPOCH: u64 = 32;
+
+// ---------------------------------------------------------------------------
+// EpochScheduleError — why a schedule could not be built.
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum EpochScheduleError {
+    /// An epoch must hold at least one slot.
+    InvalidSlotsPerEpoch(u64),
+}
+
+// ---------------------------------------------------------------------------
+// EpochInfo — where a slot sits in the schedule, as getEpochInfo reports it.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EpochInfo {
+    pub epoch:          Epoch,
+    /// The slot's position within its epoch, from 0.
+    pub slot_index:     u64,
+    pub slots_in_epoch: u64,
+    pub absolute_slot:  Slot,
+    pub block_height:   u64,
+}
+
+// ---------------------------------------------------------------------------
+// EpochSchedule
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EpochSchedule {
+    pub slots_per_epoch: u64,
+}
+
+impl Default for EpochSchedule {
+    fn default() -> Self {
+        EpochSchedule { slots_per_epoch: DEFAULT_SLOTS_PER_EPOCH }
+    }
+}
+
+impl EpochSchedule {
+    /// A schedule whose every epoch is `slots_per_epoch` slots long.
+    pub fn new(slots_per_epoch: u64) -> Result<Self, EpochScheduleError> {
+        if slots_per_epoch == 0 {
+            return Err(EpochScheduleError::InvalidSlotsPerEpoch(slots_per_epoch));
+        }
+        Ok(EpochSchedule { slots_per_epoch })
+    }
+
+    /// The epoch `slot` falls in.
+    pub fn get_epoch(&self, slot: Slot) -> Epoch {
+        slot / self.slots_per_epoch
+    }
+
+    /// The position of `slot` within its epoch.
+    pub fn get_slot_index(&self, slot: Slot) -> u64 {
+        slot % self.slots_per_epoch
+    }
+
+    /// Where `slot` sits in the schedule, with the chain's `block_height`
+    /// at that slot.
+    pub fn epoch_info(&self, slot: Slot, block_height: u64) -> EpochInfo {
+        EpochInfo {
+            epoch:          self.get_epoch(slot),
+            slot_index:     self.get_slot_index(slot),
+            slots_in_epoch: self.slots_per_epoch,
+            absolute_slot:  slot,
+            block_height,
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// ---------------------------------------------------------------------------
+// Fee — what a transaction pays before the priority fee.
+//
+//   base fee = lamports_per_signature   * num_required_signatures
+//            + lamports_per_instruction * instructions
+//
+// Solana charges per signature only, 5000 lamports each; the
+// per-instruction part is a node-specific knob and is 0 unless set. The
+// calculator lives in NodeState, so the node's fees can be tuned in one
+// place, and getLatestBlockhash reports it as feeCalculator so a client can
+// work out its fee before signing. Bank::calculate_fee adds the priority
+// fee on top.
+//
+// Ledger replay charges with the same calculator: changing it between runs
+// of a persisted ledger changes what replaying it gives.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/fee/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use crate::types::transaction::Message;
+
+// ---------------------------------------------------------------------------
+// FeeCalculator
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeCalculator {
+    pub lamports_per_signature:   u64,
+    /// Not in Solana: charged for every instruction in the message.
+    pub lamports_per_instruction: u64,
+}
+
+impl FeeCalculator {
+    /// A calculator that charges `lamports_per_signature` per required
+    /// signature and nothing per instruction, as Solana does.
+    pub fn new(lamports_per_signature: u64) -> Self {
+        FeeCalculator { lamports_per_signature, lamports_per_instruction: 0 }
+    }
+
+    /// The base fee for `message`. Saturates rather than overflowing.
+    pub fn calculate_fee(&self, message: &Message) -> u64 {
+        let signatures   = message.header.num_required_signatures as u64;
+        let instructions = message.instructions.len() as u64;
+        self.lamports_per_signature
+            .saturating_mul(signatures)
+            .saturating_add(self.lamports_per_instruction.saturating_mul(instructions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::compute_budget;
+    use crate::programs::system;
+    use crate::runtime::bank;
+    use crate::types::account::Pubkey;
+
+    #[test]
+    fn fee_is_per_signature_plus_the_priority_fee() {
+        let (payer, from, to) = (Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32]));
+        let transfer  = system::transfer(&from, &to, 1);
+        let unpriced  = Message::compile(Some(&payer), std::slice::from_ref(&transfer)).unwrap();
+        // One non-ComputeBudget instruction: a 200,000-unit limit, so
+        // 25 micro-lamports a unit is 5 lamports.
+        let priced    = Message::compile(Some(&payer), &[compute_budget::set_compute_unit_price(25), transfer]).unwrap();
+        let fees      = FeeCalculator::new(5_000);
+        assert_eq!(unpriced.header.num_required_signatures, 2);
+
+        assert_eq!(fees.calculate_fee(&unpriced), 10_000);
+        assert_eq!(fees.calculate_fee(&priced), 10_000);
+        assert_eq!(bank::calculate_fee(&unpriced, &fees).unwrap(), 10_000);
+        assert_eq!(bank::calculate_fee(&priced, &fees).unwrap(), 10_005);
+
+        // The node-specific per-instruction charge, and saturation.
+        let per_instruction = FeeCalculator { lamports_per_signature: 5_000, lamports_per_instruction: 7 };
+        assert_eq!(per_instruction.calculate_fee(&priced), 10_014);
+        assert_eq!(FeeCalculator::new(u64::MAX).calculate_fee(&priced), u64::MAX);
+    }
+}
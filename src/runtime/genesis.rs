@@ -0,0 +1,274 @@
+// ---------------------------------------------------------------------------
+// Genesis — the settings a chain is born with.
+//
+// Real Solana runs several independent clusters from the same code. Which
+// one a node belongs to is fixed at genesis and decides defaults that
+// should never be mixed up — above all, development shortcuts must not be
+// allowed on the cluster that holds real value.
+//
+// What the chain starts with is a GenesisConfig: the accounts that exist
+// before the first transaction and the PoH tick settings. It is either
+// built in code (GenesisConfig::new, the node's five development keys) or
+// loaded from a JSON file, so a scenario can be reproduced without editing
+// source:
+//
+//   { "hashes_per_tick": 100, "ticks_per_slot": 8,
+//     "accounts": [ { "pubkey": "<base58>", "lamports": 5000000000,
+//                     "owner": "<base58>"?, "data": "<base64>"?,
+//                     "executable": false? } ] }
+//
+// The tick settings default to the node's; "owner" defaults to the
+// SystemProgram and "data" to empty.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/genesis-config/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use base64::prelude::*;
+use serde_json::Value;
+
+use crate::programs::system::SYSTEM_PROGRAM_ID;
+use crate::runtime::accounts_db::{AccountHashAlgorithm, AccountsDB};
+use crate::runtime::bank::{LAMPORTS_PER_SIGNATURE, MAX_TX_ACCOUNT_LOCKS};
+use crate::runtime::poh::DEFAULT_TICKS_PER_SLOT;
+use crate::runtime::rpc::DEFAULT_HASHES_PER_TICK;
+use crate::types::account::{AccountSharedData, Lamports, Pubkey};
+use crate::types::keys::genesis_pubkey;
+
+// How many development keypairs the node holds: ids 1 through 5 (see
+// keys::genesis_keypair). GenesisConfig::new funds each of them.
+pub const NUM_GENESIS_KEYPAIRS: u8 = 5;
+
+// ---------------------------------------------------------------------------
+// ClusterType — which network this chain is.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ClusterType {
+    /// A local throwaway chain. Everything goes.
+    #[default]
+    Development,
+    /// A public test network. Tokens are free but the rules are real.
+    Testnet,
+    /// The real network. No development shortcuts.
+    MainnetBeta,
+}
+
+impl ClusterType {
+    /// Parse the name used on the command line: development, testnet,
+    /// or mainnet-beta.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "development"  => Some(ClusterType::Development),
+            "testnet"      => Some(ClusterType::Testnet),
+            "mainnet-beta" => Some(ClusterType::MainnetBeta),
+            _              => None,
+        }
+    }
+
+    /// Lamports each genesis account starts with.
+    pub fn genesis_account_lamports(&self) -> Lamports {
+        match self {
+            ClusterType::Development => 100_000_000_000, // 100 SOL
+            ClusterType::Testnet     =>  10_000_000_000, //  10 SOL
+            ClusterType::MainnetBeta =>   1_000_000_000, //   1 SOL
+        }
+    }
+
+    /// Base fee per required signature. Every real cluster charges the
+    /// same 5000 lamports; it is looked up per cluster so a development
+    /// chain can be tuned without touching the others.
+    pub fn lamports_per_signature(&self) -> u64 {
+        LAMPORTS_PER_SIGNATURE
+    }
+
+    /// Most accounts a single transaction may reference. The same 64 on
+    /// every cluster for now, looked up here like the fee so it can be
+    /// tuned per cluster.
+    pub fn max_accounts_per_transaction(&self) -> usize {
+        MAX_TX_ACCOUNT_LOCKS
+    }
+
+    /// Whether the node may run with signature verification switched off
+    /// (--skip-sig-verify). Never on MainnetBeta.
+    pub fn permits_signature_bypass(&self) -> bool {
+        !matches!(self, ClusterType::MainnetBeta)
+    }
+}
+
+impl fmt::Display for ClusterType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ClusterType::Development => "development",
+            ClusterType::Testnet     => "testnet",
+            ClusterType::MainnetBeta => "mainnet-beta",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// GenesisError — why a genesis file could not be loaded.
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum GenesisError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The file is not JSON.
+    InvalidJson(serde_json::Error),
+    /// A field is missing or malformed; names the field.
+    InvalidField(String),
+    /// A slot must contain at least one tick.
+    InvalidTicksPerSlot(u64),
+    /// A tick must contain at least one hash.
+    InvalidHashesPerTick(u64),
+}
+
+// ---------------------------------------------------------------------------
+// GenesisConfig — the accounts and PoH settings a chain starts with.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct GenesisConfig {
+    /// Every account that exists before the first transaction.
+    pub accounts:        Vec<(Pubkey, AccountSharedData)>,
+    pub hashes_per_tick: u64,
+    pub ticks_per_slot:  u64,
+}
+
+impl GenesisConfig {
+    /// The node's default genesis: one system-owned account per genesis
+    /// keypair, each holding what `cluster_type` gives it.
+    pub fn new(cluster_type: ClusterType, hashes_per_tick: u64) -> Self {
+        let lamports = cluster_type.genesis_account_lamports();
+        let accounts = (1..=NUM_GENESIS_KEYPAIRS)
+            .map(|id| (genesis_pubkey(id), AccountSharedData::new(lamports, 0, SYSTEM_PROGRAM_ID)))
+            .collect();
+        GenesisConfig { accounts, hashes_per_tick, ticks_per_slot: DEFAULT_TICKS_PER_SLOT }
+    }
+
+    /// Load a genesis from the JSON file at `path` (format above).
+    pub fn load(path: &Path) -> Result<Self, GenesisError> {
+        let text  = std::fs::read_to_string(path).map_err(GenesisError::Io)?;
+        let value = serde_json::from_str(&text).map_err(GenesisError::InvalidJson)?;
+        GenesisConfig::from_json(&value)
+    }
+
+    /// Parse a genesis from its JSON form (format above).
+    pub fn from_json(value: &Value) -> Result<Self, GenesisError> {
+        let invalid = |field: &str| GenesisError::InvalidField(field.to_string());
+        let optional_u64 = |field: &str, default: u64| match &value[field] {
+            Value::Null => Ok(default),
+            v => v.as_u64().ok_or_else(|| invalid(field)),
+        };
+
+        let hashes_per_tick = optional_u64("hashes_per_tick", DEFAULT_HASHES_PER_TICK)?;
+        if hashes_per_tick == 0 {
+            return Err(GenesisError::InvalidHashesPerTick(hashes_per_tick));
+        }
+        let ticks_per_slot = optional_u64("ticks_per_slot", DEFAULT_TICKS_PER_SLOT)?;
+        if ticks_per_slot == 0 {
+            return Err(GenesisError::InvalidTicksPerSlot(ticks_per_slot));
+        }
+
+        let accounts = value["accounts"]
+            .as_array()
+            .ok_or_else(|| invalid("accounts"))?
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let field   = |name: &str| format!("accounts[{}].{}", i, name);
+                let address = |name: &str| match &entry[name] {
+                    Value::Null if name == "owner" => Ok(SYSTEM_PROGRAM_ID),
+                    v => v.as_str().and_then(Pubkey::from_base58).ok_or_else(|| invalid(&field(name))),
+                };
+                let pubkey   = address("pubkey")?;
+                let owner    = address("owner")?;
+                let lamports = entry["lamports"].as_u64().ok_or_else(|| invalid(&field("lamports")))?;
+                let data = match &entry["data"] {
+                    Value::Null => Vec::new(),
+                    v => v
+                        .as_str()
+                        .and_then(|data| BASE64_STANDARD.decode(data).ok())
+                        .ok_or_else(|| invalid(&field("data")))?,
+                };
+                let executable = match &entry["executable"] {
+                    Value::Null => false,
+                    v => v.as_bool().ok_or_else(|| invalid(&field("executable")))?,
+                };
+
+                let mut account = AccountSharedData::new(lamports, data.len(), owner);
+                account.data_mut().copy_from_slice(&data);
+                account.set_executable(executable);
+                Ok((pubkey, account))
+            })
+            .collect::<Result<_, GenesisError>>()?;
+
+        Ok(GenesisConfig { accounts, hashes_per_tick, ticks_per_slot })
+    }
+
+    /// A fresh AccountsDB holding the genesis accounts, hashed with
+    /// `hash_algorithm`.
+    pub fn create_db(&self, hash_algorithm: AccountHashAlgorithm) -> AccountsDB {
+        let mut db = AccountsDB::with_hash_algorithm(hash_algorithm);
+        for (pubkey, account) in &self.accounts {
+            db.store(*pubkey, account.clone());
+        }
+        db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn genesis_file_accounts_and_defaults() {
+        let (plain, program, owner) = (Pubkey([1; 32]), Pubkey([2; 32]), Pubkey([3; 32]));
+        let config = json!({
+            "ticks_per_slot": 4,
+            "accounts": [
+                { "pubkey": plain.to_base58(), "lamports": 10 },
+                { "pubkey": program.to_base58(), "lamports": 20, "owner": owner.to_base58(),
+                  "data": BASE64_STANDARD.encode([1, 2, 3]), "executable": true },
+            ],
+        });
+        let path = std::env::temp_dir().join(format!("genesis-test-{}.json", std::process::id()));
+        std::fs::write(&path, config.to_string()).unwrap();
+        let genesis = GenesisConfig::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let genesis = genesis.unwrap();
+
+        assert_eq!((genesis.hashes_per_tick, genesis.ticks_per_slot), (DEFAULT_HASHES_PER_TICK, 4));
+        let db = genesis.create_db(AccountHashAlgorithm::default());
+        let plain = db.load(&plain).unwrap();
+        assert_eq!((plain.lamports(), plain.owner(), plain.data(), plain.executable()), (10, &SYSTEM_PROGRAM_ID, &[][..], false));
+        let program = db.load(&program).unwrap();
+        assert_eq!((program.lamports(), program.owner(), program.data(), program.executable()), (20, &owner, &[1, 2, 3][..], true));
+    }
+
+    #[test]
+    fn malformed_genesis_names_what_is_wrong() {
+        let pubkey = Pubkey([1; 32]).to_base58();
+        let field  = |value: Value| match GenesisConfig::from_json(&value) {
+            Err(GenesisError::InvalidField(field)) => field,
+            other => panic!("{value} gave {other:?}"),
+        };
+        assert_eq!(field(json!({})), "accounts");
+        assert_eq!(field(json!({ "accounts": [{ "pubkey": pubkey }] })), "accounts[0].lamports");
+        assert_eq!(field(json!({ "accounts": [{ "pubkey": "0OIl", "lamports": 1 }] })), "accounts[0].pubkey");
+        assert_eq!(field(json!({ "accounts": [{ "pubkey": pubkey, "lamports": 1, "data": "!" }] })), "accounts[0].data");
+        assert_eq!(field(json!({ "hashes_per_tick": "many", "accounts": [] })), "hashes_per_tick");
+
+        assert!(matches!(
+            GenesisConfig::from_json(&json!({ "ticks_per_slot": 0, "accounts": [] })),
+            Err(GenesisError::InvalidTicksPerSlot(0))
+        ));
+        assert!(matches!(
+            GenesisConfig::from_json(&json!({ "hashes_per_tick": 0, "accounts": [] })),
+            Err(GenesisError::InvalidHashesPerTick(0))
+        ));
+    }
+}
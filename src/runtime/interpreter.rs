@@ -0,0 +1,320 @@
+// ---------------------------------------------------------------------------
+// Interpreter — runs on-chain programs written in a tiny bytecode.
+//
+// Real Solana programs are sBPF, executed by an EbpfVm. This is a stand-in
+// with just enough to write useful programs: a program is an executable
+// account, deployed by programs::loader, whose data is bytecode for a
+// stack machine over u64 values. It
+// sees the accounts and data of the instruction that invoked it, like any
+// program, and can read and write their lamports and data, log, and invoke
+// a native program.
+//
+// Opcodes (operands follow the opcode byte; `acct` indexes the
+// instruction's accounts, `off` is a byte offset, u64s are little-endian):
+//
+//   0x00 RETURN                 stop; the instruction succeeds
+//   0x01 PUSH      <u8>         push a small constant
+//   0x02 PUSH64    <u64>        push a full-width constant
+//   0x03 LOAD_LAMPORTS  <acct>  push the account's lamports
+//   0x04 STORE_LAMPORTS <acct>  pop into the account's lamports
+//   0x05 LOAD_DATA  <acct> <off>  push the u64 at data[off..off+8]
+//   0x06 STORE_DATA <acct> <off>  pop into data[off..off+8]
+//   0x07 LOAD_INPUT <off>       push the u64 at instruction data[off..off+8]
+//   0x08 ADD                    pop b, pop a, push a + b
+//   0x09 SUB                    pop b, pop a, push a - b
+//   0x0a DUP                    push a copy of the top value
+//   0x0b LOG                    pop a value and log it
+//   0x0c ABORT     <u8>         stop; the instruction fails with that code
+//   0x0d INVOKE <len:u16> <call>  invoke a native program (see below)
+//
+// INVOKE's call is `len` bytes laid out as
+//
+//   program id (32) | n (u8) | n × [acct (u8), flags (u8: 1 signer, 2 writable)]
+//   | data len (u16) | data | seed count (u8) | seed count × [len (u8), seed]
+//
+// and runs through svm::invoke_signed, with the program signing for the
+// PDA the seeds derive under its own id (or for none, with no seeds). The
+// call is part of the bytecode, so only the program's author decides
+// which of its PDAs it signs for.
+//
+// There are no jumps, so every program terminates within its own length
+// and needs no compute meter. Running off the end is the same as RETURN.
+//
+// The same rules the runtime applies to every program hold here: lamports
+// may only leave accounts the program owns and may not be created or
+// destroyed (svm checks both after the program returns). Data may only be
+// written in accounts the program owns, which STORE_DATA checks itself.
+//
+// Reference: https://github.com/anza-xyz/sbpf
+// ---------------------------------------------------------------------------
+
+use crate::runtime::log_collector::LogCollector;
+use crate::runtime::svm::{self, InvokeContext, InvokeError};
+use crate::types::account::{AccountSharedData, Pubkey};
+use crate::types::transaction::{AccountMeta, Instruction};
+
+pub const OP_RETURN: u8         = 0x00;
+pub const OP_PUSH: u8           = 0x01;
+pub const OP_PUSH64: u8         = 0x02;
+pub const OP_LOAD_LAMPORTS: u8  = 0x03;
+pub const OP_STORE_LAMPORTS: u8 = 0x04;
+pub const OP_LOAD_DATA: u8      = 0x05;
+pub const OP_STORE_DATA: u8     = 0x06;
+pub const OP_LOAD_INPUT: u8     = 0x07;
+pub const OP_ADD: u8            = 0x08;
+pub const OP_SUB: u8            = 0x09;
+pub const OP_DUP: u8            = 0x0a;
+pub const OP_LOG: u8            = 0x0b;
+pub const OP_ABORT: u8          = 0x0c;
+pub const OP_INVOKE: u8         = 0x0d;
+
+// Values the stack may hold at once.
+pub const MAX_STACK_DEPTH: usize = 64;
+
+// ---------------------------------------------------------------------------
+// InterpreterError — why a program failed. `pc` is the offset of the
+// opcode that failed within the program.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum InterpreterError {
+    /// The byte at `pc` is not an opcode.
+    InvalidOpcode { pc: usize, opcode: u8 },
+    /// The program ends in the middle of an opcode's operands.
+    TruncatedInstruction { pc: usize },
+    /// An opcode needed more values than the stack holds.
+    StackUnderflow { pc: usize },
+    /// A push would take the stack past MAX_STACK_DEPTH.
+    StackOverflow { pc: usize },
+    /// The instruction has no account at this index.
+    AccountIndexOutOfBounds { pc: usize, index: u8 },
+    /// An account's data is too short for an 8-byte read or write at
+    /// this offset.
+    DataOutOfBounds { pc: usize, offset: u8 },
+    /// The instruction data is too short for an 8-byte read at this offset.
+    InputOutOfBounds { pc: usize, offset: u8 },
+    /// STORE_DATA into an account the program does not own.
+    AccountNotOwned { pc: usize, index: u8 },
+    /// ADD or SUB overflowed a u64.
+    ArithmeticOverflow { pc: usize },
+    /// The program executed ABORT with this code.
+    Aborted(u8),
+    /// INVOKE's call does not decode.
+    InvalidInvoke { pc: usize },
+    /// The program INVOKE called failed, or the call was refused.
+    Invoke { pc: usize, error: InvokeError },
+}
+
+// ---------------------------------------------------------------------------
+// run — execute `program` on behalf of `program_id`.
+//
+// `accounts` and `input` are the invoking instruction's accounts and data;
+// changes to the accounts are made in place, and only kept by the caller
+// if the program succeeds. `context` is what INVOKE passes on to
+// svm::invoke_signed. LOG writes "Program log: <value>" to `logs`.
+// ---------------------------------------------------------------------------
+pub fn run(
+    program: &[u8],
+    program_id: &Pubkey,
+    accounts: &mut [AccountSharedData],
+    input: &[u8],
+    context: &mut InvokeContext,
+    logs: &mut LogCollector,
+) -> Result<(), InterpreterError> {
+    let mut stack: Vec<u64> = Vec::with_capacity(MAX_STACK_DEPTH);
+    let mut pc = 0;
+
+    while pc < program.len() {
+        let opcode = program[pc];
+        let operands = operand_len(program, pc).ok_or(InterpreterError::InvalidOpcode { pc, opcode })?;
+        let args = program
+            .get(pc + 1..pc + 1 + operands)
+            .ok_or(InterpreterError::TruncatedInstruction { pc })?;
+
+        let pop = |stack: &mut Vec<u64>| stack.pop().ok_or(InterpreterError::StackUnderflow { pc });
+        let push = |stack: &mut Vec<u64>, value| {
+            if stack.len() == MAX_STACK_DEPTH {
+                return Err(InterpreterError::StackOverflow { pc });
+            }
+            stack.push(value);
+            Ok(())
+        };
+
+        match opcode {
+            OP_RETURN => return Ok(()),
+            OP_PUSH => push(&mut stack, args[0] as u64)?,
+            OP_PUSH64 => push(&mut stack, u64::from_le_bytes(args.try_into().unwrap()))?,
+            OP_LOAD_LAMPORTS | OP_STORE_LAMPORTS | OP_LOAD_DATA | OP_STORE_DATA
+                if args[0] as usize >= accounts.len() =>
+            {
+                return Err(InterpreterError::AccountIndexOutOfBounds { pc, index: args[0] });
+            }
+            OP_LOAD_LAMPORTS => push(&mut stack, accounts[args[0] as usize].lamports())?,
+            OP_STORE_LAMPORTS => {
+                let value = pop(&mut stack)?;
+                accounts[args[0] as usize].set_lamports(value);
+            }
+            OP_LOAD_DATA => {
+                let (index, offset) = (args[0], args[1]);
+                let bytes = read_u64(accounts[index as usize].data(), offset)
+                    .ok_or(InterpreterError::DataOutOfBounds { pc, offset })?;
+                push(&mut stack, bytes)?;
+            }
+            OP_STORE_DATA => {
+                let (index, offset) = (args[0], args[1]);
+                let value = pop(&mut stack)?;
+                let account = &mut accounts[index as usize];
+                if account.owner() != program_id {
+                    return Err(InterpreterError::AccountNotOwned { pc, index });
+                }
+                let slot = account
+                    .data_mut()
+                    .get_mut(offset as usize..offset as usize + 8)
+                    .ok_or(InterpreterError::DataOutOfBounds { pc, offset })?;
+                slot.copy_from_slice(&value.to_le_bytes());
+            }
+            OP_LOAD_INPUT => {
+                let offset = args[0];
+                let value = read_u64(input, offset).ok_or(InterpreterError::InputOutOfBounds { pc, offset })?;
+                push(&mut stack, value)?;
+            }
+            OP_ADD | OP_SUB => {
+                let b = pop(&mut stack)?;
+                let a = pop(&mut stack)?;
+                let result = if opcode == OP_ADD { a.checked_add(b) } else { a.checked_sub(b) };
+                push(&mut stack, result.ok_or(InterpreterError::ArithmeticOverflow { pc })?)?;
+            }
+            OP_DUP => {
+                let top = *stack.last().ok_or(InterpreterError::StackUnderflow { pc })?;
+                push(&mut stack, top)?;
+            }
+            OP_LOG => {
+                let value = pop(&mut stack)?;
+                logs.log(&format!("Program log: {}", value));
+            }
+            OP_ABORT => return Err(InterpreterError::Aborted(args[0])),
+            OP_INVOKE => {
+                let call = decode_call(&args[2..]).ok_or(InterpreterError::InvalidInvoke { pc })?;
+                let mut metas = Vec::with_capacity(call.accounts.len());
+                for &(index, is_signer, is_writable) in &call.accounts {
+                    let pubkey = *context
+                        .key(index as usize)
+                        .ok_or(InterpreterError::AccountIndexOutOfBounds { pc, index })?;
+                    metas.push(AccountMeta { pubkey, is_signer, is_writable });
+                }
+                let instruction = Instruction { program_id: call.program_id, accounts: metas, data: call.data.to_vec() };
+                let signers_seeds: &[&[&[u8]]] = if call.seeds.is_empty() { &[] } else { &[&call.seeds] };
+                svm::invoke_signed(&instruction, accounts, context, signers_seeds, program_id, logs)
+                    .map_err(|error| InterpreterError::Invoke { pc, error })?;
+            }
+            _ => unreachable!("operand_len accepted an unknown opcode"),
+        }
+
+        pc += 1 + operands;
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// verify — check that `program` decodes.
+//
+// Every byte at an opcode position must be an opcode, with all of its
+// operands present. The loader runs this before deploying, so a deployed
+// program cannot fail with InvalidOpcode or TruncatedInstruction.
+// ---------------------------------------------------------------------------
+pub fn verify(program: &[u8]) -> Result<(), InterpreterError> {
+    let mut pc = 0;
+    while pc < program.len() {
+        let opcode = program[pc];
+        let operands = operand_len(program, pc).ok_or(InterpreterError::InvalidOpcode { pc, opcode })?;
+        if pc + 1 + operands > program.len() {
+            return Err(InterpreterError::TruncatedInstruction { pc });
+        }
+        if opcode == OP_INVOKE && decode_call(&program[pc + 3..pc + 1 + operands]).is_none() {
+            return Err(InterpreterError::InvalidInvoke { pc });
+        }
+        pc += 1 + operands;
+    }
+    Ok(())
+}
+
+// Bytes of operands that follow the opcode at `pc`. None if it is not an
+// opcode. INVOKE's are its two length bytes and the call they announce;
+// just the two if the length itself is cut off.
+fn operand_len(program: &[u8], pc: usize) -> Option<usize> {
+    match program[pc] {
+        OP_RETURN | OP_ADD | OP_SUB | OP_DUP | OP_LOG => Some(0),
+        OP_PUSH | OP_LOAD_LAMPORTS | OP_STORE_LAMPORTS | OP_LOAD_INPUT | OP_ABORT => Some(1),
+        OP_LOAD_DATA | OP_STORE_DATA => Some(2),
+        OP_PUSH64 => Some(8),
+        OP_INVOKE => {
+            let len = program.get(pc + 1..pc + 3).map_or(0, |len| u16::from_le_bytes([len[0], len[1]]) as usize);
+            Some(2 + len)
+        }
+        _ => None,
+    }
+}
+
+// An INVOKE call, decoded. Accounts are (acct, is_signer, is_writable).
+struct Call<'a> {
+    program_id: Pubkey,
+    accounts:   Vec<(u8, bool, bool)>,
+    data:       &'a [u8],
+    seeds:      Vec<&'a [u8]>,
+}
+
+// Decode an INVOKE call (layout in the header). None if it is cut short,
+// has bytes left over, or sets an unknown account flag.
+fn decode_call<'a>(bytes: &'a [u8]) -> Option<Call<'a>> {
+    let mut rest = bytes;
+    let mut take = |n: usize| -> Option<&'a [u8]> {
+        let (head, tail) = rest.split_at_checked(n)?;
+        rest = tail;
+        Some(head)
+    };
+
+    let program_id = Pubkey(take(32)?.try_into().unwrap());
+    let count = take(1)?[0];
+    let mut accounts = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let &[index, flags] = take(2)? else { unreachable!() };
+        if flags & !3 != 0 {
+            return None;
+        }
+        accounts.push((index, flags & 1 != 0, flags & 2 != 0));
+    }
+    let data_len = take(2)?;
+    let data = take(u16::from_le_bytes([data_len[0], data_len[1]]) as usize)?;
+    let seed_count = take(1)?[0];
+    let mut seeds = Vec::with_capacity(seed_count as usize);
+    for _ in 0..seed_count {
+        let len = take(1)?[0];
+        seeds.push(take(len as usize)?);
+    }
+
+    rest.is_empty().then_some(Call { program_id, accounts, data, seeds })
+}
+
+// The little-endian u64 at bytes[offset..offset+8], if it fits.
+fn read_u64(bytes: &[u8], offset: u8) -> Option<u64> {
+    let slice = bytes.get(offset as usize..offset as usize + 8)?;
+    Some(u64::from_le_bytes(slice.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_accepts_whole_programs_and_reports_where_decoding_stops() {
+        assert_eq!(verify(&[]), Ok(()));
+        assert_eq!(verify(&[OP_PUSH, 1, OP_DUP, OP_ADD, OP_LOG, OP_PUSH64, 1, 0, 0, 0, 0, 0, 0, 0, OP_RETURN]), Ok(()));
+
+        assert_eq!(verify(&[OP_PUSH, 1, 0xff]), Err(InterpreterError::InvalidOpcode { pc: 2, opcode: 0xff }));
+        assert_eq!(verify(&[OP_PUSH, 1, OP_LOAD_DATA, 0]), Err(InterpreterError::TruncatedInstruction { pc: 2 }));
+        assert_eq!(verify(&[OP_PUSH64, 1, 2]), Err(InterpreterError::TruncatedInstruction { pc: 0 }));
+        assert_eq!(verify(&[OP_INVOKE, 5]), Err(InterpreterError::TruncatedInstruction { pc: 0 }));
+        // A one-byte call is far too short to name a program.
+        assert_eq!(verify(&[OP_INVOKE, 1, 0, 0]), Err(InterpreterError::InvalidInvoke { pc: 0 }));
+    }
+}
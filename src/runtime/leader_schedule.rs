@@ -0,0 +1,63 @@
+// ---------------------------------------------------------------------------
+// Leader schedule — which validator produces each slot.
+//
+// Solana does not elect a block producer per slot. Each epoch, every node
+// derives the same schedule from stake and walks it: a leader gets a run
+// of consecutive slots (4 on mainnet) and then hands over to the next.
+// Anyone can ask "who leads slot N?" without talking to anyone else.
+//
+// Here the schedule is a plain rotation through a fixed validator set —
+// no stake weighting, no per-epoch shuffle. That is enough for a node to
+// know whose turn it is once there is more than one of them.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/ledger/src/leader_schedule.rs
+// ---------------------------------------------------------------------------
+
+use crate::types::account::{Pubkey, Slot};
+
+// How many consecutive slots a leader gets before the next one takes over.
+// Matches NUM_CONSECUTIVE_LEADER_SLOTS in real Solana.
+pub const NUM_CONSECUTIVE_LEADER_SLOTS: u64 = 4;
+
+// ---------------------------------------------------------------------------
+// LeaderScheduleError — why a schedule could not be built.
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum LeaderScheduleError {
+    /// A schedule needs at least one validator.
+    NoLeaders,
+    /// Every leader must get at least one slot.
+    InvalidSlotsPerLeader(u64),
+}
+
+// ---------------------------------------------------------------------------
+// LeaderSchedule — a rotation through the validator set.
+//
+// Slots 0..slots_per_leader belong to leaders[0], the next slots_per_leader
+// to leaders[1], and so on, wrapping back to leaders[0] after the last.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug)]
+pub struct LeaderSchedule {
+    pub slots_per_leader: u64,
+    pub leaders:          Vec<Pubkey>,
+}
+
+impl LeaderSchedule {
+    /// Build a schedule that gives each of `leaders`, in order,
+    /// `slots_per_leader` consecutive slots.
+    pub fn new(slots_per_leader: u64, leaders: Vec<Pubkey>) -> Result<Self, LeaderScheduleError> {
+        if leaders.is_empty() {
+            return Err(LeaderScheduleError::NoLeaders);
+        }
+        if slots_per_leader == 0 {
+            return Err(LeaderScheduleError::InvalidSlotsPerLeader(slots_per_leader));
+        }
+        Ok(LeaderSchedule { slots_per_leader, leaders })
+    }
+
+    /// The validator that leads `slot`.
+    pub fn leader_at(&self, slot: Slot) -> &Pubkey {
+        let turn = slot / self.slots_per_leader;
+        &self.leaders[(turn % self.leaders.len() as u64) as usize]
+    }
+}
@@ -0,0 +1,105 @@
+// ---------------------------------------------------------------------------
+// LogCollector — the program log of one transaction, capped in size.
+//
+// Every line a transaction's programs log is kept, until the lines written
+// so far add up to the byte budget. Past that, further lines are dropped
+// and a single "Log truncated" line marks the cut, so a program that logs
+// in a loop cannot make the node hold an unbounded log. Real Solana caps
+// the log at 10,000 bytes per transaction the same way.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/log-collector/src/lib.rs
+// ---------------------------------------------------------------------------
+
+// Log bytes a transaction may write before the rest is truncated.
+pub const DEFAULT_LOG_MESSAGES_BYTES_LIMIT: usize = 10 * 1000;
+
+// The line that replaces everything past the budget.
+pub const LOG_TRUNCATED: &str = "Log truncated";
+
+// ---------------------------------------------------------------------------
+// LogCollector
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct LogCollector {
+    messages:      Vec<String>,
+    bytes_written: usize,
+    bytes_limit:   usize,
+    truncated:     bool,
+}
+
+impl Default for LogCollector {
+    fn default() -> Self {
+        LogCollector::with_limit(DEFAULT_LOG_MESSAGES_BYTES_LIMIT)
+    }
+}
+
+impl LogCollector {
+    /// An empty log with the default 10,000-byte budget.
+    pub fn new() -> Self {
+        LogCollector::default()
+    }
+
+    /// An empty log that keeps at most `bytes_limit` bytes of messages.
+    pub fn with_limit(bytes_limit: usize) -> Self {
+        LogCollector { messages: Vec::new(), bytes_written: 0, bytes_limit, truncated: false }
+    }
+
+    /// Append one line, or drop it if it would take the log past its
+    /// budget. The first dropped line is replaced by LOG_TRUNCATED; once
+    /// truncated, nothing more is kept.
+    pub fn log(&mut self, message: &str) {
+        if self.truncated {
+            return;
+        }
+        let bytes_written = self.bytes_written.saturating_add(message.len());
+        if bytes_written > self.bytes_limit {
+            self.truncated = true;
+            self.messages.push(LOG_TRUNCATED.to_string());
+        } else {
+            self.bytes_written = bytes_written;
+            self.messages.push(message.to_string());
+        }
+    }
+
+    /// Whether any line has been dropped.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// The lines kept, in order, ending with LOG_TRUNCATED if truncated.
+    pub fn into_messages(self) -> Vec<String> {
+        self.messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_is_cut_at_the_first_line_past_the_budget() {
+        let mut logs = LogCollector::with_limit(10);
+        logs.log("12345");
+        logs.log("67890");
+        assert!(!logs.is_truncated());
+
+        // Exactly at the budget so far; the next line, however short, and
+        // everything after it is dropped.
+        logs.log("x");
+        logs.log("");
+        assert!(logs.is_truncated());
+        assert_eq!(logs.into_messages(), vec!["12345", "67890", LOG_TRUNCATED]);
+    }
+
+    #[test]
+    fn default_budget_is_solanas() {
+        let mut logs = LogCollector::new();
+        let line     = "a".repeat(1_000);
+        for _ in 0..11 {
+            logs.log(&line);
+        }
+        let messages = logs.into_messages();
+        assert_eq!(messages.len(), 11);
+        assert_eq!(messages[10], LOG_TRUNCATED);
+    }
+}
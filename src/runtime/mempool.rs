@@ -0,0 +1,185 @@
+// ---------------------------------------------------------------------------
+// Mempool — transactions waiting for the leader to pack them.
+//
+// A leader does not execute transactions the moment they arrive. They wait
+// in a queue, and each slot the leader takes the ones paying the most and
+// records them into PoH. The mempool is that queue: push() adds a
+// transaction with the fee it pays, drain_for_slot() takes the
+// highest-paying ones, up to a batch size. Among equal fees, the one pushed
+// first comes out first.
+//
+// The queue holds at most `capacity` transactions, so flooding it cannot
+// exhaust the node's memory. When it is full, a new transaction takes the
+// place of the one that would drain last — the lowest fee, and among
+// those the newest — if it pays more; otherwise it is refused.
+//
+// Nothing is checked here: the caller decides what may be queued, and the
+// drained transactions still go through the full pipeline, so one whose
+// blockhash expired while it waited is rejected then.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/core/src/banking_stage/transaction_scheduler/transaction_state_container.rs
+// ---------------------------------------------------------------------------
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeSet;
+
+use crate::types::transaction::Transaction;
+
+// Most queued transactions the node processes per slot.
+pub const MAX_MEMPOOL_TXS_PER_SLOT: usize = 64;
+
+// Most transactions Mempool::new() holds at once.
+pub const MAX_MEMPOOL_LEN: usize = 10_000;
+
+// ---------------------------------------------------------------------------
+// MempoolFull — a transaction was refused because the mempool is full and
+// it pays no more than the cheapest one queued.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MempoolFull {
+    /// The lowest queued fee; a transaction must pay more to get in.
+    pub min_fee: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Pending — a queued transaction, ordered by fee, then by arrival.
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+struct Pending {
+    fee: u64,
+    seq: Reverse<u64>,
+    tx:  Transaction,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.fee, self.seq).cmp(&(other.fee, other.seq))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Mempool
+//
+// Pending orders by (fee, arrival reversed), so the set's last element is
+// the next to drain and its first the next to evict.
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+pub struct Mempool {
+    queue:    BTreeSet<Pending>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+impl Default for Mempool {
+    fn default() -> Self {
+        Mempool::with_capacity(MAX_MEMPOOL_LEN)
+    }
+}
+
+impl Mempool {
+    /// An empty mempool holding up to MAX_MEMPOOL_LEN transactions.
+    pub fn new() -> Self {
+        Mempool::default()
+    }
+
+    /// An empty mempool holding up to `capacity` transactions.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Mempool { queue: BTreeSet::new(), capacity, next_seq: 0 }
+    }
+
+    /// Queue `tx`, which pays `fee` lamports. If the mempool is full, `tx`
+    /// evicts the transaction that would drain last, which is returned, or
+    /// is refused if it pays no more than that one.
+    pub fn push(&mut self, tx: Transaction, fee: u64) -> Result<Option<Transaction>, MempoolFull> {
+        let evicted = match self.queue.first() {
+            Some(cheapest) if self.queue.len() >= self.capacity => {
+                if fee <= cheapest.fee {
+                    return Err(MempoolFull { min_fee: cheapest.fee });
+                }
+                self.queue.pop_first().map(|pending| pending.tx)
+            }
+            None if self.capacity == 0 => return Err(MempoolFull { min_fee: 0 }),
+            _ => None,
+        };
+        let seq = Reverse(self.next_seq);
+        self.next_seq += 1;
+        self.queue.insert(Pending { fee, seq, tx });
+        Ok(evicted)
+    }
+
+    /// Take up to `max` transactions, highest fee first. The rest stay
+    /// queued for a later slot.
+    pub fn drain_for_slot(&mut self, max: usize) -> Vec<Transaction> {
+        std::iter::from_fn(|| self.queue.pop_last())
+            .take(max)
+            .map(|pending| pending.tx)
+            .collect()
+    }
+
+    /// How many transactions are queued.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Whether no transaction is queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::Signature;
+
+    // A transaction told apart by its signature byte.
+    fn tx(id: u8) -> Transaction {
+        Transaction { signatures: vec![Signature([id; 64])], ..Default::default() }
+    }
+
+    fn ids(txs: &[Transaction]) -> Vec<u8> {
+        txs.iter().map(|tx| tx.signatures[0].0[0]).collect()
+    }
+
+    #[test]
+    fn highest_fee_drains_first_then_first_come_first_served() {
+        let mut mempool = Mempool::new();
+        for (id, fee) in [(1, 10), (2, 30), (3, 10), (4, 20), (5, 30)] {
+            assert_eq!(mempool.push(tx(id), fee), Ok(None));
+        }
+
+        assert_eq!(ids(&mempool.drain_for_slot(3)), vec![2, 5, 4]);
+        assert_eq!(mempool.len(), 2);
+        assert_eq!(ids(&mempool.drain_for_slot(10)), vec![1, 3]);
+        assert!(mempool.is_empty());
+    }
+
+    #[test]
+    fn full_mempool_evicts_the_last_to_drain_or_refuses() {
+        let mut mempool = Mempool::with_capacity(3);
+        for (id, fee) in [(1, 10), (2, 10), (3, 20)] {
+            assert_eq!(mempool.push(tx(id), fee), Ok(None));
+        }
+
+        // Paying no more than the cheapest is refused.
+        assert_eq!(mempool.push(tx(4), 10), Err(MempoolFull { min_fee: 10 }));
+        // Paying more evicts the newest of the cheapest.
+        assert_eq!(mempool.push(tx(5), 11), Ok(Some(tx(2))));
+        assert_eq!(mempool.len(), 3);
+        assert_eq!(ids(&mempool.drain_for_slot(10)), vec![3, 5, 1]);
+    }
+}
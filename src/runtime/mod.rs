@@ -1,5 +1,17 @@
+pub mod account_locks;
 pub mod accounts_db;
 pub mod bank;
+pub mod commitment;
+pub mod epoch_schedule;
+pub mod fee;
+pub mod genesis;
+pub mod interpreter;
+pub mod leader_schedule;
+pub mod log_collector;
+pub mod mempool;
 pub mod poh;
 pub mod rpc;
+pub mod snapshot;
 pub mod svm;
+pub mod transaction_error;
+pub mod transaction_index;
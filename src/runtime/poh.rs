@@ -14,27 +14,85 @@
 //   Record entry: { num_hashes: 1, hash: h, transactions: [tx, ...] }
 //
 // Hash mixing for record():
-//   tx_hash  = SHA-256( sig_0 || sig_1 || ... )   — hash of all signatures
+//   tx_hash  = SHA-256( tx_0 || tx_1 || ... )   — full wire-format bytes
 //   new_hash = SHA-256( current_hash || tx_hash )
 //
-//   Note: while the Bank layer (and real Ed25519 signing) is not yet built,
-//   signatures are empty. We fall back to hashing the serialized account
-//   keys as a stand-in so the chain stays deterministic and verifiable.
+//   Each tx_i is the whole serialized transaction, signatures and message,
+//   so an entry commits to everything in it — see hash_transactions.
+//
+// Every hash goes through a Hasher. The chain is always SHA-256, but how
+// it is computed is pluggable, so implementations can be compared on
+// throughput; PohGenerator and verify() default to the sha2 crate.
 //
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/entry/src/entry.rs
 // ---------------------------------------------------------------------------
 
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use rayon::prelude::*;
 use sha2::{Digest, Sha256};
-use crate::types::transaction::Transaction;
+use crate::types::transaction::{Transaction, TxDeserializeError};
+
+// Ticks per slot when the node doesn't say otherwise. Real Solana uses 64
+// (~400ms slots); our ticks are 500ms apart, so we use a much smaller number
+// to see slots advance.
+pub const DEFAULT_TICKS_PER_SLOT: u64 = 8;
+
+// Most transactions one record entry holds unless max_txs_per_entry is
+// changed. Matches the 64-transaction batches Solana's banking stage
+// records.
+pub const DEFAULT_MAX_TXS_PER_ENTRY: usize = 64;
+
+// ---------------------------------------------------------------------------
+// Hasher — the hash function behind the chain.
+//
+// One call per hash, stateless: the backend is chosen by type, so a
+// generator and the verify_with() that checks its entries agree on it
+// without passing anything around. A backend other than SHA-256 produces
+// a different chain, which the rest of the node will not accept.
+// ---------------------------------------------------------------------------
+pub trait Hasher {
+    /// The 32-byte digest of `data`.
+    fn hash32(data: &[u8]) -> [u8; 32];
+}
+
+// SHA-256 from the sha2 crate — the default backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn hash32(data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(data);
+        hasher.finalize().into()
+    }
+}
+
+// BLAKE3 from the blake3 crate. Faster than SHA-256, but not what Solana's
+// PoH uses; AccountsDB can hash accounts with it (see AccountHashAlgorithm).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake3Hasher;
+
+impl Hasher for Blake3Hasher {
+    fn hash32(data: &[u8]) -> [u8; 32] {
+        blake3::hash(data).into()
+    }
+}
 
 // ---------------------------------------------------------------------------
 // Entry — one record in the PoH ledger.
 //
-// Matches Solana's Entry struct exactly:
+// Matches Solana's Entry struct:
 //   num_hashes:   how many SHA-256 iterations since the previous entry
 //   hash:         the chain value at this entry
 //   transactions: the batch of transactions stamped at this point in time
 //                 empty for tick entries
+//
+// plus one bookkeeping flag, slot_boundary, marking the tick that completes
+// a slot. It is not part of the hash.
 // ---------------------------------------------------------------------------
 #[derive(Clone, Debug)]
 pub struct Entry {
@@ -49,12 +107,51 @@ pub struct Entry {
     /// Transactions stamped into this entry.
     /// Empty for tick entries. One or more for record entries.
     pub transactions: Vec<Transaction>,
+
+    /// True on the last tick of a slot, so a replayer can split the ledger
+    /// into slots without counting ticks itself. Always false on records.
+    pub slot_boundary: bool,
+}
+
+// ---------------------------------------------------------------------------
+// PohError
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub enum PohError {
+    /// A slot must contain at least one tick.
+    InvalidTicksPerSlot(u64),
+    /// A tick must contain at least one hash.
+    InvalidHashesPerTick(u64),
+    /// Entry number `index` does not follow from the one before it:
+    /// replaying the chain gives `expected`, the entry records `got`.
+    EntryMismatch { index: usize, expected: [u8; 32], got: [u8; 32] },
+    /// Entry number `index` claims num_hashes == 0, which no generator
+    /// produces: a tick is at least one hash and a record at least its
+    /// mixing hash.
+    InvalidEntry { index: usize },
+}
+
+// ---------------------------------------------------------------------------
+// LedgerError — why a ledger file could not be replayed.
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum LedgerError {
+    /// Reading the file failed.
+    Io(io::Error),
+    /// The generator settings are invalid.
+    Poh(PohError),
+    /// Entry number `entry` is cut short or malformed.
+    Corrupt { entry: usize },
+    /// A transaction in entry number `entry` does not deserialize.
+    InvalidTransaction { entry: usize, error: TxDeserializeError },
+    /// Entry number `entry` does not follow from the one before it.
+    InvalidEntry { entry: usize },
 }
 
 // ---------------------------------------------------------------------------
-// PohGenerator — the running hash chain.
+// PohGenerator — the running hash chain, hashed with H.
 // ---------------------------------------------------------------------------
-pub struct PohGenerator {
+pub struct PohGenerator<H: Hasher = Sha256Hasher> {
     /// The current (latest) hash in the chain.
     current_hash: [u8; 32],
 
@@ -68,35 +165,89 @@ pub struct PohGenerator {
     /// Real Solana: ~12,500 (calibrated to 6.25ms on validator hardware).
     /// We use a smaller number so output is human-readable.
     pub hashes_per_tick: u64,
+
+    /// How many ticks make up one slot. Always >= 1.
+    pub ticks_per_slot: u64,
+
+    /// Most transactions one record entry holds; record() splits larger
+    /// batches. 0 is treated as 1.
+    pub max_txs_per_entry: usize,
+
+    /// Total ticks produced since genesis.
+    tick_height: u64,
+
+    /// Oldest slot whose entries are still held in `entries`. Advanced by
+    /// purge_slots_before(); 0 until the ledger is first pruned.
+    first_available_slot: u64,
+
+    /// How many entries have been purged from the front of `entries`, so
+    /// entries[i] is ledger entry number purged_entries + i.
+    purged_entries: usize,
+
+    /// How many entries (ledger-wide) have been written by append_to_ledger().
+    persisted_entries: usize,
+
+    hasher: PhantomData<H>,
 }
 
 impl PohGenerator {
-    /// Create a new PoH chain starting from a seed (the genesis hash).
-    pub fn new(seed: &[u8], hashes_per_tick: u64) -> Self {
-        PohGenerator {
-            current_hash: sha256(seed),
+    /// Create a new PoH chain starting from a seed (the genesis hash),
+    /// hashed with SHA-256 from the sha2 crate.
+    ///
+    /// Fails if `ticks_per_slot` is 0 — a slot with no ticks would never
+    /// end — or if `hashes_per_tick` is 0, since a tick that hashes nothing
+    /// proves no time passed and verify() rejects it.
+    pub fn new(seed: &[u8], hashes_per_tick: u64, ticks_per_slot: u64) -> Result<Self, PohError> {
+        Self::with_hasher(seed, hashes_per_tick, ticks_per_slot)
+    }
+}
+
+impl<H: Hasher> PohGenerator<H> {
+    /// Like new(), but hashed with H:
+    /// `PohGenerator::<MyHasher>::with_hasher(seed, ...)`.
+    pub fn with_hasher(seed: &[u8], hashes_per_tick: u64, ticks_per_slot: u64) -> Result<Self, PohError> {
+        if ticks_per_slot == 0 {
+            return Err(PohError::InvalidTicksPerSlot(ticks_per_slot));
+        }
+        if hashes_per_tick == 0 {
+            return Err(PohError::InvalidHashesPerTick(hashes_per_tick));
+        }
+
+        Ok(PohGenerator {
+            current_hash: H::hash32(seed),
             num_hashes: 0,
             entries: vec![],
             hashes_per_tick,
-        }
+            ticks_per_slot,
+            max_txs_per_entry: DEFAULT_MAX_TXS_PER_ENTRY,
+            tick_height: 0,
+            first_available_slot: 0,
+            purged_entries: 0,
+            persisted_entries: 0,
+            hasher: PhantomData,
+        })
     }
 
     // -----------------------------------------------------------------------
     // tick — advance the chain by one full tick (hashes_per_tick hashes).
     //
     // Produces a tick entry with no transactions. This is how PoH proves
-    // that time passed even when no transactions arrived.
+    // that time passed even when no transactions arrived. Every
+    // ticks_per_slot-th tick is flagged as the slot boundary.
     // -----------------------------------------------------------------------
     pub fn tick(&mut self) {
         for _ in 0..self.hashes_per_tick {
-            self.current_hash = sha256(&self.current_hash);
+            self.current_hash = H::hash32(&self.current_hash);
             self.num_hashes += 1;
         }
 
+        self.tick_height += 1;
+
         self.entries.push(Entry {
             num_hashes: self.num_hashes,
             hash: self.current_hash,
             transactions: vec![],
+            slot_boundary: self.tick_height.is_multiple_of(self.ticks_per_slot),
         });
 
         // Reset counter — num_hashes in each entry is relative to the
@@ -107,27 +258,49 @@ impl PohGenerator {
     // -----------------------------------------------------------------------
     // record — stamp a batch of transactions into the chain.
     //
+    // The batch is split, in order, into chunks of at most
+    // max_txs_per_entry transactions, and each chunk becomes one record
+    // entry, as Solana packs transactions into entries. An empty batch
+    // records nothing. For each chunk:
+    //
     // Computes one additional hash mixing in the transaction data, then
     // records an entry containing the transactions.
     //
-    // Hash mixing (Solana spec):
-    //   tx_hash  = SHA-256( all_signature_bytes_concatenated )
+    // Hash mixing (see hash_transactions):
+    //   tx_hash  = SHA-256( all_transaction_bytes_concatenated )
     //   new_hash = SHA-256( current_hash || tx_hash )
+    //
+    // The entry's num_hashes counts only the hashes since the previous
+    // entry. Both tick() and record() reset the counter, and nothing else
+    // advances the chain, so a record entry always carries exactly the one
+    // mixing hash: { num_hashes: 1, ... }.
     // -----------------------------------------------------------------------
     pub fn record(&mut self, transactions: Vec<Transaction>) {
-        let tx_hash = hash_transactions(&transactions);
+        let max = self.max_txs_per_entry.max(1);
+        let mut transactions = transactions.into_iter().peekable();
+        while transactions.peek().is_some() {
+            let chunk: Vec<Transaction> = transactions.by_ref().take(max).collect();
+            self.record_entry(chunk);
+        }
+    }
+
+    // One record entry holding `transactions`.
+    fn record_entry(&mut self, transactions: Vec<Transaction>) {
+        let tx_hash = hash_transactions::<H>(&transactions);
 
         let mut input = Vec::with_capacity(64);
         input.extend_from_slice(&self.current_hash);
         input.extend_from_slice(&tx_hash);
-        self.current_hash = sha256(&input);
+        self.current_hash = H::hash32(&input);
         self.num_hashes += 1;
 
         let num_hashes = self.num_hashes;
+        debug_assert_eq!(num_hashes, 1, "record entry must carry only its mixing hash");
         self.entries.push(Entry {
             num_hashes,
             hash: self.current_hash,
             transactions,
+            slot_boundary: false,
         });
 
         self.num_hashes = 0;
@@ -138,71 +311,509 @@ impl PohGenerator {
     pub fn last_hash(&self) -> [u8; 32] {
         self.current_hash
     }
+
+    /// Total ticks produced since genesis.
+    pub fn tick_height(&self) -> u64 {
+        self.tick_height
+    }
+
+    /// The slot the chain is currently in: tick_height / ticks_per_slot.
+    /// Starts at 0 and advances on the tick flagged as the slot boundary.
+    pub fn slot(&self) -> u64 {
+        self.tick_height / self.ticks_per_slot
+    }
+
+    /// How many slots have had at least one entry: every slot before the
+    /// current one (each has its ticks), plus the current slot once
+    /// anything has been recorded or ticked in it. 0 at genesis.
+    pub fn block_height(&self) -> u64 {
+        let current_started = self.entries.last().is_some_and(|entry| !entry.slot_boundary);
+        self.slot() + current_started as u64
+    }
+
+    /// The oldest slot whose entries are still available.
+    pub fn first_available_slot(&self) -> u64 {
+        self.first_available_slot
+    }
+
+    /// The ledger-wide number of entries[0] — 0 until entries are purged.
+    pub fn first_entry_index(&self) -> usize {
+        self.purged_entries
+    }
+
+    // -----------------------------------------------------------------------
+    // purge_slots_before — drop the entries of every slot older than `slot`.
+    //
+    // A slot's entries run up to and including its boundary tick, so
+    // dropping N slots means cutting just past the N-th boundary. The
+    // current slot is never purged, however large `slot` is. Returns how
+    // many entries were dropped.
+    //
+    // Once purged, verify() can no longer replay these entries from the
+    // seed; the chain still continues from last_hash() as before.
+    // -----------------------------------------------------------------------
+    pub fn purge_slots_before(&mut self, slot: u64) -> usize {
+        let slot = slot.min(self.slot());
+        if slot <= self.first_available_slot {
+            return 0;
+        }
+
+        let slots_to_drop = slot - self.first_available_slot;
+        let cut = match self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.slot_boundary)
+            .nth((slots_to_drop - 1) as usize)
+        {
+            Some((i, _)) => i + 1,
+            None         => return 0,
+        };
+
+        self.entries.drain(..cut);
+        self.purged_entries       += cut;
+        self.first_available_slot  = slot;
+        cut
+    }
 }
 
 // ---------------------------------------------------------------------------
-// hash_transactions — compute the hash mixed into PoH for a tx batch.
+// Ledger file — the entry stream on disk, for crash recovery.
+//
+// One record per entry, appended in order:
+//   [record_len: u32 LE]                  — bytes that follow
+//   [num_hashes: u64 LE]
+//   [hash:       32 bytes]
+//   [num_txs:    u32 LE]
+//   per transaction: [tx_len: u32 LE] [tx: wire format]
 //
-// Real Solana hashes all signature bytes concatenated.
-// While the Bank / Ed25519 signing layer is not yet built, transactions
-// carry no real signatures, so we hash the account_keys bytes instead
-// as a deterministic stand-in.
+// slot_boundary is not stored; replay recomputes it by counting ticks.
 // ---------------------------------------------------------------------------
-fn hash_transactions(transactions: &[Transaction]) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    for tx in transactions {
-        if !tx.signatures.is_empty() {
-            // Real path: hash signature bytes.
-            for sig in &tx.signatures {
-                hasher.update(sig.0);
-            }
-        } else {
-            // Stand-in path (no Bank yet): hash account key bytes.
-            for key in &tx.message.account_keys {
-                hasher.update(key.0);
+impl<H: Hasher> PohGenerator<H> {
+    /// Append every entry produced since the last call to the ledger file
+    /// at `path`, creating it if needed.
+    ///
+    /// Call it after each tick() or record(). Entries purged before they
+    /// were appended are lost, so call it before purge_slots_before().
+    pub fn append_to_ledger(&mut self, path: &Path) -> io::Result<()> {
+        let end   = self.purged_entries + self.entries.len();
+        let start = self.persisted_entries.max(self.purged_entries);
+        if start == end {
+            return Ok(());
+        }
+
+        let mut buf = Vec::new();
+        for entry in &self.entries[start - self.purged_entries..] {
+            let mut record = Vec::new();
+            record.extend_from_slice(&entry.num_hashes.to_le_bytes());
+            record.extend_from_slice(&entry.hash);
+            record.extend_from_slice(&(entry.transactions.len() as u32).to_le_bytes());
+            for tx in &entry.transactions {
+                let bytes = tx.serialize();
+                record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                record.extend_from_slice(&bytes);
             }
+            buf.extend_from_slice(&(record.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&record);
+        }
+
+        OpenOptions::new().create(true).append(true).open(path)?.write_all(&buf)?;
+        self.persisted_entries = end;
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// replay_ledger — rebuild a PohGenerator from a ledger file.
+//
+// Every entry is checked against the one before it (starting from the
+// seed) as it is loaded, so a tampered or mismatched ledger is refused
+// rather than continued. The returned generator carries on from the last
+// entry, with tick height and slot boundaries recomputed, and appends new
+// entries after the ones already in the file.
+// ---------------------------------------------------------------------------
+pub fn replay_ledger(
+    seed: &[u8],
+    path: &Path,
+    hashes_per_tick: u64,
+    ticks_per_slot: u64,
+) -> Result<PohGenerator, LedgerError> {
+    let mut poh   = PohGenerator::new(seed, hashes_per_tick, ticks_per_slot).map_err(LedgerError::Poh)?;
+    let bytes     = std::fs::read(path).map_err(LedgerError::Io)?;
+    let mut rest  = &bytes[..];
+    let mut index = 0;
+
+    while !rest.is_empty() {
+        let corrupt = LedgerError::Corrupt { entry: index };
+        let (record, tail) = take_len_prefixed(rest).ok_or(corrupt)?;
+        rest = tail;
+
+        let mut entry = read_entry(record, index)?;
+        if next_hash::<Sha256Hasher>(&poh.current_hash, &entry) != Some(entry.hash) {
+            return Err(LedgerError::InvalidEntry { entry: index });
         }
+
+        if entry.transactions.is_empty() {
+            poh.tick_height += 1;
+            entry.slot_boundary = poh.tick_height.is_multiple_of(poh.ticks_per_slot);
+        }
+        poh.current_hash = entry.hash;
+        poh.entries.push(entry);
+        index += 1;
+    }
+
+    poh.persisted_entries = index;
+    Ok(poh)
+}
+
+// Parse one ledger record (everything after its length prefix).
+fn read_entry(record: &[u8], index: usize) -> Result<Entry, LedgerError> {
+    let corrupt = || LedgerError::Corrupt { entry: index };
+
+    let num_hashes = u64::from_le_bytes(record.get(0..8).ok_or_else(corrupt)?.try_into().unwrap());
+    let hash: [u8; 32] = record.get(8..40).ok_or_else(corrupt)?.try_into().unwrap();
+    let num_txs = u32::from_le_bytes(record.get(40..44).ok_or_else(corrupt)?.try_into().unwrap());
+
+    let mut rest = &record[44..];
+    let mut transactions = Vec::new();
+    for _ in 0..num_txs {
+        let (tx_bytes, tail) = take_len_prefixed(rest).ok_or_else(corrupt)?;
+        rest = tail;
+        let tx = Transaction::deserialize(tx_bytes)
+            .map_err(|error| LedgerError::InvalidTransaction { entry: index, error })?;
+        transactions.push(tx);
+    }
+    if !rest.is_empty() {
+        return Err(corrupt());
+    }
+
+    Ok(Entry { num_hashes, hash, transactions, slot_boundary: false })
+}
+
+// Split a [len: u32 LE][len bytes] prefix off `bytes`.
+fn take_len_prefixed(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len  = u32::from_le_bytes(bytes.get(0..4)?.try_into().unwrap()) as usize;
+    let body = bytes.get(4..4 + len)?;
+    Some((body, &bytes[4 + len..]))
+}
+
+// ---------------------------------------------------------------------------
+// hash_transactions — compute the hash mixed into PoH for a tx batch.
+//
+// Real Solana mixes in a hash of the signatures only. That leaves the
+// message out of the chain: an entry whose instruction data was swapped
+// after signing would still verify, and only a signature check would
+// catch it. Hashing each transaction's wire format (Transaction::serialize)
+// instead makes the entry commit to the message as well. The wire format
+// is self-delimiting, so concatenating the transactions is unambiguous.
+//
+// An unsigned transaction, recorded by hand, needs no special case: its
+// message bytes alone still make the hash deterministic.
+// ---------------------------------------------------------------------------
+fn hash_transactions<H: Hasher>(transactions: &[Transaction]) -> [u8; 32] {
+    let mut bytes = Vec::new();
+    for tx in transactions {
+        bytes.extend_from_slice(&tx.serialize());
     }
-    hasher.finalize().into()
+    H::hash32(&bytes)
 }
 
 // ---------------------------------------------------------------------------
 // verify — replay the chain from a seed and confirm every entry is valid.
 //
-// This can be parallelised in real Solana (each segment between entries
-// is independent once you know the start hash). We keep it sequential.
+// Every entry must count at least one hash: a tick that hashed nothing
+// would leave the chain where it was, and a record must count its own
+// mixing hash. An entry claiming num_hashes == 0 is rejected as
+// InvalidEntry rather than treated as a no-op or as 1.
+//
+// verify_with::<H>() checks a chain produced by PohGenerator<H>.
+// verify_detailed() says where the chain breaks: the first entry that does
+// not follow from the one before it.
 // ---------------------------------------------------------------------------
 pub fn verify(seed: &[u8], entries: &[Entry]) -> bool {
-    let mut current_hash = sha256(seed);
+    verify_with::<Sha256Hasher>(seed, entries)
+}
 
-    for entry in entries {
-        if entry.transactions.is_empty() {
-            // Tick entry: plain sequential hashes.
-            for _ in 0..entry.num_hashes {
-                current_hash = sha256(&current_hash);
-            }
-        } else {
-            // Record entry: (num_hashes - 1) plain hashes + 1 mixing hash.
-            for _ in 0..entry.num_hashes.saturating_sub(1) {
-                current_hash = sha256(&current_hash);
-            }
-            let tx_hash = hash_transactions(&entry.transactions);
-            let mut input = Vec::with_capacity(64);
-            input.extend_from_slice(&current_hash);
-            input.extend_from_slice(&tx_hash);
-            current_hash = sha256(&input);
+pub fn verify_with<H: Hasher>(seed: &[u8], entries: &[Entry]) -> bool {
+    verify_detailed_with::<H>(seed, entries).is_ok()
+}
+
+pub fn verify_detailed(seed: &[u8], entries: &[Entry]) -> Result<(), PohError> {
+    verify_detailed_with::<Sha256Hasher>(seed, entries)
+}
+
+pub fn verify_detailed_with<H: Hasher>(seed: &[u8], entries: &[Entry]) -> Result<(), PohError> {
+    let mut current_hash = H::hash32(seed);
+
+    for (index, entry) in entries.iter().enumerate() {
+        let expected = next_hash::<H>(&current_hash, entry).ok_or(PohError::InvalidEntry { index })?;
+        if expected != entry.hash {
+            return Err(PohError::EntryMismatch { index, expected, got: entry.hash });
         }
+        current_hash = expected;
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// verify_parallel — verify() spread across every core.
+//
+// Each entry's starting hash is simply the previous entry's recorded hash
+// (the seed hash for the first), so once those are known every entry can
+// be recomputed independently. If all of them check out, the recorded
+// hashes form an unbroken chain from the seed — the same result verify()
+// reaches sequentially. This is how real validators verify PoH fast even
+// though producing it can't be parallelised.
+// ---------------------------------------------------------------------------
+pub fn verify_parallel(seed: &[u8], entries: &[Entry]) -> bool {
+    verify_parallel_with::<Sha256Hasher>(seed, entries)
+}
+
+pub fn verify_parallel_with<H: Hasher>(seed: &[u8], entries: &[Entry]) -> bool {
+    let seed_hash = H::hash32(seed);
+
+    entries.par_iter().enumerate().all(|(i, entry)| {
+        let start = if i == 0 { &seed_hash } else { &entries[i - 1].hash };
+        next_hash::<H>(start, entry) == Some(entry.hash)
+    })
+}
 
-        if current_hash != entry.hash {
-            return false;
+// ---------------------------------------------------------------------------
+// next_hash — recompute an entry's hash from the hash before it.
+//
+//   tick entry:   num_hashes plain hashes
+//   record entry: (num_hashes - 1) plain hashes + 1 mixing hash
+//
+// None for an entry with num_hashes == 0, which can't be valid.
+// ---------------------------------------------------------------------------
+fn next_hash<H: Hasher>(start: &[u8; 32], entry: &Entry) -> Option<[u8; 32]> {
+    if entry.num_hashes == 0 {
+        return None;
+    }
+    let mut current_hash = *start;
+
+    if entry.transactions.is_empty() {
+        // Tick entry: plain sequential hashes.
+        for _ in 0..entry.num_hashes {
+            current_hash = H::hash32(&current_hash);
+        }
+    } else {
+        // Record entry: (num_hashes - 1) plain hashes + 1 mixing hash.
+        for _ in 0..entry.num_hashes - 1 {
+            current_hash = H::hash32(&current_hash);
         }
+        let tx_hash = hash_transactions::<H>(&entry.transactions);
+        let mut input = Vec::with_capacity(64);
+        input.extend_from_slice(&current_hash);
+        input.extend_from_slice(&tx_hash);
+        current_hash = H::hash32(&input);
     }
 
-    true
+    Some(current_hash)
 }
 
-fn sha256(data: &[u8]) -> [u8; 32] {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    hasher.finalize().into()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::system;
+    use crate::types::account::Pubkey;
+    use crate::types::transaction::Message;
+
+    // A transfer of `lamports` carrying the same (zero) signature whatever
+    // the amount.
+    fn transfer(lamports: u64) -> Transaction {
+        let (from, to) = (Pubkey([1; 32]), Pubkey([2; 32]));
+        let message    = Message::compile(Some(&from), &[system::transfer(&from, &to, lamports)]).unwrap();
+        Transaction::new(message, vec![Default::default()])
+    }
+
+    #[test]
+    fn same_signatures_with_different_instruction_data_hash_differently() {
+        let (a, b) = (transfer(1), transfer(2));
+        assert_eq!(a.signatures, b.signatures);
+
+        let mut first  = PohGenerator::new(b"seed", 4, 8).unwrap();
+        let mut second = PohGenerator::new(b"seed", 4, 8).unwrap();
+        first.record(vec![a]);
+        second.record(vec![b]);
+
+        assert_ne!(first.last_hash(), second.last_hash());
+
+        // So an entry whose transaction is swapped for the other no longer
+        // verifies.
+        let mut tampered = first.entries.clone();
+        tampered[0].transactions = second.entries[0].transactions.clone();
+        assert!(verify(b"seed", &first.entries));
+        assert!(!verify(b"seed", &tampered));
+    }
+
+    #[test]
+    fn parallel_verification_agrees_with_sequential() {
+        let mut poh = PohGenerator::new(b"seed", 4, 8).unwrap();
+        for lamports in 0..3 {
+            poh.record(vec![transfer(lamports)]);
+            poh.tick();
+        }
+        let entries = poh.entries.clone();
+
+        let mut broken_link = entries.clone();
+        broken_link[3].hash[0] ^= 1;
+        let mut no_hashes = entries.clone();
+        no_hashes[2].num_hashes = 0;
+        let mut reordered = entries.clone();
+        reordered.swap(1, 2);
+
+        for (seed, chain, valid) in [
+            (&b"seed"[..], &entries, true),
+            (&b"seed"[..], &Vec::new(), true),
+            (&b"other"[..], &entries, false),
+            (&b"seed"[..], &broken_link, false),
+            (&b"seed"[..], &no_hashes, false),
+            (&b"seed"[..], &reordered, false),
+        ] {
+            assert_eq!(verify(seed, chain), valid);
+            assert_eq!(verify_parallel(seed, chain), valid);
+        }
+    }
+
+    #[test]
+    fn purging_drops_whole_slots_and_never_the_current_one() {
+        // Four ticks per slot, a record in slot 0: slots 0-2 are complete
+        // (5 + 4 + 4 entries) and slot 3 has two ticks so far.
+        let mut poh = PohGenerator::new(b"seed", 2, 4).unwrap();
+        poh.record(vec![transfer(1)]);
+        for _ in 0..14 {
+            poh.tick();
+        }
+        assert_eq!(poh.slot(), 3);
+        let last_hash = poh.last_hash();
+
+        assert_eq!(poh.purge_slots_before(2), 9);
+        assert_eq!((poh.first_available_slot(), poh.first_entry_index()), (2, 9));
+        assert_eq!(poh.purge_slots_before(1), 0);
+
+        assert_eq!(poh.purge_slots_before(100), 4);
+        assert_eq!((poh.first_available_slot(), poh.first_entry_index()), (3, 13));
+        assert_eq!(poh.entries.len(), 2);
+        assert_eq!(poh.last_hash(), last_hash);
+    }
+
+    #[test]
+    fn ledger_replays_to_the_same_chain_and_refuses_a_tampered_one() {
+        let path = std::env::temp_dir().join(format!("poh-ledger-test-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // Appended in two batches, as the ticker does after each entry.
+        let mut poh = PohGenerator::new(b"seed", 2, 4).unwrap();
+        poh.record(vec![transfer(1)]);
+        poh.tick();
+        poh.append_to_ledger(&path).unwrap();
+        for _ in 0..5 {
+            poh.tick();
+        }
+        poh.append_to_ledger(&path).unwrap();
+
+        let mut replayed = replay_ledger(b"seed", &path, 2, 4).unwrap();
+        let boundaries = |poh: &PohGenerator| poh.entries.iter().map(|e| (e.hash, e.slot_boundary)).collect::<Vec<_>>();
+        assert_eq!(boundaries(&replayed), boundaries(&poh));
+        assert_eq!((replayed.slot(), replayed.tick_height()), (poh.slot(), poh.tick_height()));
+        poh.tick();
+        replayed.tick();
+        assert_eq!(replayed.last_hash(), poh.last_hash());
+
+        // Flip a bit of the second entry's hash, which follows the first
+        // record, the second's length prefix and its num_hashes.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let second = 4 + u32::from_le_bytes(bytes[..4].try_into().unwrap()) as usize;
+        bytes[second + 4 + 8] ^= 1;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(replay_ledger(b"seed", &path, 2, 4), Err(LedgerError::InvalidEntry { entry: 1 })));
+
+        std::fs::write(&path, &bytes[..second + 10]).unwrap();
+        assert!(matches!(replay_ledger(b"seed", &path, 2, 4), Err(LedgerError::Corrupt { entry: 1 })));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn chain_verifies_only_with_the_hasher_that_made_it() {
+        let mut sha    = PohGenerator::new(b"seed", 4, 8).unwrap();
+        let mut blake3 = PohGenerator::<Blake3Hasher>::with_hasher(b"seed", 4, 8).unwrap();
+        sha.tick();
+        sha.record(vec![transfer(1)]);
+        blake3.tick();
+        blake3.record(vec![transfer(1)]);
+        assert_ne!(sha.last_hash(), blake3.last_hash());
+
+        assert!(verify_with::<Sha256Hasher>(b"seed", &sha.entries));
+        assert!(verify_parallel_with::<Sha256Hasher>(b"seed", &sha.entries));
+        assert!(verify_with::<Blake3Hasher>(b"seed", &blake3.entries));
+        assert!(verify_parallel_with::<Blake3Hasher>(b"seed", &blake3.entries));
+        assert!(!verify(b"seed", &blake3.entries));
+        assert!(!verify_with::<Blake3Hasher>(b"seed", &sha.entries));
+    }
+
+    #[test]
+    fn detailed_verification_names_the_first_broken_entry() {
+        let mut poh = PohGenerator::new(b"seed", 4, 8).unwrap();
+        poh.tick();
+        poh.tick();
+        poh.record(vec![transfer(1)]);
+        assert_eq!(verify_detailed(b"seed", &poh.entries), Ok(()));
+
+        // Entry 2 no longer follows either, but entry 1 is reported.
+        let mut flipped = poh.entries.clone();
+        flipped[1].hash[0] ^= 1;
+        assert_eq!(
+            verify_detailed(b"seed", &flipped),
+            Err(PohError::EntryMismatch { index: 1, expected: poh.entries[1].hash, got: flipped[1].hash })
+        );
+
+        let mut no_hashes = poh.entries.clone();
+        no_hashes[2].num_hashes = 0;
+        assert_eq!(verify_detailed(b"seed", &no_hashes), Err(PohError::InvalidEntry { index: 2 }));
+        assert!(!verify(b"seed", &no_hashes));
+    }
+
+    #[test]
+    fn large_batch_is_split_into_entries_in_order() {
+        let mut poh = PohGenerator::new(b"seed", 4, 8).unwrap();
+        poh.max_txs_per_entry = 4;
+        let batch: Vec<Transaction> = (0..10).map(transfer).collect();
+        poh.record(batch.clone());
+
+        let sizes: Vec<usize> = poh.entries.iter().map(|entry| entry.transactions.len()).collect();
+        assert_eq!(sizes, vec![4, 4, 2]);
+        let recorded: Vec<Transaction> = poh.entries.iter().flat_map(|entry| entry.transactions.clone()).collect();
+        assert_eq!(recorded, batch);
+        assert!(poh.entries.iter().all(|entry| entry.num_hashes == 1));
+        assert!(verify(b"seed", &poh.entries));
+
+        // An empty batch records nothing; 0 is taken as 1 per entry.
+        poh.record(vec![]);
+        assert_eq!(poh.entries.len(), 3);
+        poh.max_txs_per_entry = 0;
+        poh.record(batch[..2].to_vec());
+        assert_eq!(poh.entries.len(), 5);
+    }
+
+    #[test]
+    fn zero_hash_entries_never_verify() {
+        let mut poh = PohGenerator::new(b"seed", 4, 8).unwrap();
+        poh.tick();
+        poh.record(vec![transfer(1)]);
+
+        // A tick that hashed nothing repeats the hash before it.
+        let mut idle_tick = poh.entries.clone();
+        idle_tick.insert(1, Entry { num_hashes: 0, hash: poh.entries[0].hash, transactions: vec![], slot_boundary: false });
+        let mut empty_record = poh.entries.clone();
+        empty_record[1].num_hashes = 0;
+
+        for entries in [idle_tick, empty_record] {
+            assert_eq!(verify_detailed(b"seed", &entries), Err(PohError::InvalidEntry { index: 1 }));
+            assert!(!verify_parallel(b"seed", &entries));
+        }
+        assert!(matches!(PohGenerator::new(b"seed", 0, 8), Err(PohError::InvalidHashesPerTick(0))));
+    }
 }
@@ -0,0 +1,610 @@
+// ---------------------------------------------------------------------------
+// Account and transaction queries, plus the airdrop faucet.
+//
+// Every read takes the AccountsDB lock once, so a response never mixes
+// state from two points in time.
+// ---------------------------------------------------------------------------
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::prelude::*;
+use tiny_http::Response;
+
+use crate::runtime::accounts_db::ACCOUNT_STATS_SLOTS;
+use crate::runtime::commitment::CommitmentLevel;
+use crate::runtime::transaction_error::TransactionError;
+use crate::types::account::{AccountSharedData, Pubkey, Slot};
+use crate::types::rent::Rent;
+use crate::types::transaction::Signature;
+
+use super::{DEFAULT_ACCOUNT_STATS_LIMIT, NodeState};
+use super::ledger::{airdrop, check_state_consistency, consistency_error_json, consistency_json};
+use super::transactions::processed_response;
+use super::util::{json_response, optional_u64, pubkey_param, read_json_body};
+
+// Most pubkeys one getMultipleAccounts may ask for, as in Solana.
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+// Most accounts one getProgramAccounts may return; a scan matching more
+// is refused.
+const MAX_PROGRAM_ACCOUNTS: usize = 1000;
+
+// Accounts one GET /accounts page lists unless the query says, and the
+// most it may ask for.
+const DEFAULT_ACCOUNTS_PAGE: usize = 100;
+const MAX_ACCOUNTS_PAGE: usize = 1000;
+
+// How long accountSubscribe waits for a change unless the request says,
+// the most it may ask for, and how often it checks the account meanwhile.
+const DEFAULT_SUBSCRIBE_TIMEOUT_MS: u64 = 30_000;
+const MAX_SUBSCRIBE_TIMEOUT_MS: u64 = 60_000;
+const SUBSCRIBE_POLL: Duration = Duration::from_millis(50);
+
+// ---------------------------------------------------------------------------
+// balance — lamports held by a single account.
+//
+// A missing account reports 0 rather than an error: in Solana an account
+// that was never funded and one drained to zero look the same.
+//
+// Returns the slot the balance was read at along with it: the current slot
+// for processed, or the older slot the commitment level looks back to.
+// ---------------------------------------------------------------------------
+pub(super) fn balance(state: &Arc<NodeState>, pubkey: &Pubkey, commitment: CommitmentLevel) -> (Slot, u64) {
+    let db = state.db.lock().unwrap();
+    match commitment.slot(db.slot()) {
+        None => (db.slot(), db.load(pubkey).map(|a| a.lamports()).unwrap_or(0)),
+        Some(slot) => (slot, db.load_at_slot(pubkey, slot).map(|a| a.lamports()).unwrap_or(0)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// account_info — full state of a single account, as JSON.
+//
+// Mirrors the shape of Solana's getAccountInfo with base64 encoding:
+//   { "lamports", "owner" (base58), "executable", "rentEpoch", "space",
+//     "data": ["<base64>", "base64"] }
+// or null when the account does not exist.
+// ---------------------------------------------------------------------------
+pub(super) fn account_info(state: &Arc<NodeState>, pubkey: &Pubkey) -> serde_json::Value {
+    account_json(state.db.lock().unwrap().load(pubkey))
+}
+
+// ---------------------------------------------------------------------------
+// multiple_account_info — account_info for several accounts at once.
+//
+// Every account is loaded under a single AccountsDB lock, so the list is a
+// consistent snapshot: no transaction commits between two of the loads.
+// Returns the slot the snapshot was taken at along with it.
+// ---------------------------------------------------------------------------
+pub(super) fn multiple_account_info(state: &Arc<NodeState>, pubkeys: &[Pubkey]) -> (Slot, serde_json::Value) {
+    let db = state.db.lock().unwrap();
+    let accounts = pubkeys.iter().map(|pubkey| account_json(db.load(pubkey))).collect();
+    (db.slot(), serde_json::Value::Array(accounts))
+}
+
+// ---------------------------------------------------------------------------
+// program_accounts — every account `owner` owns, as getProgramAccounts
+// reports them.
+//
+// Scans the whole AccountsDB under one lock, so like getMultipleAccounts
+// the list is a consistent snapshot, taken at the returned slot. Sorted by
+// pubkey so the answer does not depend on HashMap order. Err if more than
+// MAX_PROGRAM_ACCOUNTS accounts match.
+// ---------------------------------------------------------------------------
+pub(super) fn program_accounts(state: &Arc<NodeState>, owner: &Pubkey) -> Result<(Slot, serde_json::Value), String> {
+    let db = state.db.lock().unwrap();
+    let mut owned: Vec<(&Pubkey, &AccountSharedData)> = db.iter().filter(|(_, account)| account.owner() == owner).collect();
+    if owned.len() > MAX_PROGRAM_ACCOUNTS {
+        return Err(format!("scan aborted: {} accounts match, at most {} may be returned", owned.len(), MAX_PROGRAM_ACCOUNTS));
+    }
+    owned.sort_by_key(|(pubkey, _)| **pubkey);
+    let accounts = owned
+        .into_iter()
+        .map(|(pubkey, account)| serde_json::json!({ "pubkey": pubkey.to_base58(), "account": account_json(Some(account)) }))
+        .collect();
+    Ok((db.slot(), serde_json::Value::Array(accounts)))
+}
+
+// The pubkeys a getMultipleAccounts request lists: base58 strings, at most
+// MAX_MULTIPLE_ACCOUNTS of them.
+pub(super) fn pubkey_list(value: &serde_json::Value) -> Result<Vec<Pubkey>, String> {
+    let addresses = value.as_array().ok_or("expected a list of base58 pubkeys")?;
+    if addresses.len() > MAX_MULTIPLE_ACCOUNTS {
+        return Err(format!("at most {} pubkeys may be requested", MAX_MULTIPLE_ACCOUNTS));
+    }
+    addresses
+        .iter()
+        .map(|address| address.as_str().and_then(Pubkey::from_base58))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| "expected a list of base58 pubkeys".to_string())
+}
+
+// An account in getAccountInfo's shape, or null for a missing one.
+pub(super) fn account_json(account: Option<&AccountSharedData>) -> serde_json::Value {
+    match account {
+        Some(account) => serde_json::json!({
+            "lamports":   account.lamports(),
+            "owner":      account.owner().to_base58(),
+            "executable": account.executable(),
+            "rentEpoch":  account.rent_epoch(),
+            "space":      account.data().len(),
+            "data":       [BASE64_STANDARD.encode(account.data()), "base64"],
+        }),
+        None => serde_json::Value::Null,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// transaction_info — a recorded transaction, looked up by signature.
+//
+// Mirrors the shape of Solana's getTransaction with json encoding:
+//   { "slot", "entryIndex",
+//     "transaction": { "signatures": [base58],
+//                      "message": { "header", "accountKeys", "recentBlockhash",
+//                                   "instructions": [{ "programIdIndex", "accounts", "data" }] } },
+//     "meta": { "err", "status": { "Ok": null } | { "Err": <error> } } }
+// with instruction data in base58. "entryIndex" (not in Solana) is the PoH
+// entry that holds it. Null if no recorded transaction has that signature
+// or its entry has been purged.
+// ---------------------------------------------------------------------------
+pub(super) fn transaction_info(state: &Arc<NodeState>, signature: &Signature) -> serde_json::Value {
+    let poh = state.poh.lock().unwrap();
+    let Some(status) = state.transaction_index.lock().unwrap().get(signature).cloned() else {
+        return serde_json::Value::Null;
+    };
+    let tx = status.entry_index
+        .checked_sub(poh.first_entry_index())
+        .and_then(|i| poh.entries.get(i))
+        .and_then(|entry| entry.transactions.iter().find(|tx| tx.signature() == *signature));
+    let Some(tx) = tx else {
+        return serde_json::Value::Null;
+    };
+
+    let message      = &tx.message;
+    let instructions: Vec<serde_json::Value> = message.instructions
+        .iter()
+        .map(|ix| serde_json::json!({
+            "programIdIndex": ix.program_id_index,
+            "accounts":       ix.accounts,
+            "data":           bs58::encode(&ix.data).into_string(),
+        }))
+        .collect();
+    let err    = status.err.as_ref().map(TransactionError::to_json);
+    let result = match &err {
+        None      => serde_json::json!({ "Ok": null }),
+        Some(err) => serde_json::json!({ "Err": err }),
+    };
+
+    serde_json::json!({
+        "slot":       status.slot,
+        "entryIndex": status.entry_index,
+        "transaction": {
+            "signatures": tx.signatures.iter().map(|sig| bs58::encode(sig.0).into_string()).collect::<Vec<_>>(),
+            "message": {
+                "header": {
+                    "numRequiredSignatures":       message.header.num_required_signatures,
+                    "numReadonlySignedAccounts":   message.header.num_readonly_signed_accounts,
+                    "numReadonlyUnsignedAccounts": message.header.num_readonly_unsigned_accounts,
+                },
+                "accountKeys":     message.account_keys.iter().map(|key| key.to_base58()).collect::<Vec<_>>(),
+                "recentBlockhash": bs58::encode(message.recent_blockhash.0).into_string(),
+                "instructions":    instructions,
+            },
+        },
+        "meta": { "err": err, "status": result },
+    })
+}
+
+// ---------------------------------------------------------------------------
+// handle_request_airdrop
+// ---------------------------------------------------------------------------
+pub(super) fn handle_request_airdrop(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let pubkey = match pubkey_param(&parsed) {
+        Ok(pk) => pk,
+        Err(r) => return r,
+    };
+    let lamports = match parsed["lamports"].as_u64() {
+        Some(l) => l,
+        None    => return json_response(400, r#"{"error":"\"lamports\" must be a u64"}"#),
+    };
+
+    processed_response(airdrop(state, pubkey, lamports))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_balance
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_balance(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let pubkey = match pubkey_param(&parsed) {
+        Ok(pk) => pk,
+        Err(r) => return r,
+    };
+    let commitment = match parsed["commitment"].as_str() {
+        None => CommitmentLevel::default(),
+        Some(name) => match CommitmentLevel::from_name(name) {
+            Some(level) => level,
+            None => return json_response(400,
+                r#"{"error":""commitment" must be processed, confirmed or finalized"}"#),
+        },
+    };
+
+    let (_, lamports) = balance(state, &pubkey, commitment);
+    json_response(200, &format!(r#"{{"value":{}}}"#, lamports))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_account_info
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_account_info(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let pubkey = match read_json_body(request).and_then(|parsed| pubkey_param(&parsed)) {
+        Ok(pk) => pk,
+        Err(r) => return r,
+    };
+
+    json_response(200, &serde_json::json!({ "value": account_info(state, &pubkey) }).to_string())
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_multiple_accounts
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_multiple_accounts(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let pubkeys = match pubkey_list(&parsed["pubkeys"]) {
+        Ok(pubkeys) => pubkeys,
+        Err(e) => return json_response(400, &serde_json::json!({ "error": format!("\"pubkeys\": {}", e) }).to_string()),
+    };
+
+    let (_, accounts) = multiple_account_info(state, &pubkeys);
+    json_response(200, &serde_json::json!({ "value": accounts }).to_string())
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_program_accounts
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_program_accounts(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let owner = match parsed["owner"].as_str().and_then(Pubkey::from_base58) {
+        Some(owner) => owner,
+        None => return json_response(400, r#"{"error":"\"owner\" must be a base58 pubkey"}"#),
+    };
+
+    match program_accounts(state, &owner) {
+        Ok((_, accounts)) => json_response(200, &serde_json::json!({ "value": accounts }).to_string()),
+        Err(e) => json_response(413, &serde_json::json!({ "error": e }).to_string()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_accounts — GET /accounts, the debugging state dump.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_accounts(query: &str, state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let mut offset = 0;
+    let mut limit  = DEFAULT_ACCOUNTS_PAGE;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let target = match name {
+            "offset" => &mut offset,
+            "limit"  => &mut limit,
+            _        => continue,
+        };
+        match value.parse() {
+            Ok(n)  => *target = n,
+            Err(_) => return json_response(400, &format!(r#"{{"error":"\"{}\" must be a number"}}"#, name)),
+        }
+    }
+    let limit = limit.min(MAX_ACCOUNTS_PAGE);
+
+    let db = state.db.lock().unwrap();
+    let mut accounts: Vec<(&Pubkey, &AccountSharedData)> = db.iter().collect();
+    accounts.sort_by_key(|(pubkey, _)| **pubkey);
+    let page: Vec<serde_json::Value> = accounts
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(pubkey, account)| serde_json::json!({
+            "pubkey":   pubkey.to_base58(),
+            "lamports": account.lamports(),
+            "owner":    account.owner().to_base58(),
+            "data_len": account.data().len(),
+        }))
+        .collect();
+
+    let body = serde_json::json!({
+        "slot":     db.slot(),
+        "total":    accounts.len(),
+        "offset":   offset,
+        "accounts": page,
+    });
+    json_response(200, &body.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// handle_account_subscribe — long-poll until an account changes.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_account_subscribe(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let pubkey = match pubkey_param(&parsed) {
+        Ok(pk) => pk,
+        Err(r) => return r,
+    };
+    let last_seen = match optional_u64(&parsed, "version") {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let timeout_ms = match optional_u64(&parsed, "timeout_ms") {
+        Ok(v)  => v.unwrap_or(DEFAULT_SUBSCRIBE_TIMEOUT_MS).min(MAX_SUBSCRIBE_TIMEOUT_MS),
+        Err(r) => return r,
+    };
+
+    let body = subscribe(state, &pubkey, last_seen, Duration::from_millis(timeout_ms));
+    json_response(200, &body.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// subscribe — wait until `pubkey`'s write version differs from `last_seen`.
+//
+// Returns at once when there is no `last_seen` (the caller learns the
+// current version), otherwise when the account changes, `timeout` passes
+// or the node shuts down, whichever is first. "changed" says which. The
+// DB lock is only held while checking, so transactions keep committing.
+// ---------------------------------------------------------------------------
+fn subscribe(state: &NodeState, pubkey: &Pubkey, last_seen: Option<u64>, timeout: Duration) -> serde_json::Value {
+    let deadline = Instant::now() + timeout;
+    loop {
+        {
+            let db      = state.db.lock().unwrap();
+            let version = db.version(pubkey).unwrap_or(0);
+            let changed = last_seen.is_some_and(|seen| seen != version);
+            let done    = last_seen.is_none() || changed || Instant::now() >= deadline || state.is_shutting_down();
+            if done {
+                return serde_json::json!({
+                    "changed": changed,
+                    "version": version,
+                    "value":   account_json(db.load(pubkey)),
+                });
+            }
+        }
+        std::thread::sleep(SUBSCRIBE_POLL);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_minimum_balance — getMinimumBalanceForRentExemption.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_minimum_balance(request: &mut tiny_http::Request) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let data_len = match parsed["data_len"].as_u64().and_then(|n| usize::try_from(n).ok()) {
+        Some(n) => n,
+        None    => return json_response(400, r#"{"error":"\"data_len\" must be a u64"}"#),
+    };
+
+    json_response(200, &format!(r#"{{"value":{}}}"#, Rent::default().minimum_balance(data_len)))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_transaction
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_transaction(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let signature = match parsed["signature"].as_str().and_then(Signature::from_base58) {
+        Some(signature) => signature,
+        None => return json_response(400, r#"{"error":"\"signature\" must be a base58 signature"}"#),
+    };
+
+    json_response(200, &serde_json::json!({ "value": transaction_info(state, &signature) }).to_string())
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_state_root — Merkle root over every account, hex-encoded.
+//
+// Two nodes that processed the same transactions report the same root.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_state_root(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let db = state.db.lock().unwrap();
+    json_response(200, &format!(r#"{{"state_root":"{}","algorithm":"{}"}}"#,
+        hex::encode(db.state_root()), db.hash_algorithm()))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_account_stats — read/write counts for hot-account hunting.
+//
+// The body is optional: an empty one lists the hottest accounts.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_account_stats(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let mut body = String::new();
+    if request.as_reader().read_to_string(&mut body).is_err() {
+        return json_response(400, r#"{"error":"could not read body"}"#);
+    }
+    let parsed = if body.trim().is_empty() {
+        serde_json::Value::Null
+    } else {
+        match serde_json::from_str(&body) {
+            Ok(v)  => v,
+            Err(e) => return json_response(400, &format!("{{\"error\":\"{}\"}}", e)),
+        }
+    };
+
+    let pubkey = match parsed["pubkey"] {
+        serde_json::Value::Null => None,
+        _ => match pubkey_param(&parsed) {
+            Ok(pk) => Some(pk),
+            Err(r) => return r,
+        },
+    };
+    let limit = match optional_u64(&parsed, "limit") {
+        Ok(limit) => limit.map_or(DEFAULT_ACCOUNT_STATS_LIMIT, |limit| limit as usize),
+        Err(r)    => return r,
+    };
+
+    json_response(200, &account_stats_json(state, pubkey, limit).to_string())
+}
+
+// The stats of `pubkey`, or of the `limit` hottest accounts, as both REST
+// and JSON-RPC return them.
+pub(super) fn account_stats_json(state: &Arc<NodeState>, pubkey: Option<Pubkey>, limit: usize) -> serde_json::Value {
+    let db = state.db.lock().unwrap();
+    let accounts = match pubkey {
+        Some(pubkey) => vec![(pubkey, db.account_stats(&pubkey))],
+        None         => db.hottest_accounts(limit),
+    };
+    serde_json::json!({
+        "slots":    ACCOUNT_STATS_SLOTS,
+        "accounts": accounts
+            .into_iter()
+            .map(|(pubkey, stats)| serde_json::json!({
+                "pubkey": pubkey.to_base58(), "reads": stats.reads, "writes": stats.writes,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// handle_check_state_consistency — replay the ledger and compare roots.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_check_state_consistency(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    match check_state_consistency(state) {
+        Ok(consistency) => json_response(200, &consistency_json(&consistency).to_string()),
+        Err(e) => json_response(503, &consistency_error_json(&e).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{lamports, node, signed};
+    use super::super::transactions::process_transaction;
+    use crate::programs::system;
+    use crate::types::keys;
+
+    #[test]
+    fn balance_reads_the_state_its_commitment_level_looks_back_to() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let at_genesis = balance(&state, &to, CommitmentLevel::Processed).1;
+
+        // 10 lamports arrive in slot 1 and 20 more in slot 2.
+        for (slot, lamports) in [(1, 10), (2, 20)] {
+            state.db.lock().unwrap().set_slot(slot);
+            let tx = signed(&state, 1, &[], &[system::transfer(&from, &to, lamports)]);
+            assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+        }
+
+        assert_eq!(balance(&state, &to, CommitmentLevel::Processed), (2, at_genesis + 30));
+        assert_eq!(balance(&state, &to, CommitmentLevel::Confirmed), (1, at_genesis + 10));
+        assert_eq!(balance(&state, &to, CommitmentLevel::Finalized), (0, at_genesis));
+        assert_eq!(balance(&state, &Pubkey([42; 32]), CommitmentLevel::Processed), (2, 0));
+    }
+
+    #[test]
+    fn subscriber_wakes_on_a_concurrent_transfer_or_times_out() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let before     = lamports(&state, 2);
+
+        let current = subscribe(&state, &to, None, Duration::ZERO);
+        assert_eq!(current["changed"], false);
+        let version = current["version"].as_u64().unwrap();
+
+        let nothing = subscribe(&state, &to, Some(version), Duration::from_millis(100));
+        assert_eq!(nothing["changed"], false);
+        assert_eq!(nothing["version"], version);
+
+        let waiter = {
+            let state = Arc::clone(&state);
+            std::thread::spawn(move || subscribe(&state, &to, Some(version), Duration::from_secs(10)))
+        };
+        std::thread::sleep(Duration::from_millis(100));
+        let tx = signed(&state, 1, &[], &[system::transfer(&from, &to, 10)]);
+        assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+
+        let woken = waiter.join().unwrap();
+        assert_eq!(woken["changed"], true);
+        assert!(woken["version"].as_u64().unwrap() > version);
+        assert_eq!(woken["value"]["lamports"], before + 10);
+    }
+
+    #[test]
+    fn account_dump_lists_both_sides_of_a_transfer_a_page_at_a_time() {
+        use std::io::Read;
+
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), Pubkey([42; 32]));
+        let before     = lamports(&state, 1);
+        let tx = signed(&state, 1, &[], &[system::transfer(&from, &to, 1_000_000)]);
+        assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+        let fee = state.fee_calculator.lamports_per_signature;
+
+        let page = |query: &str| {
+            let mut body = String::new();
+            handle_get_accounts(query, &state).into_reader().read_to_string(&mut body).unwrap();
+            serde_json::from_str::<serde_json::Value>(&body).unwrap()
+        };
+        let all   = page("");
+        let total = all["total"].as_u64().unwrap() as usize;
+        let listed = |pubkey: Pubkey| {
+            all["accounts"].as_array().unwrap().iter().find(|a| a["pubkey"] == pubkey.to_base58()).map(|a| a["lamports"].clone())
+        };
+        assert_eq!(listed(from), Some((before - fee - 1_000_000).into()));
+        assert_eq!(listed(to), Some(1_000_000.into()));
+
+        // Pages in pubkey order that together list every account once.
+        let mut paged = Vec::new();
+        for offset in (0..total).step_by(2) {
+            paged.extend(page(&format!("offset={offset}&limit=2"))["accounts"].as_array().unwrap().clone());
+        }
+        assert_eq!(&paged, all["accounts"].as_array().unwrap());
+        assert_eq!(handle_get_accounts("limit=many", &state).status_code().0, 400);
+
+        let (_, owned) = program_accounts(&state, &system::SYSTEM_PROGRAM_ID).unwrap();
+        assert!(owned.as_array().unwrap().iter().any(|a| a["pubkey"] == to.to_base58()));
+        assert_eq!(program_accounts(&state, &Pubkey([7; 32])).unwrap().1, serde_json::json!([]));
+    }
+}
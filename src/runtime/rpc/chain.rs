@@ -0,0 +1,163 @@
+// ---------------------------------------------------------------------------
+// Chain position — genesis hash, slot, block height, blockhash and epoch,
+// all read from PoH.
+// ---------------------------------------------------------------------------
+
+use std::sync::Arc;
+
+use tiny_http::Response;
+
+use crate::runtime::bank::BlockhashQueue;
+use crate::runtime::epoch_schedule::EpochInfo;
+use crate::runtime::poh::PohGenerator;
+use crate::types::transaction::Hash;
+
+use super::NodeState;
+use super::util::json_response;
+
+// ---------------------------------------------------------------------------
+// handle_get_genesis_hash — identify which chain this node is on.
+//
+// Returns the genesis hash alongside the cluster type, so a client can
+// refuse to talk to a node from the wrong network.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_genesis_hash(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    json_response(
+        200,
+        &format!(
+            r#"{{"genesis_hash":"{}","cluster_type":"{}","creation_time":{}}}"#,
+            hex::encode(state.genesis_hash),
+            state.cluster_type,
+            state.creation_time,
+        ),
+    )
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_first_available_block — the oldest slot still held.
+//
+// Anything older has been purged from the in-memory ledger and can no
+// longer be queried from this node.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_first_available_block(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let slot = state.poh.lock().unwrap().first_available_slot();
+    json_response(200, &format!(r#"{{"value":{}}}"#, slot))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_slot — the slot PoH is in, for clients polling progress.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_slot(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let slot = state.poh.lock().unwrap().slot();
+    json_response(200, &format!(r#"{{"value":{}}}"#, slot))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_block_height — how many slots have produced entries.
+//
+// Every slot here gets its ticks, so this trails getSlot by at most one:
+// the slot just entered has no entry until its first tick or record.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_block_height(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let height = state.poh.lock().unwrap().block_height();
+    json_response(200, &format!(r#"{{"value":{}}}"#, height))
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_latest_blockhash — a blockhash to sign against, and until when.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_latest_blockhash(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let (blockhash, last_valid_block_height) = latest_blockhash(state);
+    json_response(200, &serde_json::json!({
+        "blockhash":               blockhash,
+        "last_valid_block_height": last_valid_block_height,
+        "fee_calculator": {
+            "lamports_per_signature":   state.fee_calculator.lamports_per_signature,
+            "lamports_per_instruction": state.fee_calculator.lamports_per_instruction,
+        },
+    }).to_string())
+}
+
+// The newest registered blockhash in base58 and the last block height it is
+// sure to be accepted at.
+pub(super) fn latest_blockhash(state: &Arc<NodeState>) -> (String, u64) {
+    let poh   = state.poh.lock().unwrap();
+    let queue = state.blockhash_queue.lock().unwrap();
+    let (blockhash, last_valid_block_height) = newest_blockhash(&poh, &queue);
+    (bs58::encode(blockhash.0).into_string(), last_valid_block_height)
+}
+
+// The newest blockhash was registered as the current slot began, and the
+// queue drops it when the slot max_age() later begins. Until then the block
+// height is at most one past the slot, so the hash is accepted up to the
+// current slot plus max_age() - 1.
+fn newest_blockhash(poh: &PohGenerator, queue: &BlockhashQueue) -> (Hash, u64) {
+    let blockhash = queue.last_hash().expect("genesis registers a blockhash");
+    (blockhash, poh.slot() + queue.max_age() as u64 - 1)
+}
+
+// ---------------------------------------------------------------------------
+// handle_get_epoch_info — the current epoch and how far into it PoH is.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_get_epoch_info(state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let info = epoch_info(state);
+    json_response(200, &serde_json::json!({
+        "epoch":          info.epoch,
+        "slot_index":     info.slot_index,
+        "slots_in_epoch": info.slots_in_epoch,
+        "absolute_slot":  info.absolute_slot,
+        "block_height":   info.block_height,
+    }).to_string())
+}
+
+// Where the current slot sits in the epoch schedule. The slot and block
+// height are read under one PoH lock, so they agree.
+pub(super) fn epoch_info(state: &Arc<NodeState>) -> EpochInfo {
+    let poh = state.poh.lock().unwrap();
+    state.epoch_schedule.epoch_info(poh.slot(), poh.block_height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::system;
+    use crate::runtime::bank::MAX_RECENT_BLOCKHASHES;
+    use crate::types::account::Pubkey;
+    use crate::types::transaction::{Message, Transaction};
+
+    use super::super::ledger::register_blockhash;
+
+    // One tick, after a few recorded transactions, registered the way the
+    // ticker does it.
+    fn busy_tick(poh: &mut PohGenerator, queue: &mut BlockhashQueue) {
+        let (from, to) = (Pubkey([1; 32]), Pubkey([2; 32]));
+        for lamports in 0..5 {
+            let message = Message::compile(Some(&from), &[system::transfer(&from, &to, lamports)]).unwrap();
+            poh.record(vec![Transaction::new(message, vec![Default::default()])]);
+        }
+        poh.tick();
+        register_blockhash(queue, poh.entries.last().unwrap());
+    }
+
+    #[test]
+    fn latest_blockhash_is_accepted_through_last_valid_block_height() {
+        let mut poh   = PohGenerator::new(b"seed", 2, 4).unwrap();
+        let mut queue = BlockhashQueue::new(MAX_RECENT_BLOCKHASHES);
+        queue.register_hash(Hash::new(poh.last_hash()));
+        for _ in 0..10 {
+            busy_tick(&mut poh, &mut queue);
+        }
+
+        let (blockhash, last_valid_block_height) = newest_blockhash(&poh, &queue);
+        while poh.block_height() <= last_valid_block_height {
+            assert!(queue.is_valid(&blockhash), "expired at block height {}", poh.block_height());
+            busy_tick(&mut poh, &mut queue);
+        }
+
+        // It does not outlive the height by more than the slot in hand.
+        while poh.slot() <= last_valid_block_height {
+            busy_tick(&mut poh, &mut queue);
+        }
+        assert!(!queue.is_valid(&blockhash));
+    }
+}
@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use base64::prelude::*;
+use tiny_http::Response;
+
+use crate::runtime::commitment::CommitmentLevel;
+use crate::runtime::transaction_error::TransactionError;
+use crate::types::account::Pubkey;
+use crate::types::rent::Rent;
+use crate::types::transaction::{Signature, VersionedTransaction};
+
+use super::{DEFAULT_ACCOUNT_STATS_LIMIT, NodeState};
+use super::accounts::{
+    account_info, account_stats_json, balance, multiple_account_info, program_accounts,
+    pubkey_list, transaction_info,
+};
+use super::chain::{epoch_info, latest_blockhash};
+use super::ledger::{airdrop, check_state_consistency, consistency_json};
+use super::transactions::{
+    process_versioned_transaction, Processed, return_accounts_param, simulate_transaction,
+    simulated_json,
+};
+use super::util::json_response;
+
+// ---------------------------------------------------------------------------
+// JSON-RPC 2.0 — the envelope standard Solana clients speak.
+//
+//   POST /
+//   { "jsonrpc": "2.0", "id": 1, "method": "getBalance", "params": ["<base58>"] }
+//   → { "jsonrpc": "2.0", "id": 1, "result": { "context": { "slot": 4 }, "value": 100000000000 } }
+//   → { "jsonrpc": "2.0", "id": 1, "error": { "code": -32601, "message": "Method not found" } }
+//
+// Methods (params as in Solana's RPC):
+//   getBalance      [pubkey, { commitment }?]          → { context, value: lamports }
+//   getAccountInfo  [pubkey, { encoding: "base64" }?]  → { context, value: account | null }
+//   getMultipleAccounts [[pubkey, ...], { encoding: "base64" }?] → { context, value: [account | null] }
+//   getProgramAccounts [program_id, { encoding: "base64" }?] → [{ pubkey, account }]
+//   getMinimumBalanceForRentExemption [data_len]       → lamports
+//   getTransaction  [signature, { encoding: "json" }?] → transaction | null
+//   sendTransaction [tx, { encoding: "base58" | "base64" }?] → signature
+//   simulateTransaction [tx, { encoding, returnAccounts }?] → { context, value: simulation }
+//   requestAirdrop  [pubkey, lamports]                 → signature
+//   getGenesisHash  []                                 → base58 hash
+//   getFirstAvailableBlock []                          → slot
+//   getSlot         []                                 → slot
+//   getBlockHeight  []                                 → slots with an entry
+//   getLatestBlockhash []                              → { context, value: { blockhash, lastValidBlockHeight, feeCalculator } }
+//   getEpochInfo    []                                 → { epoch, slotIndex, slotsInEpoch, absoluteSlot, blockHeight }
+//   getStateRoot    []                                 → hex root (not in Solana)
+//   getAccountStats [{ pubkey?, limit? }?]             → read/write counts (not in Solana)
+//   checkStateConsistency []                           → consistency report (not in Solana)
+//
+// Errors reuse the standard codes, plus Solana's -32002 for a transaction
+// that was rejected or failed and -32001 for history the node no longer
+// has.
+//
+// Reference: https://www.jsonrpc.org/specification
+// ---------------------------------------------------------------------------
+const PARSE_ERROR:          i64 = -32700;
+const INVALID_REQUEST:      i64 = -32600;
+const METHOD_NOT_FOUND:     i64 = -32601;
+const INVALID_PARAMS:       i64 = -32602;
+const HISTORY_UNAVAILABLE:  i64 = -32001;
+const TRANSACTION_REJECTED: i64 = -32002;
+
+#[derive(Debug)]
+struct RpcError {
+    code:    i64,
+    message: String,
+    data:    Option<serde_json::Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError { code, message: message.into(), data: None }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        RpcError::new(INVALID_PARAMS, message)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// handle_json_rpc — parse the envelope, dispatch, wrap the reply.
+//
+// Always answers 200; success or failure is carried in the envelope.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_json_rpc(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let mut body = String::new();
+    if request.as_reader().read_to_string(&mut body).is_err() {
+        return json_rpc_response(serde_json::Value::Null, Err(RpcError::new(PARSE_ERROR, "Parse error")));
+    }
+    let envelope: serde_json::Value = match serde_json::from_str(&body) {
+        Ok(v)  => v,
+        Err(_) => {
+            return json_rpc_response(serde_json::Value::Null, Err(RpcError::new(PARSE_ERROR, "Parse error")));
+        }
+    };
+
+    let id = envelope.get("id").cloned().unwrap_or(serde_json::Value::Null);
+    let method = match (envelope.get("jsonrpc").and_then(|v| v.as_str()), envelope.get("method")) {
+        (Some("2.0"), Some(serde_json::Value::String(method))) => method.as_str(),
+        _ => return json_rpc_response(id, Err(RpcError::new(INVALID_REQUEST, "Invalid request"))),
+    };
+    let no_params = serde_json::Value::Array(vec![]);
+    let params = match envelope.get("params") {
+        None | Some(serde_json::Value::Null)          => &no_params,
+        Some(params @ serde_json::Value::Array(_))    => params,
+        Some(_) => return json_rpc_response(id, Err(RpcError::new(INVALID_REQUEST, "Invalid request"))),
+    };
+
+    println!("[rpc] json-rpc {}", method);
+    json_rpc_response(id, dispatch(method, params, state))
+}
+
+// ---------------------------------------------------------------------------
+// dispatch — route a JSON-RPC method to its implementation.
+// ---------------------------------------------------------------------------
+fn dispatch(
+    method: &str,
+    params: &serde_json::Value,
+    state: &Arc<NodeState>,
+) -> Result<serde_json::Value, RpcError> {
+    match method {
+        "getBalance" => {
+            let pubkey = rpc_pubkey_param(params, 0)?;
+            let commitment = match params[1]["commitment"].as_str() {
+                None => CommitmentLevel::default(),
+                Some(name) => CommitmentLevel::from_name(name)
+                    .ok_or_else(|| RpcError::invalid_params(format!("unsupported commitment: {}", name)))?,
+            };
+            let (slot, lamports) = balance(state, &pubkey, commitment);
+            Ok(serde_json::json!({ "context": { "slot": slot }, "value": lamports }))
+        }
+        "getAccountInfo" => {
+            let pubkey = rpc_pubkey_param(params, 0)?;
+            match params[1]["encoding"].as_str() {
+                None | Some("base64") => {}
+                Some(other) => return Err(RpcError::invalid_params(format!("unsupported encoding: {}", other))),
+            }
+            Ok(with_context(state, account_info(state, &pubkey)))
+        }
+        "getMultipleAccounts" => {
+            let pubkeys = pubkey_list(&params[0])
+                .map_err(|e| RpcError::invalid_params(format!("param 0: {}", e)))?;
+            match params[1]["encoding"].as_str() {
+                None | Some("base64") => {}
+                Some(other) => return Err(RpcError::invalid_params(format!("unsupported encoding: {}", other))),
+            }
+            let (slot, accounts) = multiple_account_info(state, &pubkeys);
+            Ok(serde_json::json!({ "context": { "slot": slot }, "value": accounts }))
+        }
+        "getProgramAccounts" => {
+            let owner = rpc_pubkey_param(params, 0)?;
+            match params[1]["encoding"].as_str() {
+                None | Some("base64") => {}
+                Some(other) => return Err(RpcError::invalid_params(format!("unsupported encoding: {}", other))),
+            }
+            program_accounts(state, &owner).map(|(_, accounts)| accounts).map_err(RpcError::invalid_params)
+        }
+        "getMinimumBalanceForRentExemption" => {
+            let data_len = params[0]
+                .as_u64()
+                .and_then(|n| usize::try_from(n).ok())
+                .ok_or_else(|| RpcError::invalid_params("param 0 must be a data length"))?;
+            Ok(Rent::default().minimum_balance(data_len).into())
+        }
+        "getTransaction" => {
+            let signature = params[0]
+                .as_str()
+                .and_then(Signature::from_base58)
+                .ok_or_else(|| RpcError::invalid_params("param 0 must be a base58 signature"))?;
+            match params[1]["encoding"].as_str() {
+                None | Some("json") => {}
+                Some(other) => return Err(RpcError::invalid_params(format!("unsupported encoding: {}", other))),
+            }
+            Ok(transaction_info(state, &signature))
+        }
+        "sendTransaction" => {
+            let (tx, len) = rpc_transaction_param(params)?;
+            println!("[rpc] sendTransaction  {} bytes  sig={}", len, tx.id_base58());
+            processed_result(process_versioned_transaction(tx, state))
+        }
+        "simulateTransaction" => {
+            let (tx, len) = rpc_transaction_param(params)?;
+            let tx = tx
+                .into_legacy()
+                .ok_or_else(|| RpcError::invalid_params("v0 transactions cannot be simulated"))?;
+            let return_accounts = return_accounts_param(&params[1]["returnAccounts"], &tx)
+                .map_err(RpcError::invalid_params)?;
+            println!("[rpc] simulateTransaction  {} bytes  sig={}", len, tx.id_base58());
+            let simulated = simulated_json(&simulate_transaction(&tx, state, return_accounts.as_deref()));
+            Ok(with_context(state, simulated))
+        }
+        "requestAirdrop" => {
+            let pubkey   = rpc_pubkey_param(params, 0)?;
+            let lamports = params[1]
+                .as_u64()
+                .ok_or_else(|| RpcError::invalid_params("expected lamports as a u64"))?;
+            processed_result(airdrop(state, pubkey, lamports))
+        }
+        "getGenesisHash" => Ok(bs58::encode(state.genesis_hash).into_string().into()),
+        "getFirstAvailableBlock" => Ok(state.poh.lock().unwrap().first_available_slot().into()),
+        "getSlot"        => Ok(state.poh.lock().unwrap().slot().into()),
+        "getBlockHeight" => Ok(state.poh.lock().unwrap().block_height().into()),
+        "getLatestBlockhash" => {
+            let (blockhash, last_valid_block_height) = latest_blockhash(state);
+            Ok(with_context(state, serde_json::json!({
+                "blockhash":            blockhash,
+                "lastValidBlockHeight": last_valid_block_height,
+                "feeCalculator": {
+                    "lamportsPerSignature":   state.fee_calculator.lamports_per_signature,
+                    "lamportsPerInstruction": state.fee_calculator.lamports_per_instruction,
+                },
+            })))
+        }
+        "getEpochInfo"   => {
+            let info = epoch_info(state);
+            Ok(serde_json::json!({
+                "epoch":        info.epoch,
+                "slotIndex":    info.slot_index,
+                "slotsInEpoch": info.slots_in_epoch,
+                "absoluteSlot": info.absolute_slot,
+                "blockHeight":  info.block_height,
+            }))
+        }
+        "getStateRoot"   => Ok(hex::encode(state.db.lock().unwrap().state_root()).into()),
+        "getAccountStats" => {
+            let pubkey = match &params[0]["pubkey"] {
+                serde_json::Value::Null => None,
+                value => Some(value.as_str().and_then(Pubkey::from_base58)
+                    .ok_or_else(|| RpcError::invalid_params("pubkey must be a base58 pubkey"))?),
+            };
+            let limit = match &params[0]["limit"] {
+                serde_json::Value::Null => DEFAULT_ACCOUNT_STATS_LIMIT,
+                value => value.as_u64().map(|limit| limit as usize)
+                    .ok_or_else(|| RpcError::invalid_params("limit must be a u64"))?,
+            };
+            Ok(account_stats_json(state, pubkey, limit))
+        }
+        "checkStateConsistency" => check_state_consistency(state)
+            .map(|consistency| consistency_json(&consistency))
+            .map_err(|e| RpcError::new(HISTORY_UNAVAILABLE, format!("{:?}", e))),
+        _ => Err(RpcError::new(METHOD_NOT_FOUND, "Method not found")),
+    }
+}
+
+// Wrap a value in Solana's { "context": { "slot" }, "value" } response shape.
+fn with_context(state: &Arc<NodeState>, value: serde_json::Value) -> serde_json::Value {
+    let slot = state.db.lock().unwrap().slot();
+    serde_json::json!({ "context": { "slot": slot }, "value": value })
+}
+
+// A submitted transaction's JSON-RPC result: its signature, or an error
+// whose data carries the TransactionError as "err" (and the signature, if
+// it was recorded anyway).
+fn processed_result(outcome: Result<Processed, TransactionError>) -> Result<serde_json::Value, RpcError> {
+    match outcome {
+        Ok(Processed { signature, result: Ok(()), .. }) => Ok(signature.into()),
+        Ok(Processed { signature, result: Err(e), .. }) => Err(RpcError {
+            code:    TRANSACTION_REJECTED,
+            message: format!("Transaction failed: {:?}", e),
+            data:    Some(serde_json::json!({ "err": e.to_json(), "signature": signature })),
+        }),
+        Err(e) => Err(RpcError {
+            code:    TRANSACTION_REJECTED,
+            message: format!("Transaction rejected: {:?}", e),
+            data:    Some(serde_json::json!({ "err": e.to_json() })),
+        }),
+    }
+}
+
+// The transaction at params[0], legacy or v0, encoded as
+// params[1].encoding says (base58 by default), and its size in bytes.
+fn rpc_transaction_param(params: &serde_json::Value) -> Result<(VersionedTransaction, usize), RpcError> {
+    let encoded = params[0]
+        .as_str()
+        .ok_or_else(|| RpcError::invalid_params("expected an encoded transaction"))?;
+    let bytes = match params[1]["encoding"].as_str() {
+        None | Some("base58") => bs58::decode(encoded).into_vec().ok(),
+        Some("base64")        => BASE64_STANDARD.decode(encoded).ok(),
+        Some(other) => return Err(RpcError::invalid_params(format!("unsupported encoding: {}", other))),
+    }
+    .ok_or_else(|| RpcError::invalid_params("transaction is not validly encoded"))?;
+    let tx = VersionedTransaction::deserialize(&bytes)
+        .map_err(|e| RpcError::invalid_params(format!("failed to deserialize transaction: {:?}", e)))?;
+    Ok((tx, bytes.len()))
+}
+
+// The base58 pubkey at params[index].
+fn rpc_pubkey_param(params: &serde_json::Value, index: usize) -> Result<Pubkey, RpcError> {
+    params[index]
+        .as_str()
+        .and_then(Pubkey::from_base58)
+        .ok_or_else(|| RpcError::invalid_params(format!("param {} must be a base58 pubkey", index)))
+}
+
+fn json_rpc_response(
+    id: serde_json::Value,
+    outcome: Result<serde_json::Value, RpcError>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let body = match outcome {
+        Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => {
+            let mut error = serde_json::json!({ "code": e.code, "message": e.message });
+            if let Some(data) = e.data {
+                error["data"] = data;
+            }
+            serde_json::json!({ "jsonrpc": "2.0", "id": id, "error": error })
+        }
+    };
+    json_response(200, &body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::{node, signed};
+    use crate::programs::system;
+    use crate::types::keys;
+
+    #[test]
+    fn dispatch_maps_outcomes_to_json_rpc_codes() {
+        let state = node();
+        let code  = |method: &str, params: serde_json::Value| dispatch(method, &params, &state).unwrap_err().code;
+
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let tx      = signed(&state, 1, &[], &[system::transfer(&from, &to, 10)]);
+        let encoded = bs58::encode(tx.serialize()).into_string();
+        let sent    = dispatch("sendTransaction", &serde_json::json!([encoded]), &state).unwrap();
+        assert_eq!(sent, serde_json::Value::from(tx.id_base58()));
+        assert_eq!(code("sendTransaction", serde_json::json!([encoded])), TRANSACTION_REJECTED);
+
+        assert_eq!(code("getBalance", serde_json::json!(["not a pubkey"])), INVALID_PARAMS);
+        assert_eq!(code("sendTransaction", serde_json::json!([encoded, { "encoding": "hex" }])), INVALID_PARAMS);
+        assert_eq!(code("getBlockProduction", serde_json::json!([])), METHOD_NOT_FOUND);
+    }
+}
@@ -0,0 +1,371 @@
+// ---------------------------------------------------------------------------
+// Ledger — recording transactions into PoH, the ledger file, replaying it
+// at startup, and checking that the replay matches the live state.
+// ---------------------------------------------------------------------------
+
+use std::sync::Arc;
+
+use crate::programs::system::{self, SystemInstruction};
+use crate::runtime::accounts_db::AccountsDB;
+use crate::runtime::bank::{self, BlockhashQueue, MAX_RECENT_BLOCKHASHES, StatusCache};
+use crate::runtime::fee::FeeCalculator;
+use crate::runtime::log_collector::LogCollector;
+use crate::runtime::poh::{self, Entry, LedgerError, PohGenerator};
+use crate::runtime::transaction_error::TransactionError;
+use crate::runtime::transaction_index::{TransactionIndex, TransactionStatus};
+use crate::types::account::{Pubkey, Slot};
+use crate::types::builder::TransactionBuilder;
+use crate::types::transaction::{Hash, Transaction};
+
+use super::{GENESIS_SEED, NodeState};
+use super::transactions::Processed;
+
+// ---------------------------------------------------------------------------
+// airdrop — mint lamports into any account.
+//
+// Not a SystemProgram transfer: the faucet holds nothing, so the lamports
+// are credited directly in AccountsDB. What goes into PoH is a Transfer
+// from the faucet, signed by it, so the airdrop has a signature like any
+// other transaction and the ledger accounts for every minted lamport.
+// ---------------------------------------------------------------------------
+pub(super) fn airdrop(state: &Arc<NodeState>, pubkey: Pubkey, lamports: u64) -> Result<Processed, TransactionError> {
+    if lamports > state.airdrop_cap {
+        println!("[rpc] airdrop rejected: {} lamports exceeds cap {}", lamports, state.airdrop_cap);
+        return Err(TransactionError::AirdropCapExceeded { lamports, cap: state.airdrop_cap });
+    }
+
+    println!("[rpc] airdrop  {} lamports → {}", lamports, pubkey.to_base58());
+
+    // --- Build and sign the record of the airdrop ---
+    let (faucet, faucet_key) = &state.faucet;
+    let recent_blockhash = state.blockhash_queue.lock().unwrap().last_hash().expect("genesis registers a blockhash");
+    let message = TransactionBuilder::new()
+        .recent_blockhash(recent_blockhash)
+        .add_transfer(faucet, &pubkey, lamports)
+        .build()
+        .expect("a transfer has three account keys");
+    let mut tx = Transaction::new(message, Vec::new());
+    tx.sign(&[faucet_key]).expect("the faucet is the transfer's only signer");
+    let signature = tx.id_base58();
+
+    // --- Mint ---
+    mint(&mut state.db.lock().unwrap(), pubkey, lamports);
+
+    // --- Record into PoH ---
+    let entry_hash = record(state, &mut state.poh.lock().unwrap(), tx, None, " (airdrop)");
+
+    Ok(Processed { signature, entry_hash, result: Ok(()), failed_instruction: None })
+}
+
+// ---------------------------------------------------------------------------
+// record — stamp one transaction into PoH and index it by signature with
+// `err` as its outcome. Returns the entry hash, hex-encoded.
+// ---------------------------------------------------------------------------
+pub(super) fn record(
+    state: &Arc<NodeState>,
+    poh: &mut PohGenerator,
+    tx: Transaction,
+    err: Option<TransactionError>,
+    note: &str,
+) -> String {
+    let signature = tx.signature();
+    poh.record(vec![tx]);
+    let idx   = poh.first_entry_index() + poh.entries.len() - 1;
+    let entry = poh.entries.last().unwrap();
+    state.transaction_index.lock().unwrap().insert(signature, TransactionStatus {
+        slot: poh.slot(),
+        entry_index: idx,
+        err,
+    });
+    if state.log_entries {
+        print_entry(idx, entry);
+    } else {
+        println!("[poh]  record hashes={:<6} hash={} txs=1{}",
+            entry.num_hashes, hex::encode(&entry.hash[..8]), note);
+    }
+    let entry_hash = hex::encode(entry.hash);
+    if let Some(path) = &state.ledger_path {
+        append_entries(poh, path);
+    }
+    entry_hash
+}
+
+// ---------------------------------------------------------------------------
+// register_blockhash — make a new entry's hash a recent blockhash if it is
+// the tick that ends a slot, the one hash per slot BlockhashQueue keeps.
+// The ticker and replay both go through here, so they register the same
+// hashes.
+// ---------------------------------------------------------------------------
+pub(super) fn register_blockhash(queue: &mut BlockhashQueue, entry: &Entry) {
+    if entry.slot_boundary {
+        queue.register_hash(Hash::new(entry.hash));
+    }
+}
+
+// ---------------------------------------------------------------------------
+// append_entries — persist new PoH entries to the ledger file.
+//
+// A failed write is reported but does not stop the node: the entries stay
+// in memory and go out with the next successful append.
+// ---------------------------------------------------------------------------
+pub(super) fn append_entries(poh: &mut PohGenerator, path: &std::path::Path) {
+    if let Err(e) = poh.append_to_ledger(path) {
+        println!("[ledger] failed to write {}: {}", path.display(), e);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// replay_entries — rebuild account state from a replayed ledger.
+//
+// Runs each recorded transaction again, in ledger order, against the
+// genesis accounts. Signatures, blockhashes and middleware are not checked
+// again: the ledger only ever holds transactions that passed them, and
+// replay_ledger() has already verified the PoH chain. What is repeated is
+// everything that changed state:
+//   - an airdrop (a transfer signed by the faucet) mints into its recipient
+//   - anything else pays its fee, then executes — a failure is ignored,
+//     just as it was the first time — against the hash of the entry before
+//     its own as the current blockhash, which is what it saw live
+//   - a tick that ends a slot advances the AccountsDB slot
+// Each slot's closing tick is registered as a recent blockhash along the
+// way (see register_blockhash), and
+// every transaction that paid its fee goes into `status_cache`, so one
+// recorded before a restart cannot be submitted again after it. Every
+// transaction also goes into `transaction_index` with its outcome.
+// ---------------------------------------------------------------------------
+#[allow(clippy::too_many_arguments)] // one per piece of state replay rebuilds
+pub(super) fn replay_entries(
+    entries: &[Entry],
+    db: &mut AccountsDB,
+    blockhash_queue: &mut BlockhashQueue,
+    status_cache: &mut StatusCache,
+    transaction_index: &mut TransactionIndex,
+    fee_calculator: &FeeCalculator,
+    faucet: &Pubkey,
+    genesis_hash: [u8; 32],
+) {
+    let mut slot      = db.slot();
+    let mut blockhash = Hash::new(genesis_hash);
+    for (entry_index, entry) in entries.iter().enumerate() {
+        if entry.slot_boundary {
+            slot += 1;
+            db.set_slot(slot);
+        }
+
+        for tx in &entry.transactions {
+            let status = |err| TransactionStatus { slot, entry_index, err };
+            if tx.message.fee_payer() == Some(faucet) {
+                let ix = &tx.message.instructions[0];
+                if let Ok(SystemInstruction::Transfer { lamports }) = system::decode(&ix.data) {
+                    mint(db, tx.message.account_keys[1], lamports);
+                }
+                transaction_index.insert(tx.signature(), status(None));
+                continue;
+            }
+
+            let Ok(fee) = bank::calculate_fee(&tx.message, fee_calculator) else {
+                continue;
+            };
+            if bank::collect_fee(&tx.message, fee, db).is_ok() {
+                let result = bank::execute_transaction(tx, db, &blockhash, &mut LogCollector::new());
+                status_cache.insert(tx);
+                transaction_index.insert(tx.signature(), status(result.err().map(TransactionError::from)));
+            }
+        }
+
+        blockhash = Hash::new(entry.hash);
+        register_blockhash(blockhash_queue, entry);
+    }
+    status_cache.purge(blockhash_queue);
+}
+
+// ---------------------------------------------------------------------------
+// mint — credit lamports to an account out of thin air, creating it if it
+// does not exist. Only airdrops do this.
+// ---------------------------------------------------------------------------
+fn mint(db: &mut AccountsDB, pubkey: Pubkey, lamports: u64) {
+    let mut account = db.load(&pubkey).cloned().unwrap_or_default();
+    let before      = account.lamports();
+    account.set_lamports(before.saturating_add(lamports));
+    println!("[db]   {:?}  {} → {} lamports", pubkey, before, account.lamports());
+    db.store(pubkey, account);
+}
+
+// ---------------------------------------------------------------------------
+// check_state_consistency — does replaying the ledger give the live state?
+//
+// Every change to accounts is a ledger entry, so replaying the whole
+// ledger from genesis (replay_entries, into a fresh AccountsDB) must land
+// on exactly the live accounts. If the state roots differ, something
+// changed accounts without going through the ledger, or execution is not
+// deterministic — either way the node's state cannot be trusted. That is
+// logged loudly, and the process exits if halt_on_divergence is set.
+//
+// The entries come from memory while no slot has been purged yet, and
+// from the ledger file after that. PoH stays locked until the live root
+// has been read, so no entry lands between the two; the replay itself
+// runs unlocked.
+// ---------------------------------------------------------------------------
+pub(super) struct Consistency {
+    live_root:     [u8; 32],
+    replayed_root: [u8; 32],
+    entries:       usize,
+}
+
+#[derive(Debug)]
+pub(super) enum ConsistencyError {
+    /// Entries have been purged from memory and there is no ledger file
+    /// to read them from.
+    HistoryPurged { first_available_slot: Slot },
+    /// The ledger file could not be replayed.
+    Ledger(LedgerError),
+    /// The ledger file holds fewer entries than the node has produced: a
+    /// write to it failed and has not been retried yet.
+    LedgerBehind { persisted: usize, produced: usize },
+}
+
+pub(super) fn check_state_consistency(state: &Arc<NodeState>) -> Result<Consistency, ConsistencyError> {
+    let (entries, live_root, hash_algorithm) = {
+        let poh      = state.poh.lock().unwrap();
+        let produced = poh.first_entry_index() + poh.entries.len();
+        let entries  = match &state.ledger_path {
+            _ if poh.first_entry_index() == 0 => poh.entries.clone(),
+            Some(path) => {
+                let entries = poh::replay_ledger(GENESIS_SEED, path, poh.hashes_per_tick, poh.ticks_per_slot)
+                    .map_err(ConsistencyError::Ledger)?
+                    .entries;
+                if entries.len() < produced {
+                    return Err(ConsistencyError::LedgerBehind { persisted: entries.len(), produced });
+                }
+                entries
+            }
+            None => {
+                return Err(ConsistencyError::HistoryPurged { first_available_slot: poh.first_available_slot() });
+            }
+        };
+        let db = state.db.lock().unwrap();
+        (entries, db.state_root(), db.hash_algorithm())
+    };
+
+    let mut db = state.genesis.create_db(hash_algorithm);
+    let mut blockhash_queue = BlockhashQueue::new(MAX_RECENT_BLOCKHASHES);
+    replay_entries(
+        &entries, &mut db, &mut blockhash_queue, &mut StatusCache::new(), &mut TransactionIndex::new(),
+        &state.fee_calculator, &state.faucet.0, state.genesis_hash,
+    );
+    let replayed_root = db.state_root();
+
+    if replayed_root == live_root {
+        println!("[check] state consistent with {} replayed entries  root={}",
+            entries.len(), hex::encode(&live_root[..8]));
+    } else {
+        println!("[check] !!! STATE DIVERGENCE !!! live root {} but replaying {} entries gives {}",
+            hex::encode(live_root), entries.len(), hex::encode(replayed_root));
+        if state.halt_on_divergence {
+            println!("[check] halting: live state no longer matches the ledger");
+            std::process::exit(1);
+        }
+    }
+
+    Ok(Consistency { live_root, replayed_root, entries: entries.len() })
+}
+
+// A consistency check's result, as both REST and JSON-RPC return it.
+pub(super) fn consistency_json(consistency: &Consistency) -> serde_json::Value {
+    serde_json::json!({
+        "consistent":    consistency.live_root == consistency.replayed_root,
+        "live_root":     hex::encode(consistency.live_root),
+        "replayed_root": hex::encode(consistency.replayed_root),
+        "entries":       consistency.entries,
+    })
+}
+
+// Why a consistency check could not run, with the numbers behind it.
+pub(super) fn consistency_error_json(error: &ConsistencyError) -> serde_json::Value {
+    match error {
+        ConsistencyError::HistoryPurged { first_available_slot } => serde_json::json!({
+            "error": "HistoryPurged", "first_available_slot": first_available_slot,
+        }),
+        ConsistencyError::Ledger(e) => serde_json::json!({ "error": format!("Ledger({:?})", e) }),
+        ConsistencyError::LedgerBehind { persisted, produced } => serde_json::json!({
+            "error": "LedgerBehind", "persisted": persisted, "produced": produced,
+        }),
+    }
+}
+
+
+// ---------------------------------------------------------------------------
+// print_entry
+// ---------------------------------------------------------------------------
+pub(super) fn print_entry(idx: usize, entry: &crate::runtime::poh::Entry) {
+    let kind = if entry.transactions.is_empty() { "TICK  " } else { "RECORD" };
+    println!(
+        "[entry #{:<4}] {}  hashes={:<6}  hash={}{}",
+        idx, kind, entry.num_hashes, hex::encode(entry.hash),
+        if entry.slot_boundary { "  [slot end]" } else { "" },
+    );
+    for (ti, tx) in entry.transactions.iter().enumerate() {
+        println!("  tx[{}]:", ti);
+        println!("    account_keys ({}):", tx.message.account_keys.len());
+        for (i, key) in tx.message.account_keys.iter().enumerate() {
+            println!("      [{}] {:?}  writable={}  signer={}",
+                i, key, tx.message.is_writable(i), tx.message.is_signer(i));
+        }
+        for (ii, ix) in tx.message.instructions.iter().enumerate() {
+            println!("    ix[{}]: program_id_index={}  accounts={:?}  data={} bytes",
+                ii, ix.program_id_index, ix.accounts, ix.data.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::system::{self, SYSTEM_PROGRAM_ID};
+    use crate::types::account::AccountSharedData;
+    use crate::types::keys;
+
+    use super::super::tests::{node, signed};
+    use super::super::transactions::process_transaction;
+
+    #[test]
+    fn airdrop_over_the_cap_mints_nothing() {
+        let state     = node();
+        let recipient = Pubkey([42; 32]);
+        let cap       = state.airdrop_cap;
+        let balance   = |state: &NodeState| state.db.lock().unwrap().load(&recipient).map_or(0, |a| a.lamports());
+
+        let result = airdrop(&state, recipient, cap + 1);
+        assert_eq!(result.err(), Some(TransactionError::AirdropCapExceeded { lamports: cap + 1, cap }));
+        assert_eq!(balance(&state), 0);
+
+        let processed = airdrop(&state, recipient, cap).unwrap();
+        assert_eq!(processed.result, Ok(()));
+        assert_eq!(balance(&state), cap);
+    }
+
+    #[test]
+    fn replaying_the_entries_reaches_the_live_state_root() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let balance    = state.db.lock().unwrap().load(&from).unwrap().lamports();
+
+        // A transfer, a failed transfer that still pays its fee, an airdrop
+        // and a tick between them.
+        let tx = signed(&state, 1, &[], &[system::transfer(&from, &to, 10)]);
+        assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+        let overdraft = signed(&state, 1, &[], &[system::transfer(&from, &to, balance)]);
+        assert!(process_transaction(overdraft, &state).unwrap().result.is_err());
+        state.poh.lock().unwrap().tick();
+        airdrop(&state, Pubkey([42; 32]), 1_000).unwrap();
+
+        let consistency = check_state_consistency(&state).unwrap();
+        assert_eq!(consistency.replayed_root, consistency.live_root);
+        assert_eq!(consistency.entries, 4);
+
+        // A change the ledger does not account for.
+        state.db.lock().unwrap().store(to, AccountSharedData::new(1, 0, SYSTEM_PROGRAM_ID));
+        let consistency = check_state_consistency(&state).unwrap();
+        assert_ne!(consistency.replayed_root, consistency.live_root);
+    }
+}
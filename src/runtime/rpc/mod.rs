@@ -0,0 +1,627 @@
+// ---------------------------------------------------------------------------
+// RPC server — the node's HTTP front end and the state it shares.
+//
+// start() brings the node up and serves POST / (JSON-RPC 2.0) plus the REST
+// endpoints kept for curl, on DEFAULT_BIND_ADDR unless --bind or --port say
+// otherwise. README documents every endpoint's request and response. The
+// handlers are grouped by what they touch:
+//
+//   transactions — /transfer, /transferBatch, /createAccount,
+//                  /sendTransaction, /simulateTransaction, and the
+//                  process_transaction pipeline they all share
+//   accounts     — account, transaction and airdrop endpoints
+//   chain        — genesis hash, slot, block height, blockhash, epoch
+//   ledger       — recording into PoH, the ledger file, replay and the
+//                  consistency check
+//   json_rpc     — POST /, mapping JSON-RPC methods onto the above
+//   util         — request parsing and signing helpers
+//
+// Unknown methods and paths are answered in the style of the caller:
+//   POST / with an unknown JSON-RPC method → JSON-RPC error -32601
+//   a known path with the wrong method → 405, with Allow: POST (or GET
+//   for /accounts)
+//   any other path → 404 { "error": "not found" }
+// A query string is ignored except by GET /accounts.
+//
+// State is shared between the PoH ticker thread, the server thread and
+// any accountSubscribe threads via Arc<Mutex<>>.
+// ---------------------------------------------------------------------------
+
+mod accounts;
+mod chain;
+mod json_rpc;
+mod ledger;
+mod transactions;
+mod util;
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use ed25519_dalek::SigningKey;
+use tiny_http::{Method, Response, Server};
+
+use crate::runtime::accounts_db::{AccountHashAlgorithm, AccountsDB, MAX_RETAINED_SLOTS};
+use crate::runtime::bank::{BlockhashQueue, MAX_RECENT_BLOCKHASHES, StatusCache};
+use crate::runtime::epoch_schedule::EpochSchedule;
+use crate::runtime::fee::FeeCalculator;
+use crate::runtime::genesis::{ClusterType, GenesisConfig, NUM_GENESIS_KEYPAIRS};
+use crate::runtime::leader_schedule::{LeaderSchedule, NUM_CONSECUTIVE_LEADER_SLOTS};
+use crate::runtime::mempool::{MAX_MEMPOOL_TXS_PER_SLOT, Mempool};
+use crate::runtime::poh::{self, LedgerError, PohError, PohGenerator};
+use crate::runtime::transaction_index::TransactionIndex;
+use crate::types::account::Pubkey;
+use crate::types::keys;
+use crate::types::transaction::{Hash, Transaction};
+
+use self::accounts::{
+    handle_account_subscribe, handle_check_state_consistency, handle_get_account_info,
+    handle_get_account_stats, handle_get_accounts, handle_get_balance, handle_get_minimum_balance,
+    handle_get_multiple_accounts, handle_get_program_accounts, handle_get_state_root,
+    handle_get_transaction, handle_request_airdrop,
+};
+use self::chain::{
+    handle_get_block_height, handle_get_epoch_info, handle_get_first_available_block,
+    handle_get_genesis_hash, handle_get_latest_blockhash, handle_get_slot,
+};
+use self::json_rpc::handle_json_rpc;
+use self::ledger::{append_entries, print_entry, register_blockhash, replay_entries};
+use self::transactions::{
+    handle_create_account, handle_send_transaction, handle_simulate_transaction, handle_transfer,
+    handle_transfer_batch, process_transaction,
+};
+use self::util::json_response;
+
+// Most lamports a single /requestAirdrop may mint unless --airdrop-cap says
+// otherwise: 2 SOL.
+pub const DEFAULT_AIRDROP_CAP: u64 = 2_000_000_000;
+
+// How many slots of PoH entries the node keeps in memory. Older slots are
+// purged by the ticker, and getFirstAvailableBlock moves forward with them.
+pub const MAX_LEDGER_SLOTS: u64 = 1_000;
+
+// PoH seed every node's chain starts from; its hash is the genesis hash.
+const GENESIS_SEED: &[u8] = b"solana-genesis";
+
+// Hashes between ticks unless --hashes-per-tick says otherwise.
+pub const DEFAULT_HASHES_PER_TICK: u64 = 100;
+
+// Milliseconds the ticker sleeps between ticks unless --tick-ms says
+// otherwise.
+pub const DEFAULT_TICK_MS: u64 = 500;
+
+// Address the RPC server listens on unless --bind or --port say otherwise:
+// every interface, port 8080.
+pub const DEFAULT_BIND_ADDR: SocketAddr = SocketAddr::new(
+    std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
+    8080,
+);
+
+// Longest the RPC server waits for a request before checking whether the
+// node is shutting down.
+const SHUTDOWN_POLL: Duration = Duration::from_millis(100);
+
+// Every path the server answers on with POST. /accounts, the one GET
+// route, is matched on its own. Anything else is a 404.
+const ROUTES: &[&str] = &[
+    "/", "/transfer", "/transferBatch", "/createAccount", "/sendTransaction", "/simulateTransaction",
+    "/requestAirdrop", "/getBalance", "/getAccountInfo", "/getMultipleAccounts", "/getProgramAccounts",
+    "/accountSubscribe",
+    "/getMinimumBalanceForRentExemption",
+    "/getTransaction", "/getStateRoot", "/getAccountStats",
+    "/checkStateConsistency", "/getGenesisHash", "/getFirstAvailableBlock", "/getSlot", "/getBlockHeight",
+    "/getLatestBlockhash", "/getEpochInfo",
+];
+
+// Accounts getAccountStats lists when the request does not say.
+const DEFAULT_ACCOUNT_STATS_LIMIT: usize = 20;
+
+// Seed of the faucet keypair that signs airdrop records. Like the genesis
+// accounts, it is derived from a fixed seed so every node agrees on it.
+const FAUCET_SEED: [u8; 32] = [0xfa; 32];
+
+// ---------------------------------------------------------------------------
+// Middleware — a node-specific check every transaction must pass.
+//
+// Registered at startup and run in order after the Bank's own checks and
+// before the fee is collected, so a rejected transaction costs its sender
+// nothing. Each sees the transaction and the current accounts and returns
+// Err(reason) to drop it — e.g. a spam filter or an allowlist of senders —
+// without the Bank having to know about it.
+// ---------------------------------------------------------------------------
+pub type Middleware = Box<dyn Fn(&Transaction, &AccountsDB) -> Result<(), String> + Send + Sync>;
+
+// ---------------------------------------------------------------------------
+// Shared state.
+//
+// keypairs maps the u8 genesis identifier (1–5) to:
+//   - the actual Ed25519-derived Pubkey stored in AccountsDB
+//   - the SigningKey used to sign transactions on behalf of that account
+// ---------------------------------------------------------------------------
+pub struct NodeState {
+    pub db:              Arc<Mutex<AccountsDB>>,
+    pub poh:             Arc<Mutex<PohGenerator>>,
+    pub keypairs:        HashMap<u8, (Pubkey, SigningKey)>,
+    pub log_entries:     bool,
+    pub cluster_type:    ClusterType,
+    /// What transactions pay before the priority fee. Starts at the
+    /// cluster's lamports_per_signature.
+    pub fee_calculator:  FeeCalculator,
+    /// What the chain started with; replays start from it again.
+    pub genesis:         GenesisConfig,
+    pub genesis_hash:    [u8; 32],
+    /// When the chain was created, in Unix seconds.
+    pub creation_time:   u64,
+    /// Recent slot hashes a transaction's recent_blockhash may name.
+    pub blockhash_queue: Arc<Mutex<BlockhashQueue>>,
+    /// Signatures of the transactions processed within the blockhash
+    /// window, so none of them can be submitted twice.
+    pub status_cache:    Arc<Mutex<StatusCache>>,
+    /// Where each recorded transaction sits in the ledger, by signature.
+    pub transaction_index: Arc<Mutex<TransactionIndex>>,
+    /// Transactions submitted with "queue": true, waiting for the next
+    /// slot boundary.
+    pub mempool:         Arc<Mutex<Mempool>>,
+    /// Development shortcut: accept transactions without checking their
+    /// signatures. Refused at startup on MainnetBeta.
+    pub skip_sig_verify: bool,
+    /// Signs the records of airdrops. Holds no lamports — airdrops mint.
+    pub faucet:          (Pubkey, SigningKey),
+    /// Most lamports a single /requestAirdrop may mint.
+    pub airdrop_cap:     u64,
+    /// Extra checks run on every transaction, in order.
+    pub middleware:      Vec<Middleware>,
+    /// File every new PoH entry is appended to, if persistence is on.
+    pub ledger_path:     Option<PathBuf>,
+    /// Which validator leads each slot. The genesis accounts stand in for
+    /// the validator set until there is more than one node.
+    pub leader_schedule: LeaderSchedule,
+    /// How slots group into epochs, for getEpochInfo.
+    pub epoch_schedule:  EpochSchedule,
+    /// Exit the process when checkStateConsistency finds that replay and
+    /// live state disagree, rather than keep serving corrupt state.
+    pub halt_on_divergence: bool,
+    /// Set by shutdown(); the ticker and the RPC server check it on every
+    /// pass of their loops and return once it is set.
+    pub shutdown:        Arc<AtomicBool>,
+}
+
+impl NodeState {
+    /// Genesis, or the replay of an existing ledger, and everything else
+    /// the node holds. Nothing runs yet: start() adds the ticker and the
+    /// server on top.
+    pub fn new(config: NodeConfig) -> Result<NodeState, StartError> {
+        let NodeConfig {
+            log_entries, cluster_type, skip_sig_verify, airdrop_cap, middleware, creation_time,
+            ledger_path, halt_on_divergence, account_hash, genesis, epoch_schedule, ..
+        } = config;
+
+        if skip_sig_verify && !cluster_type.permits_signature_bypass() {
+            return Err(StartError::SignatureBypassNotPermitted(cluster_type));
+        }
+
+        // --- Genesis ---
+        // The node holds the development keypairs 1 to 5 (see
+        // keys::genesis_keypair) whatever the genesis, so the REST endpoints can
+        // sign for them. The accounts themselves come from the GenesisConfig.
+        let keypairs: HashMap<u8, (Pubkey, SigningKey)> =
+            (1..=NUM_GENESIS_KEYPAIRS).map(|b| (b, keys::genesis_keypair(b))).collect();
+
+        println!("[genesis] cluster {}  created at {} (unix)  account hash {}",
+            cluster_type, creation_time, account_hash);
+        let mut db = genesis.create_db(account_hash);
+        for (pubkey, account) in &genesis.accounts {
+            let sol = account.lamports() / 1_000_000_000;
+            match keypairs.iter().find(|(_, (key, _))| key == pubkey) {
+                Some((b, _)) => println!("[genesis] account {} → {}  ({} SOL)", b, pubkey.to_base58(), sol),
+                None         => println!("[genesis] account   → {}  ({} SOL)", pubkey.to_base58(), sol),
+            }
+        }
+
+        // The genesis hash is the PoH seed hash — the chain value before the
+        // first tick. Every entry descends from it.
+        let poh = PohGenerator::new(GENESIS_SEED, genesis.hashes_per_tick, genesis.ticks_per_slot)
+            .map_err(StartError::Poh)?;
+        let genesis_hash = poh.last_hash();
+
+        let mut blockhash_queue = BlockhashQueue::new(MAX_RECENT_BLOCKHASHES);
+        blockhash_queue.register_hash(Hash::new(genesis_hash));
+        let mut status_cache = StatusCache::new();
+        let mut transaction_index = TransactionIndex::new();
+
+        let mut validators: Vec<(u8, Pubkey)> = keypairs.iter().map(|(b, (pubkey, _))| (*b, *pubkey)).collect();
+        validators.sort();
+        let leader_schedule = LeaderSchedule::new(
+            NUM_CONSECUTIVE_LEADER_SLOTS,
+            validators.into_iter().map(|(_, pubkey)| pubkey).collect(),
+        )
+        .expect("genesis has validators");
+
+        let fee_calculator = FeeCalculator::new(cluster_type.lamports_per_signature());
+
+        let faucet_key = SigningKey::from_bytes(&FAUCET_SEED);
+        let faucet     = (Pubkey(faucet_key.verifying_key().to_bytes()), faucet_key);
+        println!("[genesis] faucet    → {}  (cap {} lamports per airdrop)", faucet.0.to_base58(), airdrop_cap);
+
+        // --- Ledger replay ---
+        // An existing ledger picks up where the last run left off. Otherwise
+        // the chain starts fresh and the file is created on the first tick.
+        let poh = match &ledger_path {
+            Some(path) if path.exists() => {
+                let poh = poh::replay_ledger(GENESIS_SEED, path, genesis.hashes_per_tick, genesis.ticks_per_slot)
+                    .map_err(StartError::Ledger)?;
+                replay_entries(
+                    &poh.entries, &mut db, &mut blockhash_queue, &mut status_cache, &mut transaction_index,
+                    &fee_calculator, &faucet.0, genesis_hash,
+                );
+                println!("[ledger] replayed {} entries from {}  (slot {})",
+                    poh.entries.len(), path.display(), poh.slot());
+                poh
+            }
+            Some(path) => {
+                println!("[ledger] writing a new ledger to {}", path.display());
+                poh
+            }
+            None => poh,
+        };
+
+        if skip_sig_verify {
+            println!("[genesis] WARNING: signature verification is disabled");
+        }
+
+        Ok(NodeState {
+            db:  Arc::new(Mutex::new(db)),
+            poh: Arc::new(Mutex::new(poh)),
+            keypairs,
+            log_entries,
+            cluster_type,
+            fee_calculator,
+            genesis,
+            genesis_hash,
+            creation_time,
+            blockhash_queue: Arc::new(Mutex::new(blockhash_queue)),
+            status_cache:    Arc::new(Mutex::new(status_cache)),
+            transaction_index: Arc::new(Mutex::new(transaction_index)),
+            mempool:         Arc::new(Mutex::new(Mempool::new())),
+            skip_sig_verify,
+            faucet,
+            airdrop_cap,
+            middleware,
+            ledger_path,
+            leader_schedule,
+            epoch_schedule,
+            halt_on_divergence,
+            shutdown:        Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Ask the ticker thread and the RPC server to stop. They finish the
+    /// tick or request in hand first; join RunningNode::handle to wait.
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether shutdown() has been called.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// RunningNode — what start() hands back once the node is up.
+//
+// `handle` is the RPC server thread. It returns after NodeState::shutdown(),
+// once it has joined the ticker thread, so joining it waits for the whole
+// node to stop.
+// ---------------------------------------------------------------------------
+pub struct RunningNode {
+    pub state:  Arc<NodeState>,
+    pub handle: JoinHandle<()>,
+}
+
+// ---------------------------------------------------------------------------
+// StartError — reasons the node refuses to start.
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum StartError {
+    /// --skip-sig-verify was requested on a cluster that forbids it.
+    SignatureBypassNotPermitted(ClusterType),
+    /// The existing ledger file could not be replayed.
+    Ledger(LedgerError),
+    /// The RPC server could not listen on `addr`, e.g. because another
+    /// node already does.
+    Bind { addr: SocketAddr, reason: String },
+    /// The genesis PoH settings are invalid.
+    Poh(PohError),
+}
+
+// ---------------------------------------------------------------------------
+// NodeConfig — everything start() needs to know, one field per command-line
+// flag (see main.rs).
+// ---------------------------------------------------------------------------
+pub struct NodeConfig {
+    /// Dump every entry in full instead of one line per tick.
+    pub log_entries:        bool,
+    pub cluster_type:       ClusterType,
+    /// Accept transactions without checking their signatures. Refused on
+    /// clusters that forbid it.
+    pub skip_sig_verify:    bool,
+    /// Most lamports a single /requestAirdrop may mint.
+    pub airdrop_cap:        u64,
+    /// Extra checks run on every transaction, in order.
+    pub middleware:         Vec<Middleware>,
+    /// When the chain was created, in Unix seconds.
+    pub creation_time:      u64,
+    /// File to append entries to, replayed first if it already exists.
+    pub ledger_path:        Option<PathBuf>,
+    /// Exit when checkStateConsistency finds a divergence.
+    pub halt_on_divergence: bool,
+    pub account_hash:       AccountHashAlgorithm,
+    pub genesis:            GenesisConfig,
+    /// Milliseconds the ticker sleeps between ticks.
+    pub tick_ms:            u64,
+    /// Address the RPC server listens on.
+    pub bind:               SocketAddr,
+    pub epoch_schedule:     EpochSchedule,
+}
+
+// ---------------------------------------------------------------------------
+// start — bring the node up: genesis (or ledger replay), the PoH ticker and
+// the RPC server, each on its own thread. Returns as soon as both run.
+// ---------------------------------------------------------------------------
+pub fn start(config: NodeConfig) -> Result<RunningNode, StartError> {
+    let (bind, tick_ms) = (config.bind, config.tick_ms);
+
+    // Bound before anything else starts, so a node that cannot serve RPC
+    // fails here rather than after genesis and the ticker are running.
+    let server = Server::http(bind).map_err(|e| StartError::Bind { addr: bind, reason: e.to_string() })?;
+
+    let state = Arc::new(NodeState::new(config)?);
+
+    // --- PoH ticker thread ---
+    // Besides ticking, this thread advances the AccountsDB slot whenever a
+    // tick completes a slot and runs the background compaction pass, which
+    // drops account versions older than the retained slot window. It also
+    // purges PoH entries older than MAX_LEDGER_SLOTS, with their transactions'
+    // TransactionIndex statuses, and logs which leader
+    // owns the slot that is starting. At each slot boundary it also purges
+    // the status cache of signatures whose blockhash has expired, then
+    // drains the mempool: the highest-paying queued transactions, up to
+    // MAX_MEMPOOL_TXS_PER_SLOT, go through process_transaction in fee
+    // order, each into its own record entry.
+    let poh_ref      = Arc::clone(&state.poh);
+    let db_ref       = Arc::clone(&state.db);
+    let queue_ref    = Arc::clone(&state.blockhash_queue);
+    let cache_ref    = Arc::clone(&state.status_cache);
+    let index_ref    = Arc::clone(&state.transaction_index);
+    let log_entries_ = state.log_entries;
+    let ledger_path_ = state.ledger_path.clone();
+    let schedule     = state.leader_schedule.clone();
+    let shutdown     = Arc::clone(&state.shutdown);
+    let worker       = Arc::clone(&state);
+    let ticker = std::thread::spawn(move || {
+        while !shutdown.load(Ordering::SeqCst) {
+            let (slot_boundary, slot) = {
+                let mut poh = poh_ref.lock().unwrap();
+                poh.tick();
+                let idx   = poh.first_entry_index() + poh.entries.len() - 1;
+                let entry = poh.entries.last().unwrap();
+                register_blockhash(&mut queue_ref.lock().unwrap(), entry);
+                if log_entries_ {
+                    print_entry(idx, entry);
+                } else {
+                    println!(
+                        "[poh] tick  hashes={:<6} hash={}",
+                        entry.num_hashes,
+                        hex::encode(&entry.hash[..8])
+                    );
+                }
+
+                let slot_boundary = entry.slot_boundary;
+                let slot          = poh.slot();
+                if let Some(path) = &ledger_path_ {
+                    append_entries(&mut poh, path);
+                }
+                if slot_boundary {
+                    let purged = poh.purge_slots_before(slot.saturating_sub(MAX_LEDGER_SLOTS));
+                    if purged > 0 {
+                        index_ref.lock().unwrap().purge_before(poh.first_entry_index());
+                        println!("[poh]  purged {} entries (first available slot {})",
+                            purged, poh.first_available_slot());
+                    }
+                }
+                (slot_boundary, slot)
+            };
+
+            if slot_boundary {
+                let mut db = db_ref.lock().unwrap();
+                db.set_slot(slot);
+                let reclaimed = db.compact(slot.saturating_sub(MAX_RETAINED_SLOTS));
+                if reclaimed > 0 {
+                    println!("[db]   compacted {} account versions (slot {})", reclaimed, slot);
+                }
+                drop(db);
+                cache_ref.lock().unwrap().purge(&queue_ref.lock().unwrap());
+                println!("[slot] {}  leader {}", slot, schedule.leader_at(slot).to_base58());
+
+                let queued = worker.mempool.lock().unwrap().drain_for_slot(MAX_MEMPOOL_TXS_PER_SLOT);
+                if !queued.is_empty() {
+                    println!("[mempool] processing {} queued transactions", queued.len());
+                }
+                for tx in queued {
+                    let _ = process_transaction(tx, &worker);
+                }
+            }
+
+            std::thread::sleep(Duration::from_millis(tick_ms));
+        }
+    });
+
+    // --- HTTP server ---
+    // Waits for requests at most SHUTDOWN_POLL at a time, so it notices a
+    // shutdown even when no request arrives.
+    let addr = server.server_addr().to_ip().unwrap_or(bind);
+    println!("\n[rpc] listening on http://{}", addr);
+    println!("[rpc] POST /          JSON-RPC 2.0 (getBalance, getAccountInfo, sendTransaction, ...)");
+    println!("[rpc] POST /transfer  body: {{\"from\":1,\"to\":2,\"lamports\":1000000000}}");
+    println!("[rpc] POST /transferBatch body: {{\"transfers\":[{{\"from\":1,\"to\":2,\"lamports\":1000}}, ...]}}");
+    println!("[rpc] POST /createAccount body: {{\"funder\":1,\"new_account_seed\":42,\"lamports\":1000000,\"space\":0}}");
+    println!("[rpc] POST /sendTransaction body: {{\"transaction\":\"<base64>\",\"queue\":false}}");
+    println!("[rpc] POST /simulateTransaction body: {{\"transaction\":\"<base64>\"}}");
+    println!("[rpc] POST /requestAirdrop body: {{\"pubkey\":\"<base58>\",\"lamports\":1000000000}}");
+    println!("[rpc] POST /getBalance    body: {{\"pubkey\":\"<base58>\"}}");
+    println!("[rpc] POST /getAccountInfo body: {{\"pubkey\":\"<base58>\"}}");
+    println!("[rpc] POST /getMultipleAccounts body: {{\"pubkeys\":[\"<base58>\", ...]}}");
+    println!("[rpc] POST /getProgramAccounts body: {{\"owner\":\"<base58>\"}}");
+    println!("[rpc] GET  /accounts?offset=0&limit=100");
+    println!("[rpc] POST /accountSubscribe body: {{\"pubkey\":\"<base58>\",\"version\":0,\"timeout_ms\":30000}}");
+    println!("[rpc] POST /getMinimumBalanceForRentExemption body: {{\"data_len\":165}}");
+    println!("[rpc] POST /getTransaction body: {{\"signature\":\"<base58>\"}}");
+    println!("[rpc] POST /getStateRoot");
+    println!("[rpc] POST /getAccountStats body: {{\"limit\":20}} or {{\"pubkey\":\"<base58>\"}}");
+    println!("[rpc] POST /checkStateConsistency");
+    println!("[rpc] POST /getGenesisHash");
+    println!("[rpc] POST /getFirstAvailableBlock");
+    println!("[rpc] POST /getSlot");
+    println!("[rpc] POST /getBlockHeight");
+    println!("[rpc] POST /getLatestBlockhash");
+    println!("[rpc] POST /getEpochInfo\n");
+
+    let server_state = Arc::clone(&state);
+    let handle = std::thread::spawn(move || {
+        let state = server_state;
+        while !state.is_shutting_down() {
+            let mut request = match server.recv_timeout(SHUTDOWN_POLL) {
+                Ok(Some(request)) => request,
+                Ok(None)          => continue,
+                Err(e)            => {
+                    println!("[rpc] server stopped: {}", e);
+                    break;
+                }
+            };
+            // A long poll would stall every other request; give it a thread.
+            if request.method() == &Method::Post && request.url().split('?').next() == Some("/accountSubscribe") {
+                let state = Arc::clone(&state);
+                std::thread::spawn(move || {
+                    let response = route(&mut request, &state);
+                    let _ = request.respond(response);
+                });
+                continue;
+            }
+            let response = route(&mut request, &state);
+            let _ = request.respond(response);
+        }
+        let _ = ticker.join();
+        println!("[rpc] shut down");
+    });
+
+    Ok(RunningNode { state, handle })
+}
+
+// ---------------------------------------------------------------------------
+// route — answer one request by its method and path.
+// ---------------------------------------------------------------------------
+fn route(request: &mut tiny_http::Request, state: &Arc<NodeState>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    match (request.method(), path) {
+        (Method::Post, "/")               => handle_json_rpc(request, state),
+        (Method::Post, "/transfer")       => handle_transfer(request, state),
+        (Method::Post, "/transferBatch")  => handle_transfer_batch(request, state),
+        (Method::Post, "/createAccount")  => handle_create_account(request, state),
+        (Method::Post, "/sendTransaction") => handle_send_transaction(request, state),
+        (Method::Post, "/simulateTransaction") => handle_simulate_transaction(request, state),
+        (Method::Post, "/requestAirdrop") => handle_request_airdrop(request, state),
+        (Method::Post, "/getBalance")     => handle_get_balance(request, state),
+        (Method::Post, "/getAccountInfo") => handle_get_account_info(request, state),
+        (Method::Post, "/getMultipleAccounts") => handle_get_multiple_accounts(request, state),
+        (Method::Post, "/getProgramAccounts") => handle_get_program_accounts(request, state),
+        (Method::Get, "/accounts")        => handle_get_accounts(query, state),
+        (_, "/accounts") => json_response(405, r#"{"error":"method not allowed, use GET"}"#)
+            .with_header("Allow: GET".parse::<tiny_http::Header>().unwrap()),
+        (Method::Post, "/accountSubscribe") => handle_account_subscribe(request, state),
+        (Method::Post, "/getMinimumBalanceForRentExemption") => handle_get_minimum_balance(request),
+        (Method::Post, "/getTransaction") => handle_get_transaction(request, state),
+        (Method::Post, "/getStateRoot")   => handle_get_state_root(state),
+        (Method::Post, "/getAccountStats") => handle_get_account_stats(request, state),
+        (Method::Post, "/checkStateConsistency") => handle_check_state_consistency(state),
+        (Method::Post, "/getGenesisHash") => handle_get_genesis_hash(state),
+        (Method::Post, "/getFirstAvailableBlock") => handle_get_first_available_block(state),
+        (Method::Post, "/getSlot")        => handle_get_slot(state),
+        (Method::Post, "/getBlockHeight") => handle_get_block_height(state),
+        (Method::Post, "/getLatestBlockhash") => handle_get_latest_blockhash(state),
+        (Method::Post, "/getEpochInfo")   => handle_get_epoch_info(state),
+        (_, path) if ROUTES.contains(&path) => json_response(405, r#"{"error":"method not allowed, use POST"}"#)
+            .with_header("Allow: POST".parse::<tiny_http::Header>().unwrap()),
+        _ => json_response(404, r#"{"error":"not found"}"#),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::{Instruction, Message};
+
+    // A development node with the five genesis accounts and nothing
+    // running: tests tick PoH and call the handlers' helpers themselves.
+    pub(super) fn node() -> Arc<NodeState> {
+        Arc::new(NodeState::new(config()).unwrap())
+    }
+
+    // node()'s configuration, for tests that change part of it.
+    pub(super) fn config() -> NodeConfig {
+        NodeConfig {
+            log_entries:        false,
+            cluster_type:       ClusterType::Development,
+            skip_sig_verify:    false,
+            airdrop_cap:        DEFAULT_AIRDROP_CAP,
+            middleware:         Vec::new(),
+            creation_time:      0,
+            ledger_path:        None,
+            halt_on_divergence: false,
+            account_hash:       AccountHashAlgorithm::default(),
+            genesis:            GenesisConfig::new(ClusterType::Development, 1),
+            tick_ms:            DEFAULT_TICK_MS,
+            bind:               DEFAULT_BIND_ADDR,
+            epoch_schedule:     EpochSchedule::default(),
+        }
+    }
+
+    // `instructions` paid for by genesis account `payer`, against the newest
+    // blockhash and signed by `payer` and `signers`.
+    pub(super) fn signed(state: &NodeState, payer: u8, signers: &[u8], instructions: &[Instruction]) -> Transaction {
+        let (payer_key, payer_signer) = keys::genesis_keypair(payer);
+        let mut message = Message::compile(Some(&payer_key), instructions).unwrap();
+        message.recent_blockhash = state.blockhash_queue.lock().unwrap().last_hash().unwrap();
+        let mut keys = vec![payer_signer];
+        keys.extend(signers.iter().map(|&id| keys::genesis_keypair(id).1));
+        let mut tx = Transaction::new(message, Vec::new());
+        tx.sign(&keys.iter().collect::<Vec<_>>()).unwrap();
+        tx
+    }
+
+    // Lamports genesis account `id` holds.
+    pub(super) fn lamports(state: &NodeState, id: u8) -> u64 {
+        state.db.lock().unwrap().load(&keys::genesis_pubkey(id)).map_or(0, |account| account.lamports())
+    }
+
+    #[test]
+    fn genesis_pubkey_is_the_account_the_node_funds_and_signs_for() {
+        let state    = node();
+        let expected = ClusterType::Development.genesis_account_lamports();
+        for id in 1..=NUM_GENESIS_KEYPAIRS {
+            let pubkey = keys::genesis_pubkey(id);
+            assert_eq!(state.keypairs[&id].0, pubkey);
+            assert_eq!(Pubkey(state.keypairs[&id].1.verifying_key().to_bytes()), pubkey);
+            assert_eq!(lamports(&state, id), expected);
+        }
+        // Pinned, so a client deriving it from the seed [1; 32] keeps
+        // getting the node's account 1.
+        assert_eq!(keys::genesis_pubkey(1).to_base58(), "AKnL4NNf3DGWZJS6cPknBuEGnVsV4A4m5tgebLHaRSZ9");
+    }
+}
@@ -0,0 +1,936 @@
+// ---------------------------------------------------------------------------
+// Transaction endpoints and the pipeline behind them.
+//
+// /transfer, /transferBatch and /createAccount build and sign transactions
+// with the node's keys; /sendTransaction and /simulateTransaction take ones
+// the client signed. Every one of them ends in process_transaction (or, for
+// a dry run, simulate_transaction).
+// ---------------------------------------------------------------------------
+
+use std::sync::Arc;
+
+use base64::prelude::*;
+use tiny_http::Response;
+
+use crate::programs::compute_budget::{self, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::programs::loader::{self, LOADER_ID};
+use crate::programs::memo::{self, MEMO_PROGRAM_ID};
+use crate::programs::system::{self, SYSTEM_PROGRAM_ID};
+use crate::programs::token::{self, TOKEN_PROGRAM_ID};
+use crate::runtime::accounts_db::AccountsDB;
+use crate::runtime::bank;
+use crate::runtime::log_collector::LogCollector;
+use crate::runtime::svm;
+use crate::runtime::transaction_error::TransactionError;
+use crate::types::account::{AccountSharedData, Pubkey};
+use crate::types::transaction::{Hash, Transaction, VersionedMessage, VersionedTransaction};
+
+use super::NodeState;
+use super::accounts::account_json;
+use super::ledger::record;
+use super::util::{build_transaction, json_response, node_key_id, node_keypair, optional_u64, read_json_body};
+
+// ---------------------------------------------------------------------------
+// handle_transfer
+// ---------------------------------------------------------------------------
+pub(super) fn handle_transfer(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    // --- 1. Parse body ---
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+
+    let from_byte = match parsed["from"].as_u64().and_then(|v| u8::try_from(v).ok()) {
+        Some(b) => b,
+        None    => return json_response(400, r#"{"error":"\"from\" must be a u8 (1-5)"}"#),
+    };
+    let to_byte = match parsed["to"].as_u64().and_then(|v| u8::try_from(v).ok()) {
+        Some(b) => b,
+        None    => return json_response(400, r#"{"error":"\"to\" must be a u8 (1-5)"}"#),
+    };
+    let lamports = match parsed["lamports"].as_u64() {
+        Some(l) => l,
+        None    => return json_response(400, r#"{"error":"\"lamports\" must be a u64"}"#),
+    };
+    let fee_payer_byte = match optional_u64(&parsed, "fee_payer") {
+        Ok(v)  => match v.map(u8::try_from) {
+            None         => from_byte,
+            Some(Ok(b))  => b,
+            Some(Err(_)) => return json_response(400, r#"{"error":"\"fee_payer\" must be a u8 (1-5)"}"#),
+        },
+        Err(r) => return r,
+    };
+
+    // Optional ComputeBudget settings: a priority fee (micro-lamports per
+    // compute unit) and a cap on the total fee the sender is willing to pay.
+    let priority_fee = match optional_u64(&parsed, "priority_fee") {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let max_fee = match optional_u64(&parsed, "max_fee") {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+
+    // Resolve byte identifiers → actual Ed25519 pubkeys.
+    let from = match state.keypairs.get(&from_byte) {
+        Some((pk, _)) => *pk,
+        None => return json_response(400, r#"{"error":"\"from\" is not a known genesis account"}"#),
+    };
+    let to = match state.keypairs.get(&to_byte) {
+        Some((pk, _)) => *pk,
+        None => return json_response(400, r#"{"error":"\"to\" is not a known genesis account"}"#),
+    };
+    let fee_payer = match state.keypairs.get(&fee_payer_byte) {
+        Some((pk, _)) => *pk,
+        None => return json_response(400, r#"{"error":"\"fee_payer\" is not a known genesis account"}"#),
+    };
+
+    if fee_payer == from {
+        println!("[rpc] transfer  {} → {}  {} lamports", from_byte, to_byte, lamports);
+    } else {
+        println!("[rpc] transfer  {} → {}  {} lamports  (fee paid by {})",
+            from_byte, to_byte, lamports, fee_payer_byte);
+    }
+
+    // --- 2 & 3. Build the Transaction and sign it ---
+    // The fee payer signs first; with a separate fee payer the sender
+    // signs too, so the layout is [fee_payer, from, to, programs...];
+    // otherwise the sender pays and it is [from, to, programs...].
+    let mut instructions = vec![system::transfer(&from, &to, lamports)];
+    if let Some(price) = priority_fee {
+        instructions.push(compute_budget::set_compute_unit_price(price));
+    }
+    if let Some(max_fee) = max_fee {
+        instructions.push(compute_budget::set_max_fee(max_fee));
+    }
+
+    let tx = match build_transaction(state, &[fee_payer_byte, from_byte], instructions) {
+        Ok(tx) => tx,
+        Err(r) => return r,
+    };
+
+    processed_response(process_transaction(tx, state))
+}
+
+// ---------------------------------------------------------------------------
+// handle_transfer_batch
+//
+// Account layout (see TransactionBuilder): the distinct senders first (all
+// signers, the first one paying the fee), then the distinct recipients
+// that are not also senders, then the SystemProgram. An account that
+// appears in several transfers is listed once.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_transfer_batch(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    // --- 1. Parse body ---
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let items = match parsed["transfers"].as_array() {
+        Some(items) if !items.is_empty() => items,
+        _ => return json_response(400, r#"{"error":""transfers" must be a non-empty array"}"#),
+    };
+
+    let mut transfers = Vec::with_capacity(items.len());
+    for item in items {
+        let from = item["from"].as_u64().and_then(|v| u8::try_from(v).ok()).filter(|b| state.keypairs.contains_key(b));
+        let to   = item["to"].as_u64().and_then(|v| u8::try_from(v).ok()).and_then(|b| state.keypairs.get(&b));
+        let (Some(from), Some(to), Some(lamports)) = (from, to, item["lamports"].as_u64()) else {
+            return json_response(400,
+                r#"{"error":"each transfer needs "from" and "to" (genesis accounts 1-5) and "lamports" (u64)"}"#);
+        };
+        transfers.push((from, state.keypairs[&from].0, to.0, lamports));
+    }
+
+    println!("[rpc] transferBatch  {} transfers", transfers.len());
+
+    // --- 2 & 3. Build the Transaction, signed once per sender ---
+    let senders: Vec<u8> = transfers.iter().map(|(id, _, _, _)| *id).collect();
+    let instructions = transfers
+        .iter()
+        .map(|(_, from, to, lamports)| system::transfer(from, to, *lamports))
+        .collect();
+
+    let tx = match build_transaction(state, &senders, instructions) {
+        Ok(tx) => tx,
+        Err(r) => return r,
+    };
+
+    // --- 4. Process, then report each transfer ---
+    let outcome = process_transaction(tx, state);
+    let instructions: Vec<serde_json::Value> = match &outcome {
+        Ok(Processed { result: Err(e), failed_instruction, .. }) => (0..transfers.len())
+            .map(|i| match failed_instruction {
+                Some(failed) if i == *failed => serde_json::json!({ "ok": false, "error": e.to_json() }),
+                Some(failed) if i > *failed  => serde_json::json!({ "ok": false, "error": "not executed" }),
+                _ => serde_json::json!({ "ok": false, "error": "rolled back" }),
+            })
+            .collect(),
+        Ok(_)  => vec![serde_json::json!({ "ok": true }); transfers.len()],
+        Err(_) => return processed_response(outcome),
+    };
+
+    let (code, mut body) = processed_body(&outcome);
+    body["instructions"] = instructions.into();
+    json_response(code, &body.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// handle_create_account — create an account with SystemProgram CreateAccount.
+//
+// The new account's key comes from new_account_seed (see node_keypair), so
+// the node can sign for it: CreateAccount needs both the funder and the new
+// account to sign.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_create_account(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    // --- 1. Parse body ---
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+
+    // The funder is a byte identifier or the base58 address of a key the
+    // node holds.
+    let funder_byte = match &parsed["funder"] {
+        serde_json::Value::String(address) => match Pubkey::from_base58(address).and_then(|pk| node_key_id(state, &pk)) {
+            Some(b) => b,
+            None    => return json_response(400, r#"{"error":"\"funder\" is not an account the node can sign for"}"#),
+        },
+        value => match value.as_u64().and_then(|v| u8::try_from(v).ok()) {
+            Some(b) => b,
+            None    => return json_response(400, r#"{"error":"\"funder\" must be a u8 or a base58 pubkey"}"#),
+        },
+    };
+    let seed = match parsed["new_account_seed"].as_u64().and_then(|v| u8::try_from(v).ok()) {
+        Some(b) => b,
+        None    => return json_response(400, r#"{"error":"\"new_account_seed\" must be a u8"}"#),
+    };
+    let (Some(lamports), Some(space)) = (parsed["lamports"].as_u64(), parsed["space"].as_u64()) else {
+        return json_response(400, r#"{"error":"\"lamports\" and \"space\" must be u64s"}"#);
+    };
+    let owner = match &parsed["owner"] {
+        serde_json::Value::Null => SYSTEM_PROGRAM_ID,
+        value => match value.as_str().and_then(Pubkey::from_base58) {
+            Some(owner) => owner,
+            None        => return json_response(400, r#"{"error":"\"owner\" must be a base58 pubkey"}"#),
+        },
+    };
+
+    let (Some((funder, _)), Some((new_account, _))) = (node_keypair(state, funder_byte), node_keypair(state, seed)) else {
+        return json_response(400, r#"{"error":"the faucet cannot sign transactions"}"#);
+    };
+
+    println!("[rpc] createAccount  {} → {}  {} lamports  {} bytes  owner {}",
+        funder_byte, new_account.to_base58(), lamports, space, owner.to_base58());
+
+    // --- 2 & 3. Build the Transaction, signed by funder and new account ---
+    let instruction = system::create_account(&funder, &new_account, lamports, space, &owner);
+    let tx = match build_transaction(state, &[funder_byte, seed], vec![instruction]) {
+        Ok(tx) => tx,
+        Err(r) => return r,
+    };
+
+    // --- 4. Process ---
+    let (code, mut body) = processed_body(&process_transaction(tx, state));
+    body["pubkey"] = new_account.to_base58().into();
+    json_response(code, &body.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// handle_send_transaction — submit a transaction signed elsewhere.
+//
+// The body carries the wire-format transaction, base64-encoded. The node
+// never sees the signer's keys; it only checks the signatures.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_send_transaction(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let (tx, len) = match transaction_param(&parsed, "sendTransaction") {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let queue = match &parsed["queue"] {
+        serde_json::Value::Null => false,
+        value => match value.as_bool() {
+            Some(queue) => queue,
+            None => return json_response(400, r#"{"error":"\"queue\" must be a bool"}"#),
+        },
+    };
+
+    println!("[rpc] sendTransaction  {} bytes  sig={}", len, tx.id_base58());
+
+    if queue {
+        let Some(tx) = tx.into_legacy() else {
+            return json_response(400, r#"{"error":"v0 transactions cannot be queued"}"#);
+        };
+        return match preflight(&tx, state, true) {
+            Ok(fee) => {
+                let signature = tx.id_base58();
+                match state.mempool.lock().unwrap().push(tx, fee) {
+                    Ok(evicted) => {
+                        if let Some(evicted) = evicted {
+                            println!("[mempool] evicted sig={}", evicted.id_base58());
+                        }
+                        println!("[mempool] queued  fee={}  sig={}", fee, signature);
+                        json_response(200, &serde_json::json!({ "ok": true, "queued": true, "signature": signature }).to_string())
+                    }
+                    Err(full) => json_response(
+                        503,
+                        &serde_json::json!({ "error": "mempool is full", "minFee": full.min_fee }).to_string(),
+                    ),
+                }
+            }
+            Err(e) => processed_response(Err(e)),
+        };
+    }
+    processed_response(process_versioned_transaction(tx, state))
+}
+
+// ---------------------------------------------------------------------------
+// handle_simulate_transaction — dry-run a transaction signed elsewhere.
+//
+// Same body as /sendTransaction. Always 200 once the transaction decodes:
+// a simulation that fails is still a successful simulation, and "err"
+// says why it would fail.
+// ---------------------------------------------------------------------------
+pub(super) fn handle_simulate_transaction(
+    request: &mut tiny_http::Request,
+    state: &Arc<NodeState>,
+) -> Response<std::io::Cursor<Vec<u8>>> {
+    let parsed = match read_json_body(request) {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let (tx, len) = match transaction_param(&parsed, "simulateTransaction") {
+        Ok(v)  => v,
+        Err(r) => return r,
+    };
+    let Some(tx) = tx.into_legacy() else {
+        return json_response(400, r#"{"error":"v0 transactions cannot be simulated"}"#);
+    };
+    let return_accounts = match return_accounts_param(&parsed["returnAccounts"], &tx) {
+        Ok(v)  => v,
+        Err(message) => return json_response(400, &serde_json::json!({ "error": message }).to_string()),
+    };
+
+    println!("[rpc] simulateTransaction  {} bytes  sig={}", len, tx.id_base58());
+
+    let simulated = simulated_json(&simulate_transaction(&tx, state, return_accounts.as_deref()));
+    json_response(200, &serde_json::json!({ "value": simulated }).to_string())
+}
+
+// The transaction in the "transaction" field of a parsed body, with a
+// legacy or v0 message, and its size in bytes. `endpoint` names the caller
+// in the log.
+fn transaction_param(
+    parsed: &serde_json::Value,
+    endpoint: &str,
+) -> Result<(VersionedTransaction, usize), Response<std::io::Cursor<Vec<u8>>>> {
+    let bytes = match parsed["transaction"].as_str().map(|t| BASE64_STANDARD.decode(t)) {
+        Some(Ok(bytes)) => bytes,
+        _ => return Err(json_response(400, r#"{"error":"\"transaction\" must be base64"}"#)),
+    };
+
+    match VersionedTransaction::deserialize(&bytes) {
+        Ok(tx) => Ok((tx, bytes.len())),
+        Err(e) => {
+            println!("[rpc] {} rejected: {:?}", endpoint, e);
+            Err(json_response(400, &format!(r#"{{"ok":false,"error":"{:?}"}}"#, e)))
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Processed — a transaction that made it into the ledger.
+//
+// Once the fee is collected a transaction is recorded into PoH whether or
+// not it executed successfully; `result` says which, and
+// `failed_instruction` which instruction it stopped at.
+// ---------------------------------------------------------------------------
+pub(super) struct Processed {
+    pub(super) signature:          String,
+    pub(super) entry_hash:         String,
+    pub(super) result:             Result<(), TransactionError>,
+    pub(super) failed_instruction: Option<usize>,
+}
+
+// ---------------------------------------------------------------------------
+// process_transaction — the pipeline every submitted transaction runs.
+//
+//   0. Bank::sanitize_transaction (e.g. no fee payer, an instruction
+//      index past account_keys, or not exactly one signature per signer;
+//      checked even with --skip-sig-verify), then
+//      Bank::check_account_limit (at most the cluster's account cap)
+//   1. Bank::verify_signatures (skipped with --skip-sig-verify)
+//   2. Bank::check_blockhash   (recent_blockhash must still be in the queue,
+//                               or be the nonce a durable-nonce tx advances),
+//      StatusCache::check      (the same transaction was not already processed)
+//   3. Bank::calculate_fee     (rejects if the fee exceeds max_fee)
+//      middleware chain        (any registered check may reject)
+//   4. Bank::collect_fee       (debits the fee payer; kept even on failure)
+//   5. Bank::execute_transaction (SVM::execute: load, dispatch, commit;
+//                               rolled back to a checkpoint on failure)
+//   6. Record into PoH — once the fee is paid the transaction is part of
+//      the ledger, whether or not execution succeeded — and into the
+//      StatusCache, so it cannot be processed again
+//
+// Step 2 actually runs after 3, under the same db lock as execution, so a
+// durable nonce cannot be used by two transactions at once. Anything
+// rejected in steps 0–4 is dropped without touching state and comes back
+// as Err(TransactionError).
+// ---------------------------------------------------------------------------
+pub(super) fn process_transaction(tx: Transaction, state: &Arc<NodeState>) -> Result<Processed, TransactionError> {
+    process(tx, state, true)
+}
+
+// ---------------------------------------------------------------------------
+// process_versioned_transaction — process_transaction for a legacy or v0
+// message.
+//
+// A v0 transaction's signatures cover its v0 bytes, so step 1 runs here,
+// on that form (Bank::verify_versioned). Bank::resolve_transaction then
+// expands its lookup tables into a legacy message, which runs the rest of
+// the pipeline. It is recorded into PoH in that resolved form, so neither
+// replay nor getTransaction needs the tables as they were.
+// ---------------------------------------------------------------------------
+pub(super) fn process_versioned_transaction(
+    tx: VersionedTransaction,
+    state: &Arc<NodeState>,
+) -> Result<Processed, TransactionError> {
+    let VersionedMessage::V0(_) = &tx.message else {
+        return process_transaction(tx.into_legacy().expect("a legacy message"), state);
+    };
+
+    // --- 1. Bank: verify signatures, over the v0 bytes ---
+    if state.skip_sig_verify {
+        println!("[bank] skipped   (signature verification disabled)");
+    } else {
+        match bank::verify_versioned(&tx) {
+            Ok(message_hash) => println!("[bank] verified  ✓  message={}", hex::encode(&message_hash.0[..8])),
+            Err(e) => {
+                println!("[bank] rejected: {:?}", e);
+                return Err(e.into());
+            }
+        }
+    }
+
+    // --- Bank: resolve the lookup tables ---
+    let resolved = bank::resolve_transaction(&tx, &state.db.lock().unwrap()).map_err(|e| {
+        println!("[bank] rejected: {:?}", e);
+        TransactionError::from(e)
+    })?;
+    println!("[bank] resolved  v0 message → {} account keys", resolved.message.account_keys.len());
+
+    process(resolved, state, false)
+}
+
+// process_transaction, checking the signatures in step 1 only if `verify`.
+fn process(tx: Transaction, state: &Arc<NodeState>, verify: bool) -> Result<Processed, TransactionError> {
+    let signature = tx.id_base58();
+
+    // --- 0, 1 & 3. Bank: sanitize, verify signatures, compute the fee ---
+    let fee = preflight(&tx, state, verify)?;
+
+    // PoH stays locked from here until the transaction is recorded, so no
+    // tick lands between execution and its entry: the blockhash execution
+    // sees is exactly the hash of the entry before it, as replay assumes.
+    let mut poh       = state.poh.lock().unwrap();
+    let blockhash     = Hash::new(poh.last_hash());
+
+    // --- 2, middleware, then 4 & 5. Bank: blockhash, collect the fee, SVM: execute ---
+    let result = {
+        let mut db = state.db.lock().unwrap();
+
+        let writable: Vec<Pubkey> = tx.message.account_keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| tx.message.is_writable(*i))
+            .map(|(_, key)| *key)
+            .collect();
+        let before: Vec<u64> = writable
+            .iter()
+            .map(|key| db.load(key).map(|a| a.lamports()).unwrap_or(0))
+            .collect();
+
+        // Checked under the db lock: a durable nonce must still be stored
+        // when the transaction that advances it executes, not just when it
+        // arrived, or two transactions could spend the same nonce.
+        // Checked under the PoH lock, which is held until the transaction
+        // is in the cache: two copies submitted at once cannot both pass.
+        if let Err(e) = state.status_cache.lock().unwrap().check(&tx) {
+            println!("[bank] rejected: {:?}", e);
+            return Err(e.into());
+        }
+
+        let mut logs = LogCollector::new();
+        let result   = execute(&tx, state, &mut db, fee, &blockhash, &mut logs)?;
+
+        for line in logs.into_messages() {
+            println!("[svm]    {}", line);
+        }
+
+        for (key, before) in writable.iter().zip(before) {
+            let after = db.load(key).map(|a| a.lamports()).unwrap_or(0);
+            println!("[svm]    {:?}  {} → {} lamports", key, before, after);
+        }
+        result
+    };
+
+    let failed_instruction = result.as_ref().err().map(svm::SvmError::instruction);
+    let result             = result.map_err(TransactionError::from);
+
+    // --- 6. Record into PoH and the status cache ---
+    state.status_cache.lock().unwrap().insert(&tx);
+    let entry_hash = record(state, &mut poh, tx, result.clone().err(), "");
+
+    Ok(Processed { signature, entry_hash, result, failed_instruction })
+}
+
+// Steps 0, 1 and 3 of process_transaction: the checks that need no
+// account state. Step 1 is skipped unless `verify`, for a transaction
+// whose signatures were already checked in another form. Returns the fee
+// the transaction will pay.
+fn preflight(tx: &Transaction, state: &Arc<NodeState>, verify: bool) -> Result<u64, TransactionError> {
+    // --- 0. Bank: sanitize ---
+    let sanitized = bank::sanitize_transaction(tx).and_then(|()| {
+        bank::check_account_limit(&tx.message, state.cluster_type.max_accounts_per_transaction())
+    });
+    if let Err(e) = sanitized {
+        println!("[bank] rejected: {:?}", e);
+        return Err(e.into());
+    }
+
+    // --- 1. Bank: verify signatures ---
+    if !verify {
+        // Already checked (see process_versioned_transaction).
+    } else if state.skip_sig_verify {
+        println!("[bank] skipped   (signature verification disabled)");
+    } else {
+        match tx.verify_and_hash() {
+            Ok(message_hash) => println!("[bank] verified  ✓  message={}", hex::encode(&message_hash.0[..8])),
+            Err(e) => {
+                println!("[bank] rejected: {:?}", e);
+                return Err(e.into());
+            }
+        }
+    }
+
+    // --- 3. Bank: compute the fee and enforce the client's max fee ---
+    bank::calculate_fee(&tx.message, &state.fee_calculator).map_err(|e| {
+        println!("[bank] rejected: {:?}", e);
+        TransactionError::from(e)
+    })
+}
+
+// Steps 2, middleware, 4 and 5 of process_transaction, against `db`.
+// Err if the transaction is rejected before the fee is taken; otherwise
+// the fee is collected and Ok carries the SVM's result.
+fn execute(
+    tx: &Transaction,
+    state: &Arc<NodeState>,
+    db: &mut AccountsDB,
+    fee: u64,
+    blockhash: &Hash,
+    logs: &mut LogCollector,
+) -> Result<Result<(), svm::SvmError>, TransactionError> {
+    if let Err(e) = bank::check_blockhash(&tx.message, &state.blockhash_queue.lock().unwrap(), db) {
+        println!("[bank] rejected: {:?}", e);
+        return Err(e.into());
+    }
+
+    for (i, check) in state.middleware.iter().enumerate() {
+        if let Err(reason) = check(tx, db) {
+            println!("[rpc]  rejected by middleware #{}: {}", i, reason);
+            return Err(TransactionError::RejectedByMiddleware(reason));
+        }
+    }
+
+    match bank::collect_fee(&tx.message, fee, db) {
+        Ok(payer) => println!("[bank] fee       {} lamports from {:?}", fee, payer),
+        Err(e)    => {
+            println!("[bank] rejected: {:?}", e);
+            return Err(e.into());
+        }
+    }
+
+    let result = bank::execute_transaction(tx, db, blockhash, logs);
+    match &result {
+        Ok(())  => println!("[svm]  executed ✓"),
+        Err(e)  => println!("[svm]  failed: {:?}", e),
+    }
+    Ok(result)
+}
+
+// ---------------------------------------------------------------------------
+// simulate_transaction — dry-run a transaction without committing it.
+//
+// Runs the same pipeline as process_transaction against a clone of
+// AccountsDB, then throws the clone away: nothing is stored, no fee is
+// kept and nothing is recorded into PoH. What comes back is what would
+// have happened — the error if any, the program log, the compute units
+// used and every account's balance afterwards, plus the whole post-state
+// of the accounts in `return_accounts` if the client asked for them.
+//
+// Builtin programs charge a fixed number of compute units per
+// instruction, as in Solana, so units are counted from the instructions
+// that ran rather than metered. The log is the SVM's LogCollector, cut
+// off at the same 10,000-byte budget as Solana's.
+// ---------------------------------------------------------------------------
+pub(super) struct Simulated {
+    err:            Option<TransactionError>,
+    logs:           Vec<String>,
+    units_consumed: u64,
+    post_balances:  Vec<(Pubkey, u64)>,
+    /// The requested accounts as the transaction left them. None if none
+    /// were requested or the transaction was rejected before executing.
+    post_accounts:  Option<Vec<(Pubkey, Option<AccountSharedData>)>>,
+}
+
+pub(super) fn simulate_transaction(tx: &Transaction, state: &Arc<NodeState>, return_accounts: Option<&[Pubkey]>) -> Simulated {
+    println!("[sim]  simulating sig={}", tx.id_base58());
+    let rejected = |err| Simulated {
+        err: Some(err), logs: Vec::new(), units_consumed: 0, post_balances: Vec::new(), post_accounts: None,
+    };
+
+    let fee = match preflight(tx, state, true) {
+        Ok(fee)  => fee,
+        Err(err) => return rejected(err),
+    };
+
+    let blockhash = Hash::new(state.poh.lock().unwrap().last_hash());
+    let mut db    = state.db.lock().unwrap().clone();
+
+    let mut logs = LogCollector::new();
+    let result   = match execute(tx, state, &mut db, fee, &blockhash, &mut logs) {
+        Ok(result) => result,
+        Err(err)   => return rejected(err),
+    };
+
+    // The instructions that ran: all of them, or up to the failed one.
+    let message = &tx.message;
+    let ran     = result.as_ref().err().map_or(message.instructions.len(), |e| e.instruction() + 1);
+    let failure = result.err().map(TransactionError::from);
+
+    let units_consumed = message.instructions
+        .iter()
+        .take(ran)
+        .map(|instruction| match message.account_keys.get(instruction.program_id_index as usize) {
+            Some(&SYSTEM_PROGRAM_ID)         => system::DEFAULT_COMPUTE_UNITS,
+            Some(&COMPUTE_BUDGET_PROGRAM_ID) => compute_budget::DEFAULT_COMPUTE_UNITS,
+            Some(&LOADER_ID)                 => loader::DEFAULT_COMPUTE_UNITS,
+            Some(&TOKEN_PROGRAM_ID)          => token::DEFAULT_COMPUTE_UNITS,
+            Some(&MEMO_PROGRAM_ID)           => memo::DEFAULT_COMPUTE_UNITS,
+            _ => 0,
+        })
+        .sum();
+
+    let post_balances = message.account_keys
+        .iter()
+        .map(|key| (*key, db.load(key).map(|a| a.lamports()).unwrap_or(0)))
+        .collect();
+
+    let post_accounts = return_accounts
+        .map(|keys| keys.iter().map(|key| (*key, db.load(key).cloned())).collect());
+
+    Simulated { err: failure, logs: logs.into_messages(), units_consumed, post_balances, post_accounts }
+}
+
+// The accounts a simulation should return whole, from its "returnAccounts"
+// option: true for every account the transaction references, or a list
+// of base58 addresses. None if the option is absent or false.
+pub(super) fn return_accounts_param(value: &serde_json::Value, tx: &Transaction) -> Result<Option<Vec<Pubkey>>, String> {
+    match value {
+        serde_json::Value::Null | serde_json::Value::Bool(false) => Ok(None),
+        serde_json::Value::Bool(true) => Ok(Some(tx.message.account_keys.clone())),
+        serde_json::Value::Array(addresses) => addresses
+            .iter()
+            .map(|address| address.as_str().and_then(Pubkey::from_base58))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or_else(|| "\"returnAccounts\" must list base58 pubkeys".to_string()),
+        _ => Err("\"returnAccounts\" must be true or a list of base58 pubkeys".to_string()),
+    }
+}
+
+// A simulation in the shape of Solana's simulateTransaction value.
+pub(super) fn simulated_json(simulated: &Simulated) -> serde_json::Value {
+    let accounts: Vec<serde_json::Value> = simulated.post_balances
+        .iter()
+        .map(|(key, lamports)| serde_json::json!({ "pubkey": key.to_base58(), "lamports": lamports }))
+        .collect();
+    let returned = simulated.post_accounts.as_ref().map(|post_accounts| {
+        post_accounts
+            .iter()
+            .map(|(key, account)| serde_json::json!({ "pubkey": key.to_base58(), "account": account_json(account.as_ref()) }))
+            .collect::<Vec<_>>()
+    });
+    serde_json::json!({
+        "err":            simulated.err.as_ref().map(TransactionError::to_json),
+        "logs":           simulated.logs,
+        "unitsConsumed":  simulated.units_consumed,
+        "accounts":       accounts,
+        "returnAccounts": returned,
+    })
+}
+
+
+// ---------------------------------------------------------------------------
+// processed_response — the REST reply for a submitted transaction.
+//
+//   recorded and executed → 200 { "ok": true, "signature", "entry_hash" }
+//   recorded but failed   → 400 { "ok": false, "signature", "entry_hash", "error" }
+//   rejected              → 400 { "ok": false, "error" }
+//
+// "error" is the TransactionError in Solana's JSON shape, e.g.
+// { "InstructionError": [0, { "Custom": 1 }] } for an overdraft.
+// ---------------------------------------------------------------------------
+pub(super) fn processed_response(outcome: Result<Processed, TransactionError>) -> Response<std::io::Cursor<Vec<u8>>> {
+    let (code, body) = processed_body(&outcome);
+    json_response(code, &body.to_string())
+}
+
+fn processed_body(outcome: &Result<Processed, TransactionError>) -> (u32, serde_json::Value) {
+    match outcome {
+        Ok(Processed { signature, entry_hash, result: Ok(()), .. }) => (200, serde_json::json!({
+            "ok": true, "signature": signature, "entry_hash": entry_hash,
+        })),
+        Ok(Processed { signature, entry_hash, result: Err(e), .. }) => (400, serde_json::json!({
+            "ok": false, "signature": signature, "entry_hash": entry_hash, "error": e.to_json(),
+        })),
+        Err(e) => (400, serde_json::json!({ "ok": false, "error": e.to_json() })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::nonce::{NONCE_STATE_SIZE, NonceState};
+    use crate::types::keys;
+    use crate::types::transaction::Message;
+
+    use super::super::Middleware;
+    use super::super::tests::{config, lamports, node, signed};
+
+    #[test]
+    fn fee_over_max_fee_is_rejected_before_the_fee_is_collected() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let before     = lamports(&state, 1);
+        let fee        = state.fee_calculator.lamports_per_signature;
+        let capped     = |max_fee| {
+            signed(&state, 1, &[], &[system::transfer(&from, &to, 10), compute_budget::set_max_fee(max_fee)])
+        };
+
+        let result = process_transaction(capped(fee - 1), &state);
+        assert_eq!(result.err(), Some(TransactionError::FeeExceedsMax { fee, max_fee: fee - 1 }));
+        assert_eq!(lamports(&state, 1), before);
+
+        let processed = process_transaction(capped(fee), &state).unwrap();
+        assert_eq!(processed.result, Ok(()));
+        assert_eq!(lamports(&state, 1), before - fee - 10);
+    }
+
+    #[test]
+    fn sent_transaction_needs_a_known_blockhash_and_pays_its_fee_even_if_it_fails() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let (before, to_before) = (lamports(&state, 1), lamports(&state, 2));
+        let fee        = state.fee_calculator.lamports_per_signature;
+
+        let mut stale = signed(&state, 1, &[], &[system::transfer(&from, &to, 10)]);
+        stale.message.recent_blockhash = Hash([9; 32]);
+        stale.sign(&[&keys::genesis_keypair(1).1]).unwrap();
+        let result = process_versioned_transaction(stale.into(), &state);
+        assert_eq!(result.err(), Some(TransactionError::BlockhashNotFound));
+        assert_eq!(lamports(&state, 1), before);
+
+        let overdraft = signed(&state, 1, &[], &[system::transfer(&from, &to, before)]);
+        let processed = process_versioned_transaction(overdraft.into(), &state).unwrap();
+        assert!(processed.result.is_err());
+        assert_eq!(lamports(&state, 1), before - fee);
+        assert_eq!(lamports(&state, 2), to_before);
+    }
+
+    #[test]
+    fn resent_transaction_is_already_processed_and_charged_once() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let (before, to_before) = (lamports(&state, 1), lamports(&state, 2));
+        let fee        = state.fee_calculator.lamports_per_signature;
+
+        let tx = signed(&state, 1, &[], &[system::transfer(&from, &to, 10)]);
+        assert!(process_transaction(tx.clone(), &state).unwrap().result.is_ok());
+        assert_eq!(process_transaction(tx, &state).err(), Some(TransactionError::AlreadyProcessed));
+        assert_eq!(lamports(&state, 1), before - fee - 10);
+        assert_eq!(lamports(&state, 2), to_before + 10);
+    }
+
+    #[test]
+    fn middleware_runs_in_order_and_rejects_before_the_fee() {
+        // Both refuse transactions that touch genesis account 3; the first
+        // to refuse is the one that answers.
+        let blocked = keys::genesis_pubkey(3);
+        let mut config = config();
+        config.middleware = ["first", "second"]
+            .into_iter()
+            .map(|name| -> Middleware {
+                Box::new(move |tx: &Transaction, _: &AccountsDB| {
+                    match tx.message.account_keys.contains(&blocked) {
+                        true  => Err(name.to_string()),
+                        false => Ok(()),
+                    }
+                })
+            })
+            .collect();
+        let state  = Arc::new(NodeState::new(config).unwrap());
+        let from   = keys::genesis_pubkey(1);
+        let before = lamports(&state, 1);
+
+        let tx = signed(&state, 1, &[], &[system::transfer(&from, &blocked, 10)]);
+        assert_eq!(
+            process_transaction(tx, &state).err(),
+            Some(TransactionError::RejectedByMiddleware("first".to_string()))
+        );
+        assert_eq!(lamports(&state, 1), before);
+
+        let tx = signed(&state, 1, &[], &[system::transfer(&from, &keys::genesis_pubkey(2), 10)]);
+        assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+    }
+
+    #[test]
+    fn separate_fee_payer_pays_the_fee_and_the_sender_the_lamports() {
+        let state = node();
+        let (payer, from, to) = (keys::genesis_pubkey(3), keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let before = [lamports(&state, 1), lamports(&state, 2), lamports(&state, 3)];
+        let fee    = 2 * state.fee_calculator.lamports_per_signature;
+
+        let Ok(tx) = build_transaction(&state, &[3, 1], vec![system::transfer(&from, &to, 10)]) else {
+            panic!("genesis accounts 3 and 1 can sign");
+        };
+        assert_eq!(&tx.message.account_keys[..2], &[payer, from]);
+        assert_eq!(tx.signatures.len(), 2);
+
+        // Without the sender's signature the transfer is not authorized.
+        let mut unsigned = tx.clone();
+        unsigned.signatures[1] = Default::default();
+        assert_eq!(process_transaction(unsigned, &state).err(), Some(TransactionError::SignatureFailure));
+
+        assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+        assert_eq!(lamports(&state, 1), before[0] - 10);
+        assert_eq!(lamports(&state, 2), before[1] + 10);
+        assert_eq!(lamports(&state, 3), before[2] - fee);
+    }
+
+    #[test]
+    fn transfer_batch_is_atomic_and_signed_once_per_sender() {
+        let state = node();
+        let (one, two, three) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2), keys::genesis_pubkey(3));
+        let before = [lamports(&state, 1), lamports(&state, 2), lamports(&state, 3)];
+        let fee    = 2 * state.fee_calculator.lamports_per_signature;
+        let batch  = |last: u64| {
+            let instructions = vec![
+                system::transfer(&one, &three, 10),
+                system::transfer(&two, &three, 20),
+                system::transfer(&one, &three, last),
+            ];
+            let Ok(tx) = build_transaction(&state, &[1, 2, 1], instructions) else {
+                panic!("genesis accounts 1 and 2 can sign");
+            };
+            tx
+        };
+
+        // The third transfer overdraws: the first two are undone with it.
+        let tx = batch(before[0]);
+        assert_eq!(tx.signatures.len(), 2);
+        let processed = process_transaction(tx, &state).unwrap();
+        assert!(processed.result.is_err());
+        assert_eq!(processed.failed_instruction, Some(2));
+        assert_eq!(lamports(&state, 1), before[0] - fee);
+        assert_eq!(lamports(&state, 2), before[1]);
+        assert_eq!(lamports(&state, 3), before[2]);
+
+        assert_eq!(process_transaction(batch(30), &state).unwrap().result, Ok(()));
+        assert_eq!(lamports(&state, 1), before[0] - 2 * fee - 40);
+        assert_eq!(lamports(&state, 2), before[1] - 20);
+        assert_eq!(lamports(&state, 3), before[2] + 60);
+    }
+
+    #[test]
+    fn failed_durable_nonce_transaction_uses_up_its_nonce() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let nonce      = Pubkey([77; 32]);
+        let stored     = Hash([5; 32]);
+        let mut account = AccountSharedData::new(1_000_000, NONCE_STATE_SIZE, SYSTEM_PROGRAM_ID);
+        *account.data_mut() = NonceState::Initialized { authority: from, durable_nonce: stored }.to_data();
+        state.db.lock().unwrap().store(nonce, account);
+
+        let durable = |lamports| {
+            let instructions = [system::advance_nonce_account(&nonce, &from), system::transfer(&from, &to, lamports)];
+            let mut message  = Message::compile(Some(&from), &instructions).unwrap();
+            message.recent_blockhash = stored;
+            let mut tx = Transaction::new(message, Vec::new());
+            tx.sign(&[&keys::genesis_keypair(1).1]).unwrap();
+            tx
+        };
+        let before = lamports(&state, 1);
+        let fee    = state.fee_calculator.lamports_per_signature;
+
+        // The transfer overdraws: it is undone, the fee and the new nonce
+        // are not.
+        let processed = process_transaction(durable(before), &state).unwrap();
+        assert!(processed.result.is_err());
+        assert_eq!(lamports(&state, 1), before - fee);
+        let data = state.db.lock().unwrap().load(&nonce).unwrap().data().to_vec();
+        assert!(matches!(
+            NonceState::from_data(&data),
+            Some(NonceState::Initialized { durable_nonce, .. }) if durable_nonce != stored
+        ));
+
+        // So a transaction naming the old nonce no longer passes.
+        assert_eq!(process_transaction(durable(10), &state).err(), Some(TransactionError::BlockhashNotFound));
+        assert_eq!(lamports(&state, 1), before - fee);
+    }
+
+    #[test]
+    fn simulation_reports_the_outcome_and_commits_nothing() {
+        let state      = node();
+        let (from, to) = (keys::genesis_pubkey(1), keys::genesis_pubkey(2));
+        let before     = (lamports(&state, 1), lamports(&state, 2));
+        let fee        = state.fee_calculator.lamports_per_signature;
+        let (root, entries) = (state.db.lock().unwrap().state_root(), state.poh.lock().unwrap().entries.len());
+
+        let tx        = signed(&state, 1, &[], &[system::transfer(&from, &to, 10)]);
+        let simulated = simulate_transaction(&tx, &state, Some(&[to]));
+        assert_eq!(simulated.err, None);
+        assert_eq!(simulated.units_consumed, system::DEFAULT_COMPUTE_UNITS);
+        assert_eq!(&simulated.post_balances[..2], &[(from, before.0 - fee - 10), (to, before.1 + 10)]);
+        let post_to = simulated.post_accounts.as_ref().unwrap()[0].1.as_ref().unwrap().lamports();
+        assert_eq!(post_to, before.1 + 10);
+
+        let overdraft = signed(&state, 1, &[], &[system::transfer(&from, &to, before.0)]);
+        assert!(simulate_transaction(&overdraft, &state, None).err.is_some());
+
+        assert_eq!((lamports(&state, 1), lamports(&state, 2)), before);
+        assert_eq!(state.db.lock().unwrap().state_root(), root);
+        assert_eq!(state.poh.lock().unwrap().entries.len(), entries);
+
+        // Nor is it recorded as processed: the same transaction still runs.
+        assert_eq!(process_transaction(tx, &state).unwrap().result, Ok(()));
+    }
+}
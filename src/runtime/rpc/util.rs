@@ -0,0 +1,137 @@
+// ---------------------------------------------------------------------------
+// Helpers shared by the handlers: the node's keys, building and signing
+// transactions, and reading parameters from request bodies.
+// ---------------------------------------------------------------------------
+
+use std::sync::Arc;
+
+use ed25519_dalek::SigningKey;
+use tiny_http::Response;
+
+use crate::types::account::Pubkey;
+use crate::types::builder::TransactionBuilder;
+use crate::types::keys;
+use crate::types::transaction::{Instruction, Transaction};
+
+use super::NodeState;
+
+// ---------------------------------------------------------------------------
+// node_keypair — the keypair the node holds for byte identifier `id`.
+//
+// Every identifier's key is derived the same way (keys::genesis_keypair);
+// the genesis accounts 1–5 are just the ones funded at genesis. None for
+// the faucet's identifier: its key must only sign airdrop records, since
+// replay treats every transaction the faucet pays for as a mint.
+// ---------------------------------------------------------------------------
+pub(super) fn node_keypair(state: &Arc<NodeState>, id: u8) -> Option<(Pubkey, SigningKey)> {
+    let (pubkey, key) = keys::genesis_keypair(id);
+    (pubkey != state.faucet.0).then_some((pubkey, key))
+}
+
+// The byte identifier whose node_keypair is `pubkey`, if any.
+pub(super) fn node_key_id(state: &Arc<NodeState>, pubkey: &Pubkey) -> Option<u8> {
+    (0..=u8::MAX).find(|&id| node_keypair(state, id).is_some_and(|(pk, _)| pk == *pubkey))
+}
+
+// ---------------------------------------------------------------------------
+// build_transaction — compile and sign a transaction with node-held keys.
+//
+// `signer_ids` name the accounts that sign (see node_keypair), the first
+// paying the fee. TransactionBuilder lays out account_keys and the header
+// from the instructions; every account they pass as a signer must be one
+// of `signer_ids`, or the request fails with NotEnoughSigners.
+// Transaction::sign puts each signature in its signer's slot.
+// ---------------------------------------------------------------------------
+pub(super) fn build_transaction(
+    state: &Arc<NodeState>,
+    signer_ids: &[u8],
+    instructions: Vec<Instruction>,
+) -> Result<Transaction, Response<std::io::Cursor<Vec<u8>>>> {
+    let mut signers: Vec<(Pubkey, SigningKey)> = Vec::new();
+    for &id in signer_ids {
+        let Some((pubkey, key)) = node_keypair(state, id) else {
+            return Err(json_response(400, r#"{"error":"the faucet cannot sign transactions"}"#));
+        };
+        if !signers.iter().any(|(pk, _)| *pk == pubkey) {
+            signers.push((pubkey, key));
+        }
+    }
+
+    let recent_blockhash = state.blockhash_queue.lock().unwrap().last_hash().expect("genesis registers a blockhash");
+    let mut builder = TransactionBuilder::new().recent_blockhash(recent_blockhash);
+    if let Some((fee_payer, _)) = signers.first() {
+        builder = builder.fee_payer(*fee_payer);
+    }
+    let message = instructions
+        .into_iter()
+        .fold(builder, TransactionBuilder::add_instruction)
+        .build()
+        .map_err(|e| json_response(400, &format!("{{\"error\":\"{:?}\"}}", e)))?;
+
+    // Only the signers the message requires sign: a signer_id whose
+    // account no instruction passes as a signer is not asked to.
+    let required = &message.account_keys[..message.header.num_required_signatures as usize];
+    let keys: Vec<&SigningKey> = signers
+        .iter()
+        .filter(|(pubkey, _)| required.contains(pubkey))
+        .map(|(_, key)| key)
+        .collect();
+    let mut tx = Transaction::new(message, Vec::new());
+    tx.sign(&keys).map_err(|e| json_response(400, &format!("{{\"error\":\"{:?}\"}}", e)))?;
+    for signature in &tx.signatures {
+        println!("[bank] signed   sig={}", hex::encode(&signature.0[..8]));
+    }
+
+    Ok(tx)
+}
+
+// ---------------------------------------------------------------------------
+// read_json_body — read and parse a request body as JSON.
+//
+// Unreadable or malformed bodies become a 400 response.
+// ---------------------------------------------------------------------------
+pub(super) fn read_json_body(
+    request: &mut tiny_http::Request,
+) -> Result<serde_json::Value, Response<std::io::Cursor<Vec<u8>>>> {
+    let mut body = String::new();
+    if request.as_reader().read_to_string(&mut body).is_err() {
+        return Err(json_response(400, r#"{"error":"could not read body"}"#));
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| json_response(400, &format!("{{\"error\":\"{}\"}}", e)))
+}
+
+// ---------------------------------------------------------------------------
+// pubkey_param — the base58 "pubkey" field of a request body.
+// ---------------------------------------------------------------------------
+pub(super) fn pubkey_param(
+    parsed: &serde_json::Value,
+) -> Result<Pubkey, Response<std::io::Cursor<Vec<u8>>>> {
+    parsed["pubkey"]
+        .as_str()
+        .and_then(Pubkey::from_base58)
+        .ok_or_else(|| json_response(400, r#"{"error":"\"pubkey\" must be a base58 pubkey"}"#))
+}
+
+// ---------------------------------------------------------------------------
+// optional_u64 — read an optional u64 field from a JSON body.
+//
+// Missing or null → Ok(None). Present but not a u64 → a 400 response.
+// ---------------------------------------------------------------------------
+pub(super) fn optional_u64(
+    parsed: &serde_json::Value,
+    field: &str,
+) -> Result<Option<u64>, Response<std::io::Cursor<Vec<u8>>>> {
+    match &parsed[field] {
+        serde_json::Value::Null => Ok(None),
+        value => value.as_u64().map(Some).ok_or_else(|| {
+            json_response(400, &format!(r#"{{"error":"\"{}\" must be a u64"}}"#, field))
+        }),
+    }
+}
+
+pub(super) fn json_response(code: u32, body: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body)
+        .with_status_code(code)
+        .with_header("Content-Type: application/json".parse::<tiny_http::Header>().unwrap())
+}
@@ -0,0 +1,290 @@
+// ---------------------------------------------------------------------------
+// Snapshot — the live accounts of an AccountsDB, written to one file.
+//
+// A snapshot lets a node start from a known state instead of replaying the
+// ledger from genesis. It holds every live account (not the version
+// history), the slot it was taken at, the state root at that point, and
+// which AccountHashAlgorithm produced the root. Restoring rebuilds the DB
+// with that same algorithm and refuses the file if the root it recomputes
+// does not match, so a snapshot taken by a Blake3 node is never checked
+// with SHA-256 or the other way round.
+//
+// It also holds the BlockhashQueue. Without it a restored node would know
+// no recent blockhash, and every transaction signed before the restart
+// would fail with BlockhashNotFound although its blockhash is still young.
+//
+// Layout (integers little-endian):
+//   [magic:      4 bytes]  "SNAP"
+//   [version:    u8]       SNAPSHOT_VERSION
+//   [algorithm:  u8]       0 = SHA-256, 1 = BLAKE3
+//   [slot:       u64]
+//   [state_root: 32 bytes]
+//   [count:      u64]
+//   per account, in ascending pubkey order:
+//     [pubkey: 32] [lamports: u64] [owner: 32] [executable: u8]
+//     [rent_epoch: u64] [data_len: u64] [data: data_len bytes]
+//   [max_age:    u64]      the BlockhashQueue's window
+//   [hash_count: u64]
+//   per recent blockhash, oldest first:
+//     [hash: 32]
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/runtime/src/snapshot_utils.rs
+// ---------------------------------------------------------------------------
+
+use std::io;
+use std::path::Path;
+
+use crate::runtime::accounts_db::{AccountHashAlgorithm, AccountsDB};
+use crate::runtime::bank::BlockhashQueue;
+use crate::types::account::{Account, Pubkey, Slot};
+use crate::types::transaction::Hash;
+
+const MAGIC: &[u8; 4] = b"SNAP";
+
+// Bumped whenever the layout above changes.
+pub const SNAPSHOT_VERSION: u8 = 2;
+
+// ---------------------------------------------------------------------------
+// SnapshotError — why a snapshot could not be loaded.
+// ---------------------------------------------------------------------------
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading or writing the file failed.
+    Io(io::Error),
+    /// The file is not a snapshot, or is cut short or malformed.
+    Corrupt,
+    /// The file was written by a different snapshot format.
+    UnsupportedVersion(u8),
+    /// The algorithm byte names no known AccountHashAlgorithm.
+    UnknownHashAlgorithm(u8),
+    /// The restored accounts do not hash to the recorded state root.
+    StateRootMismatch { expected: [u8; 32], actual: [u8; 32] },
+}
+
+// ---------------------------------------------------------------------------
+// Snapshot
+// ---------------------------------------------------------------------------
+#[derive(Debug, PartialEq)]
+pub struct Snapshot {
+    pub hash_algorithm: AccountHashAlgorithm,
+    pub slot:           Slot,
+    pub state_root:     [u8; 32],
+    /// Live accounts in ascending pubkey order.
+    pub accounts:       Vec<(Pubkey, Account)>,
+    /// How many hashes the BlockhashQueue keeps.
+    pub max_age:        usize,
+    /// The BlockhashQueue's hashes, oldest first.
+    pub blockhashes:    Vec<Hash>,
+}
+
+impl Snapshot {
+    /// Capture the live accounts of `db`, with its slot, hash algorithm and
+    /// current state root, and the hashes in `queue`.
+    pub fn take(db: &AccountsDB, queue: &BlockhashQueue) -> Self {
+        let mut accounts: Vec<(Pubkey, Account)> =
+            db.iter().map(|(pubkey, account)| (*pubkey, account.to_account())).collect();
+        accounts.sort_by_key(|(pubkey, _)| *pubkey);
+
+        Snapshot {
+            hash_algorithm: db.hash_algorithm(),
+            slot:           db.slot(),
+            state_root:     db.state_root(),
+            accounts,
+            max_age:        queue.max_age(),
+            blockhashes:    queue.hashes().copied().collect(),
+        }
+    }
+
+    /// Rebuild an AccountsDB from the snapshot.
+    ///
+    /// The DB hashes with the recorded algorithm and starts at the recorded
+    /// slot. Fails if its state root is not the one recorded.
+    pub fn restore(&self) -> Result<AccountsDB, SnapshotError> {
+        let mut db = AccountsDB::with_hash_algorithm(self.hash_algorithm);
+        db.set_slot(self.slot);
+        db.import(self.accounts.clone(), false);
+
+        let actual = db.state_root();
+        if actual != self.state_root {
+            return Err(SnapshotError::StateRootMismatch { expected: self.state_root, actual });
+        }
+        Ok(db)
+    }
+
+    /// Rebuild the BlockhashQueue from the snapshot: the same window,
+    /// holding the same hashes in the same order.
+    pub fn blockhash_queue(&self) -> BlockhashQueue {
+        let mut queue = BlockhashQueue::new(self.max_age);
+        for hash in &self.blockhashes {
+            queue.register_hash(*hash);
+        }
+        queue
+    }
+
+    /// The snapshot in the file layout above.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(SNAPSHOT_VERSION);
+        bytes.push(algorithm_byte(self.hash_algorithm));
+        bytes.extend_from_slice(&self.slot.to_le_bytes());
+        bytes.extend_from_slice(&self.state_root);
+        bytes.extend_from_slice(&(self.accounts.len() as u64).to_le_bytes());
+        for (pubkey, account) in &self.accounts {
+            bytes.extend_from_slice(&pubkey.0);
+            bytes.extend_from_slice(&account.lamports.to_le_bytes());
+            bytes.extend_from_slice(&account.owner.0);
+            bytes.push(account.executable as u8);
+            bytes.extend_from_slice(&account.rent_epoch.to_le_bytes());
+            bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&account.data);
+        }
+        bytes.extend_from_slice(&(self.max_age as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.blockhashes.len() as u64).to_le_bytes());
+        for hash in &self.blockhashes {
+            bytes.extend_from_slice(&hash.0);
+        }
+        bytes
+    }
+
+    /// Parse the file layout above. Does not check the state root; that
+    /// happens in restore().
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != MAGIC {
+            return Err(SnapshotError::Corrupt);
+        }
+        let version = reader.u8()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let algorithm = reader.u8()?;
+        let hash_algorithm = match algorithm {
+            0 => AccountHashAlgorithm::Sha256,
+            1 => AccountHashAlgorithm::Blake3,
+            other => return Err(SnapshotError::UnknownHashAlgorithm(other)),
+        };
+        let slot       = reader.u64()?;
+        let state_root = reader.array()?;
+        let count      = reader.u64()?;
+
+        let mut accounts = Vec::new();
+        for _ in 0..count {
+            let pubkey     = Pubkey(reader.array()?);
+            let lamports   = reader.u64()?;
+            let owner      = Pubkey(reader.array()?);
+            let executable = match reader.u8()? {
+                0 => false,
+                1 => true,
+                _ => return Err(SnapshotError::Corrupt),
+            };
+            let rent_epoch = reader.u64()?;
+            let data_len   = usize::try_from(reader.u64()?).map_err(|_| SnapshotError::Corrupt)?;
+            let data       = reader.take(data_len)?.to_vec();
+            accounts.push((pubkey, Account { lamports, data, owner, executable, rent_epoch }));
+        }
+
+        let max_age    = usize::try_from(reader.u64()?).map_err(|_| SnapshotError::Corrupt)?;
+        let hash_count = reader.u64()?;
+        if hash_count > max_age as u64 {
+            return Err(SnapshotError::Corrupt);
+        }
+        let mut blockhashes = Vec::new();
+        for _ in 0..hash_count {
+            blockhashes.push(Hash::new(reader.array()?));
+        }
+        if !reader.bytes.is_empty() {
+            return Err(SnapshotError::Corrupt);
+        }
+
+        Ok(Snapshot { hash_algorithm, slot, state_root, accounts, max_age, blockhashes })
+    }
+
+    /// Write the snapshot to `path`, replacing any file there.
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        std::fs::write(path, self.to_bytes()).map_err(SnapshotError::Io)
+    }
+
+    /// Read a snapshot from `path`.
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let bytes = std::fs::read(path).map_err(SnapshotError::Io)?;
+        Snapshot::from_bytes(&bytes)
+    }
+}
+
+// The byte the layout stores for each algorithm.
+fn algorithm_byte(algorithm: AccountHashAlgorithm) -> u8 {
+    match algorithm {
+        AccountHashAlgorithm::Sha256 => 0,
+        AccountHashAlgorithm::Blake3 => 1,
+    }
+}
+
+// Reads fixed-size fields off the front of a byte slice; running out of
+// bytes is Corrupt.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::Corrupt);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SnapshotError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::account::AccountSharedData;
+
+    #[test]
+    fn restored_queue_still_accepts_blockhashes_issued_before_the_snapshot() {
+        let mut db = AccountsDB::new();
+        db.store(Pubkey([1; 32]), AccountSharedData::new(5, 3, Pubkey([2; 32])));
+        let mut queue = BlockhashQueue::new(3);
+        for byte in 1..=4 {
+            queue.register_hash(Hash([byte; 32]));
+        }
+
+        let snapshot = Snapshot::from_bytes(&Snapshot::take(&db, &queue).to_bytes()).unwrap();
+        assert_eq!(snapshot.restore().unwrap().state_root(), db.state_root());
+        let restored = snapshot.blockhash_queue();
+        assert_eq!(restored.max_age(), 3);
+        assert_eq!(restored.hashes().collect::<Vec<_>>(), queue.hashes().collect::<Vec<_>>());
+        assert!(restored.is_valid(&Hash([2; 32])));
+        assert!(!restored.is_valid(&Hash([1; 32])));
+    }
+
+    #[test]
+    fn more_hashes_than_the_window_or_trailing_bytes_are_corrupt() {
+        let mut queue = BlockhashQueue::new(2);
+        queue.register_hash(Hash([1; 32]));
+        let bytes = Snapshot::take(&AccountsDB::new(), &queue).to_bytes();
+
+        // hash_count is the u64 before the last hash; claim 3 in a window of 2.
+        let mut too_many = bytes.clone();
+        let count_at     = too_many.len() - 32 - 8;
+        too_many[count_at] = 3;
+        assert!(matches!(Snapshot::from_bytes(&too_many), Err(SnapshotError::Corrupt)));
+
+        assert!(matches!(Snapshot::from_bytes(&[bytes.clone(), vec![0]].concat()), Err(SnapshotError::Corrupt)));
+        assert!(matches!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]), Err(SnapshotError::Corrupt)));
+    }
+}
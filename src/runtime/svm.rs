@@ -8,38 +8,229 @@
 // accounts back to AccountsDB.
 //
 // It knows nothing about slots, fees, blockhashes, or signatures.
-// All of that is the Bank's responsibility (next layer up).
+// All of that is the Bank's responsibility (next layer up). The one thing
+// it is handed is the current blockhash, which durable nonce instructions
+// store into nonce accounts.
 //
 // The two-track dispatch mirrors real Solana:
 //   - known native program ID  → call hardcoded Rust function directly
-//   - unknown program ID       → BPF execution (not yet implemented)
+//                                 (SystemProgram, ComputeBudget, the loader,
+//                                 and the token and memo programs, which
+//                                 are on-chain programs in Solana but built
+//                                 in here)
+//   - unknown program ID       → run the program account's bytecode in the
+//                                 interpreter (a stand-in for BPF), or
+//                                 ProgramAccountNotFound if no account is
+//                                 there, UnsupportedProgram if it is not
+//                                 an executable account the loader owns
+//
+// Every instruction is logged to the transaction's LogCollector as Solana
+// does: "Program <id> invoke [1]", anything the program logs, then
+// "Program <id> success" or "Program <id> failed: <error>".
+//
+// A program calls another through invoke_signed, a cross-program
+// invocation (CPI), logged the same way at depth 2. It can pass on the
+// signatures and writable accounts it was given and also sign for its own
+// program derived addresses. Interpreted programs do this with INVOKE, and
+// only the native programs can be invoked this way.
 //
 // Reference: https://github.com/anza-xyz/agave/tree/master/svm
 // ---------------------------------------------------------------------------
 
-use crate::types::account::AccountSharedData;
+use crate::types::account::{AccountSharedData, Pubkey, PubkeyError};
 use crate::runtime::accounts_db::AccountsDB;
+use crate::runtime::interpreter::{self, InterpreterError};
+use crate::runtime::log_collector::LogCollector;
+use crate::runtime::transaction_error::TransactionError;
+use crate::programs::compute_budget::{self, COMPUTE_BUDGET_PROGRAM_ID};
+use crate::programs::loader::{self, LOADER_ID};
+use crate::programs::memo::{self, MEMO_PROGRAM_ID};
 use crate::programs::system::{self, SYSTEM_PROGRAM_ID};
-use crate::types::transaction::Transaction;
+use crate::programs::token::{self, TOKEN_PROGRAM_ID};
+use crate::types::transaction::{Hash, Instruction, Transaction};
 
 // ---------------------------------------------------------------------------
 // Error
 // ---------------------------------------------------------------------------
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum SvmError {
     /// An instruction referenced an account index that is out of bounds
-    /// in message.account_keys.
+    /// in message.account_keys. Message::sanitize rejects these before a
+    /// transaction reaches the Bank's fee collection, so this only fires
+    /// for a message executed without being sanitized.
     InvalidAccountIndex { instruction: usize, index: u8 },
 
-    /// The program this instruction targets is not a known native program.
-    /// BPF execution is not yet implemented.
-    UnknownProgram { instruction: usize },
+    /// The program this instruction targets is not a native program and
+    /// no account exists at its address.
+    ProgramAccountNotFound { instruction: usize },
+
+    /// The program account exists, but nothing can run it: it is not a
+    /// native program or a program the loader deployed.
+    UnsupportedProgram { instruction: usize },
+
+    /// A loader instruction failed.
+    Loader {
+        instruction: usize,
+        error: loader::LoaderError,
+    },
+
+    /// An interpreted program failed.
+    Program {
+        instruction: usize,
+        error: InterpreterError,
+    },
 
     /// A SystemProgram instruction failed.
     SystemProgram {
         instruction: usize,
         error: system::SystemProgramError,
     },
+
+    /// A token program instruction failed.
+    Token {
+        instruction: usize,
+        error: token::TokenError,
+    },
+
+    /// A memo instruction failed.
+    Memo {
+        instruction: usize,
+        error: memo::MemoError,
+    },
+
+    /// A ComputeBudget instruction could not be decoded.
+    ComputeBudget {
+        instruction: usize,
+        error: compute_budget::ComputeBudgetError,
+    },
+
+    /// The program lowered the lamports of an account it does not own.
+    /// `account` is the position in the instruction's account list.
+    ExternalAccountLamportSpend { instruction: usize, account: usize },
+
+    /// The instruction's accounts held a different total of lamports after
+    /// it ran than before: it minted or destroyed lamports.
+    UnbalancedInstruction { instruction: usize, before: u128, after: u128 },
+
+    /// The program changed the owner of an account it did not own.
+    /// `account` is the position in the instruction's account list.
+    IllegalOwnerChange { instruction: usize, account: usize },
+
+    /// The program changed the data of an account it did not own.
+    /// `account` is the position in the instruction's account list.
+    ExternalAccountDataModified { instruction: usize, account: usize },
+
+    /// The instruction lists an account more than once and the program
+    /// left two of its copies changed in different ways. `account` is the
+    /// position of the second copy in the instruction's account list.
+    DuplicateAccountOutOfSync { instruction: usize, account: usize },
+
+    /// The program changed the lamports of an account the message does
+    /// not mark writable. `account` is the position in the instruction's
+    /// account list.
+    ReadonlyLamportChange { instruction: usize, account: usize },
+
+    /// The program changed the data, owner or executable flag of an
+    /// account the message does not mark writable. `account` is the
+    /// position in the instruction's account list.
+    ReadonlyDataModified { instruction: usize, account: usize },
+}
+
+impl SvmError {
+    /// Index of the instruction that failed.
+    pub fn instruction(&self) -> usize {
+        match self {
+            SvmError::InvalidAccountIndex { instruction, .. }
+            | SvmError::ProgramAccountNotFound { instruction }
+            | SvmError::UnsupportedProgram { instruction }
+            | SvmError::Loader { instruction, .. }
+            | SvmError::Program { instruction, .. }
+            | SvmError::SystemProgram { instruction, .. }
+            | SvmError::Token { instruction, .. }
+            | SvmError::Memo { instruction, .. }
+            | SvmError::ComputeBudget { instruction, .. }
+            | SvmError::ExternalAccountLamportSpend { instruction, .. }
+            | SvmError::UnbalancedInstruction { instruction, .. }
+            | SvmError::IllegalOwnerChange { instruction, .. }
+            | SvmError::ExternalAccountDataModified { instruction, .. }
+            | SvmError::DuplicateAccountOutOfSync { instruction, .. }
+            | SvmError::ReadonlyLamportChange { instruction, .. }
+            | SvmError::ReadonlyDataModified { instruction, .. } => *instruction,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// InvokeError — why a cross-program invocation failed.
+//
+// The account checks run on the caller's own changes before it invokes as
+// well as on the callee's after, so those variants may describe either.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvokeError {
+    /// One of the signer seeds does not derive a valid PDA.
+    InvalidSeeds(PubkeyError),
+    /// The instruction names an account the caller was not passed.
+    MissingAccount { pubkey: Pubkey },
+    /// The instruction passes an account as a signer that neither signed
+    /// the caller's instruction nor is a PDA the caller signed for, or as
+    /// writable when it is not writable in the caller's instruction.
+    PrivilegeEscalation { pubkey: Pubkey },
+    /// The invoked program is not a native program CPI can reach.
+    UnsupportedProgram { program_id: Pubkey },
+    /// The invoked SystemProgram instruction failed.
+    SystemProgram(system::SystemProgramError),
+    /// The invoked token program instruction failed.
+    Token(token::TokenError),
+    /// The invoked memo instruction failed.
+    Memo(memo::MemoError),
+    /// The invoked program lowered the lamports of an account it does not
+    /// own. `account` is the position in the instruction's account list.
+    ExternalAccountLamportSpend { account: usize },
+    /// The invoked program minted or destroyed lamports.
+    UnbalancedInstruction { before: u128, after: u128 },
+    /// The invoked program changed the owner of an account it did not
+    /// own. `account` is the position in the instruction's account list.
+    IllegalOwnerChange { account: usize },
+    /// The invoked program changed the data of an account it did not own.
+    /// `account` is the position in the instruction's account list.
+    ExternalAccountDataModified { account: usize },
+    /// The invoked program changed two copies of an account the
+    /// instruction lists twice in different ways. `account` is the
+    /// position of the second copy in the instruction's account list.
+    DuplicateAccountOutOfSync { account: usize },
+    /// The invoked program changed the lamports of an account it was not
+    /// passed as writable. `account` is the position in the instruction's
+    /// account list.
+    ReadonlyLamportChange { account: usize },
+    /// The invoked program changed the data, owner or executable flag of
+    /// an account it was not passed as writable. `account` is the position
+    /// in the instruction's account list.
+    ReadonlyDataModified { account: usize },
+}
+
+// ---------------------------------------------------------------------------
+// InvokeContext — what a running program passes on to invoke_signed
+// besides its accounts.
+// ---------------------------------------------------------------------------
+pub struct InvokeContext<'a> {
+    /// Per caller account: its address, whether it signed, whether it is
+    /// writable, and its index in message.account_keys.
+    keys:            &'a [Pubkey],
+    is_signer:       &'a [bool],
+    is_writable:     &'a [bool],
+    account_indexes: &'a [usize],
+    blockhash:       &'a Hash,
+    /// The caller's accounts as last checked: as it received them, then as
+    /// each successful invocation left them.
+    pre_accounts:    Vec<AccountSharedData>,
+}
+
+impl InvokeContext<'_> {
+    /// Address of the caller's account at `index`, if there is one.
+    pub fn key(&self, index: usize) -> Option<&Pubkey> {
+        self.keys.get(index)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -54,25 +245,67 @@ pub enum SvmError {
 //   2. For each instruction:
 //        a. Pull the accounts it needs out of the working set by index
 //           into a temporary Vec.
-//        b. Dispatch to the appropriate native program.
-//        c. On success, write the (possibly mutated) temporary accounts
-//           back into the working set at their original positions.
+//        b. Dispatch to the appropriate program, then check that
+//           it changed no account the message marks readonly, only
+//           reassigned, wrote and debited accounts it owned
+//           before the instruction ran and left the total lamports across
+//           its accounts unchanged. An account listed twice is one
+//           account: its copies are reconciled first and counted once.
+//           (a and b are process_instruction.)
+//        c. On success, write the temporary accounts that actually changed
+//           back into the working set at their original positions, and
+//           mark them dirty. Later instructions load from the working set,
+//           so they see these changes: an account CreateAccount just gave
+//           a new owner is already owned by that program in the next
+//           instruction, before anything reaches AccountsDB.
 //        d. On failure, return immediately — nothing is committed.
 //
-//   3. If all instructions succeeded, store every account in the working
-//      set back to AccountsDB. This is the commit point.
+//   3. If all instructions succeeded, store every dirty account back to
+//      AccountsDB. This is the commit point. Accounts no instruction
+//      changed (e.g. both sides of a zero-lamport transfer, or a
+//      referenced account that doesn't exist) are not rewritten.
 //      On failure the function already returned, so AccountsDB is untouched.
+//
+// The steps are also public on their own — load_accounts, execute_loaded
+// and commit — so a caller can hold the AccountsDB only while loading and
+// committing, and run step 2 without it (see bank::process_entry).
 // ---------------------------------------------------------------------------
-pub fn execute(tx: &Transaction, accounts_db: &mut AccountsDB) -> Result<(), SvmError> {
-    let message = &tx.message;
+pub fn execute(
+    tx: &Transaction,
+    accounts_db: &mut AccountsDB,
+    blockhash: &Hash,
+    logs: &mut LogCollector,
+) -> Result<(), SvmError> {
+    let loaded  = load_accounts(tx, accounts_db);
+    let changed = execute_loaded(tx, loaded, blockhash, logs)?;
+    commit(accounts_db, changed);
+    Ok(())
+}
 
-    // ------------------------------------------------------------------
-    // Step 1 — load accounts into a local working set.
-    //
-    // We work on this local copy throughout execution. AccountsDB is not
-    // touched again until the final commit.
-    // ------------------------------------------------------------------
-    let mut working_set: Vec<AccountSharedData> = message
+// ---------------------------------------------------------------------------
+// LoadedTransaction — a transaction's working set after step 1.
+// ---------------------------------------------------------------------------
+pub struct LoadedTransaction {
+    /// One account per message.account_keys entry, empty where no account
+    /// exists yet.
+    accounts: Vec<AccountSharedData>,
+    /// Whether AccountsDB held an account at each key when it was loaded.
+    exists:   Vec<bool>,
+}
+
+// ---------------------------------------------------------------------------
+// load_accounts — step 1 of execute.
+//
+// We work on this local copy throughout execution. AccountsDB is not
+// touched again until the final commit, apart from counting the loads as
+// reads (see AccountStats).
+// ---------------------------------------------------------------------------
+pub fn load_accounts(tx: &Transaction, accounts_db: &mut AccountsDB) -> LoadedTransaction {
+    let message = &tx.message;
+    for pubkey in &message.account_keys {
+        accounts_db.record_read(pubkey);
+    }
+    let accounts = message
         .account_keys
         .iter()
         .map(|pubkey| {
@@ -82,73 +315,1082 @@ pub fn execute(tx: &Transaction, accounts_db: &mut AccountsDB) -> Result<(), Svm
                 .unwrap_or_default()
         })
         .collect();
+    let exists = message.account_keys.iter().map(|pubkey| accounts_db.contains(pubkey)).collect();
+    LoadedTransaction { accounts, exists }
+}
+
+// ---------------------------------------------------------------------------
+// execute_loaded — step 2 of execute, on a working set load_accounts made.
+//
+// Returns the accounts to commit: every key whose account some instruction
+// changed, with its final state. Needs no AccountsDB.
+// ---------------------------------------------------------------------------
+pub fn execute_loaded(
+    tx: &Transaction,
+    loaded: LoadedTransaction,
+    blockhash: &Hash,
+    logs: &mut LogCollector,
+) -> Result<Vec<(Pubkey, AccountSharedData)>, SvmError> {
+    let message = &tx.message;
+    let LoadedTransaction { accounts: mut working_set, exists } = loaded;
+    let mut dirty = vec![false; working_set.len()];
 
-    // ------------------------------------------------------------------
-    // Step 2 — execute each instruction.
-    // ------------------------------------------------------------------
     for (ix_index, instruction) in message.instructions.iter().enumerate() {
-        // Resolve the program ID.
-        let program_id_index = instruction.program_id_index as usize;
-        let program_id = message
+        let program = message
             .account_keys
-            .get(program_id_index)
-            .ok_or(SvmError::InvalidAccountIndex {
+            .get(instruction.program_id_index as usize)
+            .map_or_else(|| "<invalid>".to_string(), |p| p.to_base58());
+        logs.log(&format!("Program {} invoke [1]", program));
+
+        let ix_accounts = match process_instruction(ix_index, tx, &working_set, &exists, blockhash, logs) {
+            Ok(ix_accounts) => ix_accounts,
+            Err(e) => {
+                logs.log(&format!("Program {} failed: {}", program, failure_reason(&e)));
+                return Err(e);
+            }
+        };
+        logs.log(&format!("Program {} success", program));
+
+        // Write the instruction accounts that changed back into the working
+        // set at their original positions.
+        for (pos, &account_index) in instruction.accounts.iter().enumerate() {
+            let account_index = account_index as usize;
+            if working_set[account_index] != ix_accounts[pos] {
+                working_set[account_index] = ix_accounts[pos].clone();
+                dirty[account_index] = true;
+            }
+        }
+    }
+
+    Ok(message
+        .account_keys
+        .iter()
+        .zip(working_set)
+        .zip(dirty)
+        .filter(|(_, dirty)| *dirty)
+        .map(|((pubkey, account), _)| (*pubkey, account))
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// commit — step 3 of execute: store what execute_loaded returned.
+// ---------------------------------------------------------------------------
+pub fn commit(accounts_db: &mut AccountsDB, changed: Vec<(Pubkey, AccountSharedData)>) {
+    for (pubkey, account) in changed {
+        accounts_db.store(pubkey, account);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// process_instruction — run instruction `ix_index` of `tx` against the
+// working set.
+//
+// Steps 2a and 2b of execute: the instruction's accounts are cloned out of
+// the working set, handed to the program and checked after it returns.
+// Returns them as the program left them; the working set is not touched.
+// ---------------------------------------------------------------------------
+fn process_instruction(
+    ix_index: usize,
+    tx: &Transaction,
+    working_set: &[AccountSharedData],
+    exists: &[bool],
+    blockhash: &Hash,
+    logs: &mut LogCollector,
+) -> Result<Vec<AccountSharedData>, SvmError> {
+    let message = &tx.message;
+    let instruction = &message.instructions[ix_index];
+
+    // Resolve the program ID.
+    let program_id_index = instruction.program_id_index as usize;
+    let program_id = message
+        .account_keys
+        .get(program_id_index)
+        .ok_or(SvmError::InvalidAccountIndex {
+            instruction: ix_index,
+            index: instruction.program_id_index,
+        })?;
+
+    // Build a temporary Vec of the accounts this instruction operates on,
+    // cloned out of the working set. This lets us pass a plain
+    // &mut [AccountSharedData] to the program without borrow-checker issues.
+    let mut ix_accounts: Vec<AccountSharedData> = instruction
+        .accounts
+        .iter()
+        .map(|&account_index| {
+            working_set
+                .get(account_index as usize)
+                .cloned()
+                .ok_or(SvmError::InvalidAccountIndex {
+                    instruction: ix_index,
+                    index: account_index,
+                })
+        })
+        .collect::<Result<_, _>>()?;
+
+    // Their addresses, and which of them signed the transaction, in the
+    // same order. Every index was checked while building ix_accounts.
+    let keys: Vec<Pubkey> = instruction
+        .accounts
+        .iter()
+        .map(|&account_index| message.account_keys[account_index as usize])
+        .collect();
+    let is_signer: Vec<bool> = instruction
+        .accounts
+        .iter()
+        .map(|&account_index| message.is_signer(account_index as usize))
+        .collect();
+    let is_writable: Vec<bool> = instruction
+        .accounts
+        .iter()
+        .map(|&account_index| message.is_writable(account_index as usize))
+        .collect();
+    let account_indexes: Vec<usize> = instruction.accounts.iter().map(|&index| index as usize).collect();
+
+    // Snapshot of the accounts as the program receives them, for the
+    // invariant checks after it returns.
+    let mut pre_accounts = ix_accounts.clone();
+
+    // Dispatch to the correct program.
+    if program_id == &SYSTEM_PROGRAM_ID {
+        let decoded = system::decode(&instruction.data).map_err(|e| {
+            SvmError::SystemProgram {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+
+        system::process(&decoded, &mut ix_accounts, &keys, &is_signer, blockhash).map_err(|e| {
+            SvmError::SystemProgram {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+    } else if program_id == &COMPUTE_BUDGET_PROGRAM_ID {
+        // The Bank already applied these when computing the fee.
+        // At execution time they are a no-op once they decode.
+        compute_budget::decode(&instruction.data).map_err(|e| {
+            SvmError::ComputeBudget {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+    } else if program_id == &TOKEN_PROGRAM_ID {
+        let decoded = token::decode(&instruction.data).map_err(|e| {
+            SvmError::Token {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+
+        token::process(&decoded, &mut ix_accounts, &keys, &is_signer).map_err(|e| {
+            SvmError::Token {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+    } else if program_id == &MEMO_PROGRAM_ID {
+        memo::process(&instruction.data, &is_signer, logs).map_err(|e| {
+            SvmError::Memo {
                 instruction: ix_index,
-                index: instruction.program_id_index,
-            })?;
-
-        // Build a temporary Vec of the accounts this instruction operates on,
-        // cloned out of the working set. This lets us pass a plain
-        // &mut [AccountSharedData] to the program without borrow-checker issues.
-        let mut ix_accounts: Vec<AccountSharedData> = instruction
-            .accounts
+                error: e,
+            }
+        })?;
+    } else if program_id == &LOADER_ID {
+        let decoded = loader::decode(&instruction.data).map_err(|e| {
+            SvmError::Loader {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+
+        loader::process(&decoded, &mut ix_accounts, &is_signer).map_err(|e| {
+            SvmError::Loader {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+    } else if !exists[program_id_index] {
+        return Err(SvmError::ProgramAccountNotFound { instruction: ix_index });
+    } else {
+        // The program account is in account_keys, so the working set holds
+        // it as of this instruction: a program deployed by an earlier
+        // instruction of the same transaction can already be invoked.
+        let program = &working_set[program_id_index];
+        if !program.executable() || program.owner() != &LOADER_ID {
+            return Err(SvmError::UnsupportedProgram { instruction: ix_index });
+        }
+        let mut context = InvokeContext {
+            keys:            &keys,
+            is_signer:       &is_signer,
+            is_writable:     &is_writable,
+            account_indexes: &account_indexes,
+            blockhash,
+            pre_accounts,
+        };
+        interpreter::run(program.data(), program_id, &mut ix_accounts, &instruction.data, &mut context, logs).map_err(|e| {
+            SvmError::Program {
+                instruction: ix_index,
+                error: e,
+            }
+        })?;
+        // What the program invoked was checked as the callee's doing; only
+        // the program's own changes since are left to check.
+        pre_accounts = context.pre_accounts;
+    }
+
+    reconcile_duplicates(&account_indexes, &pre_accounts, &mut ix_accounts)
+        .map_err(|account| SvmError::DuplicateAccountOutOfSync { instruction: ix_index, account })?;
+    check_readonly_lamports(&is_writable, &pre_accounts, &ix_accounts)
+        .map_err(|account| SvmError::ReadonlyLamportChange { instruction: ix_index, account })?;
+    check_readonly_data(&is_writable, &pre_accounts, &ix_accounts)
+        .map_err(|account| SvmError::ReadonlyDataModified { instruction: ix_index, account })?;
+    check_owner_change(program_id, &pre_accounts, &ix_accounts)
+        .map_err(|account| SvmError::IllegalOwnerChange { instruction: ix_index, account })?;
+    check_data_change(program_id, &pre_accounts, &ix_accounts)
+        .map_err(|account| SvmError::ExternalAccountDataModified { instruction: ix_index, account })?;
+    check_lamport_spend(program_id, &pre_accounts, &ix_accounts)
+        .map_err(|account| SvmError::ExternalAccountLamportSpend { instruction: ix_index, account })?;
+    check_lamport_balance(&account_indexes, &pre_accounts, &ix_accounts)
+        .map_err(|(before, after)| SvmError::UnbalancedInstruction { instruction: ix_index, before, after })?;
+
+    Ok(ix_accounts)
+}
+
+// ---------------------------------------------------------------------------
+// invoke_signed — run `instruction` on behalf of the program `program_id`.
+//
+// `accounts` are the caller's instruction accounts and `context` the rest
+// of what it was given. Every account the instruction names must be among
+// them; the callee works on copies, which are written back over the
+// caller's only if it succeeds.
+//
+// An account may be passed as a signer if it signed the caller's
+// instruction, or if it is the PDA that one of `signers_seeds` derives
+// under `program_id` (create_program_address, so the bump is the last
+// seed). That is how a program signs for its PDAs: SystemProgram's
+// CreateAccount accepts a PDA as the new account without any Ed25519
+// signature for it. It may be passed as writable only if it is writable
+// in the caller's instruction. Anything else is a PrivilegeEscalation.
+//
+// The caller's changes so far are checked first, as if its instruction
+// ended here, and the callee's after it returns; each is held to the same
+// rules as a top-level instruction. The result becomes the caller's new
+// starting point in `context`.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/program-runtime/src/cpi.rs
+// ---------------------------------------------------------------------------
+pub fn invoke_signed(
+    instruction: &Instruction,
+    accounts: &mut [AccountSharedData],
+    context: &mut InvokeContext,
+    signers_seeds: &[&[&[u8]]],
+    program_id: &Pubkey,
+    logs: &mut LogCollector,
+) -> Result<(), InvokeError> {
+    let signer_pdas = signers_seeds
+        .iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, program_id))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(InvokeError::InvalidSeeds)?;
+
+    check_accounts(program_id, context.account_indexes, context.is_writable, &context.pre_accounts, accounts)?;
+
+    // Where each of the callee's accounts sits among the caller's, and
+    // whether it may be passed as a signer and as writable.
+    let mut positions = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let pos = context
+            .keys
             .iter()
-            .enumerate()
-            .map(|(pos, &account_index)| {
-                working_set
-                    .get(account_index as usize)
-                    .cloned()
-                    .ok_or(SvmError::InvalidAccountIndex {
-                        instruction: ix_index,
-                        index: account_index,
-                    })
+            .position(|key| key == &meta.pubkey)
+            .ok_or(InvokeError::MissingAccount { pubkey: meta.pubkey })?;
+        let signed = context.is_signer[pos] || signer_pdas.contains(&meta.pubkey);
+        if (meta.is_signer && !signed) || (meta.is_writable && !context.is_writable[pos]) {
+            return Err(InvokeError::PrivilegeEscalation { pubkey: meta.pubkey });
+        }
+        positions.push(pos);
+    }
+
+    let mut callee_accounts: Vec<AccountSharedData> = positions.iter().map(|&pos| accounts[pos].clone()).collect();
+    let callee_keys: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+    let callee_signers: Vec<bool> = instruction.accounts.iter().map(|meta| meta.is_signer).collect();
+    let callee_writable: Vec<bool> = instruction.accounts.iter().map(|meta| meta.is_writable).collect();
+    let pre_accounts = callee_accounts.clone();
+
+    let callee = instruction.program_id;
+    logs.log(&format!("Program {} invoke [2]", callee.to_base58()));
+    let result = if callee == SYSTEM_PROGRAM_ID {
+        system::decode(&instruction.data)
+            .and_then(|decoded| {
+                system::process(&decoded, &mut callee_accounts, &callee_keys, &callee_signers, context.blockhash)
             })
-            .collect::<Result<_, _>>()?;
+            .map_err(InvokeError::SystemProgram)
+    } else if callee == TOKEN_PROGRAM_ID {
+        token::decode(&instruction.data)
+            .and_then(|decoded| token::process(&decoded, &mut callee_accounts, &callee_keys, &callee_signers))
+            .map_err(InvokeError::Token)
+    } else if callee == MEMO_PROGRAM_ID {
+        memo::process(&instruction.data, &callee_signers, logs).map_err(InvokeError::Memo)
+    } else {
+        Err(InvokeError::UnsupportedProgram { program_id: callee })
+    }
+    .and_then(|()| check_accounts(&callee, &positions, &callee_writable, &pre_accounts, &mut callee_accounts));
+    if let Err(e) = result {
+        logs.log(&format!("Program {} failed: {:?}", callee.to_base58(), e));
+        return Err(e);
+    }
+    logs.log(&format!("Program {} success", callee.to_base58()));
 
-        // Dispatch to the correct program.
-        if program_id == &SYSTEM_PROGRAM_ID {
-            let decoded = system::decode(&instruction.data).map_err(|e| {
-                SvmError::SystemProgram {
-                    instruction: ix_index,
-                    error: e,
-                }
-            })?;
+    // Write back to every position the caller holds the account at, so its
+    // copies stay in step.
+    for (meta, account) in instruction.accounts.iter().zip(callee_accounts) {
+        for (pos, key) in context.keys.iter().enumerate() {
+            if key == &meta.pubkey {
+                accounts[pos] = account.clone();
+            }
+        }
+    }
+    context.pre_accounts = accounts.to_vec();
+    Ok(())
+}
 
-            system::process(&decoded, &mut ix_accounts).map_err(|e| {
-                SvmError::SystemProgram {
-                    instruction: ix_index,
-                    error: e,
-                }
-            })?;
-        } else {
-            // TODO: BPF execution via EbpfVm
-            return Err(SvmError::UnknownProgram { instruction: ix_index });
+// The checks process_instruction runs after a program returns, for
+// invoke_signed: `program_id` ran, `account_indexes` says which account
+// each position is and `is_writable` whether it was passed as writable.
+fn check_accounts(
+    program_id: &Pubkey,
+    account_indexes: &[usize],
+    is_writable: &[bool],
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &mut [AccountSharedData],
+) -> Result<(), InvokeError> {
+    reconcile_duplicates(account_indexes, pre_accounts, post_accounts)
+        .map_err(|account| InvokeError::DuplicateAccountOutOfSync { account })?;
+    check_readonly_lamports(is_writable, pre_accounts, post_accounts)
+        .map_err(|account| InvokeError::ReadonlyLamportChange { account })?;
+    check_readonly_data(is_writable, pre_accounts, post_accounts)
+        .map_err(|account| InvokeError::ReadonlyDataModified { account })?;
+    check_owner_change(program_id, pre_accounts, post_accounts)
+        .map_err(|account| InvokeError::IllegalOwnerChange { account })?;
+    check_data_change(program_id, pre_accounts, post_accounts)
+        .map_err(|account| InvokeError::ExternalAccountDataModified { account })?;
+    check_lamport_spend(program_id, pre_accounts, post_accounts)
+        .map_err(|account| InvokeError::ExternalAccountLamportSpend { account })?;
+    check_lamport_balance(account_indexes, pre_accounts, post_accounts)
+        .map_err(|(before, after)| InvokeError::UnbalancedInstruction { before, after })
+}
+
+// How a failed instruction is described in the program log: the
+// instruction error the client sees, or the transaction error if the
+// failure is not the instruction's own.
+fn failure_reason(error: &SvmError) -> String {
+    match TransactionError::from(error.clone()) {
+        TransactionError::InstructionError(_, error) => format!("{:?}", error),
+        error => format!("{:?}", error),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// check_readonly_lamports / check_readonly_data — readonly means unchanged.
+//
+// An account the message does not mark writable (`is_writable`, per
+// position in the instruction's account list) must come out of the
+// instruction as it went in, whoever owns it: same lamports, and same
+// data, owner and executable flag. This is what lets AccountLocks share a
+// readonly account between transactions running at the same time. Each
+// fails with the position of the first readonly account changed.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/sdk/src/transaction_context.rs (ReadonlyLamportChange, ReadonlyDataModified)
+// ---------------------------------------------------------------------------
+fn check_readonly_lamports(
+    is_writable: &[bool],
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &[AccountSharedData],
+) -> Result<(), usize> {
+    for (account, (pre, post)) in pre_accounts.iter().zip(post_accounts).enumerate() {
+        if !is_writable[account] && post.lamports() != pre.lamports() {
+            return Err(account);
         }
+    }
+    Ok(())
+}
 
-        // Write the (possibly mutated) instruction accounts back into the
-        // working set at their original positions.
-        for (pos, &account_index) in instruction.accounts.iter().enumerate() {
-            working_set[account_index as usize] = ix_accounts[pos].clone();
+fn check_readonly_data(
+    is_writable: &[bool],
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &[AccountSharedData],
+) -> Result<(), usize> {
+    for (account, (pre, post)) in pre_accounts.iter().zip(post_accounts).enumerate() {
+        let modified =
+            post.data() != pre.data() || post.owner() != pre.owner() || post.executable() != pre.executable();
+        if !is_writable[account] && modified {
+            return Err(account);
         }
     }
+    Ok(())
+}
 
-    // ------------------------------------------------------------------
-    // Step 3 — commit. All instructions succeeded; persist the working
-    // set back to AccountsDB.
-    // ------------------------------------------------------------------
-    for (pubkey, account) in message.account_keys.iter().zip(working_set) {
-        accounts_db.store(*pubkey, account);
+// ---------------------------------------------------------------------------
+// check_owner_change — only an account's owner may give it a new owner.
+//
+// SystemProgram's Assign only reassigns system-owned accounts, but nothing
+// stops another program calling set_owner on any account it is passed. So
+// after every instruction, any account whose owner changed must have been
+// owned by the program that ran, as of before the instruction. Fails with
+// the position of the first account reassigned against the rule.
+//
+// The one exception is the loader claiming a fresh account (see
+// acts_as_owner), which is how DeployProgram takes over a plain keypair.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/sdk/src/transaction_context.rs (ModifiedProgramId)
+// ---------------------------------------------------------------------------
+fn check_owner_change(
+    program_id: &Pubkey,
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &[AccountSharedData],
+) -> Result<(), usize> {
+    for (account, (pre, post)) in pre_accounts.iter().zip(post_accounts).enumerate() {
+        if post.owner() != pre.owner() && !acts_as_owner(program_id, pre, post) {
+            return Err(account);
+        }
     }
+    Ok(())
+}
 
+// Whether `program_id` may reassign or write an account that was `pre`
+// before the instruction and is `post` after it: it owned the
+// account, or it is the loader claiming a system-owned account that held
+// no data, and the account now belongs to the loader. A program never
+// gets to claim an account another program owns.
+fn acts_as_owner(program_id: &Pubkey, pre: &AccountSharedData, post: &AccountSharedData) -> bool {
+    pre.owner() == program_id
+        || (program_id == &LOADER_ID
+            && pre.owner() == &SYSTEM_PROGRAM_ID
+            && pre.data().is_empty()
+            && post.owner() == &LOADER_ID)
+}
+
+// ---------------------------------------------------------------------------
+// check_data_change — only an account's owner may change its data.
+//
+// Covers both the bytes and the length, so resizing counts as a change:
+// CreateAccount and Allocate stay legal because the SystemProgram owns the
+// accounts it sizes, and DeployProgram because the loader may claim the
+// account it writes (see acts_as_owner). Fails with the position of the
+// first account written against the rule.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/sdk/src/transaction_context.rs (ExternalAccountDataModified)
+// ---------------------------------------------------------------------------
+fn check_data_change(
+    program_id: &Pubkey,
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &[AccountSharedData],
+) -> Result<(), usize> {
+    for (account, (pre, post)) in pre_accounts.iter().zip(post_accounts).enumerate() {
+        if post.data() != pre.data() && !acts_as_owner(program_id, pre, post) {
+            return Err(account);
+        }
+    }
     Ok(())
 }
+
+// ---------------------------------------------------------------------------
+// check_lamport_spend — only an account's owner may take lamports from it.
+//
+// Programs enforce this themselves (SystemProgram refuses to debit accounts
+// it does not own), but the runtime does not trust them to: after every
+// instruction, any account whose balance went down must have been owned by
+// the program that ran, as of before the instruction. Crediting any
+// account is always allowed. Fails with the position of the first account
+// debited against the rule.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/program-runtime/src/invoke_context.rs (ExternalAccountLamportSpend)
+// ---------------------------------------------------------------------------
+fn check_lamport_spend(
+    program_id: &Pubkey,
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &[AccountSharedData],
+) -> Result<(), usize> {
+    for (account, (pre, post)) in pre_accounts.iter().zip(post_accounts).enumerate() {
+        if post.lamports() < pre.lamports() && pre.owner() != program_id {
+            return Err(account);
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// reconcile_duplicates — make every copy of a repeated account agree.
+//
+// An instruction may list the same account at several positions
+// (`account_indexes` says which account each position is). The program
+// gets a separate copy at each, so it can change one copy and not the
+// others, and only one copy would survive write-back. Each group of copies
+// is reconciled to the one the program changed, if any. If it changed two
+// copies differently there is no single result, and this fails with the
+// position of the second one.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/program-runtime/src/invoke_context.rs (DuplicateAccountOutOfSync)
+// ---------------------------------------------------------------------------
+fn reconcile_duplicates(
+    account_indexes: &[usize],
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &mut [AccountSharedData],
+) -> Result<(), usize> {
+    for (first, index) in account_indexes.iter().enumerate() {
+        if account_indexes[..first].contains(index) {
+            continue;
+        }
+        let copies: Vec<usize> = (first..account_indexes.len()).filter(|&pos| account_indexes[pos] == *index).collect();
+
+        let mut changed: Option<usize> = None;
+        for &pos in &copies {
+            if post_accounts[pos] == pre_accounts[pos] {
+                continue;
+            }
+            match changed {
+                Some(earlier) if post_accounts[earlier] != post_accounts[pos] => return Err(pos),
+                Some(_) => {}
+                None    => changed = Some(pos),
+            }
+        }
+        if let Some(changed) = changed {
+            let account = post_accounts[changed].clone();
+            for &pos in &copies {
+                post_accounts[pos] = account.clone();
+            }
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// check_lamport_balance — an instruction moves lamports, never makes them.
+//
+// The total across the instruction's accounts must be the same before and
+// after it runs. An account listed at several positions counts once
+// (`account_indexes` as for reconcile_duplicates, which must run first so
+// its copies agree). Summed as u128, so a program whose arithmetic wrapped
+// a u64 balance shows up as an imbalance rather than slipping through.
+// Fails with the totals before and after.
+// ---------------------------------------------------------------------------
+fn check_lamport_balance(
+    account_indexes: &[usize],
+    pre_accounts: &[AccountSharedData],
+    post_accounts: &[AccountSharedData],
+) -> Result<(), (u128, u128)> {
+    let unique = |accounts: &[AccountSharedData]| -> u128 {
+        account_indexes
+            .iter()
+            .enumerate()
+            .filter(|(pos, index)| !account_indexes[..*pos].contains(index))
+            .map(|(pos, _)| accounts[pos].lamports() as u128)
+            .sum()
+    };
+    let before = unique(pre_accounts);
+    let after  = unique(post_accounts);
+    if before != after {
+        return Err((before, after));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::interpreter::{
+        OP_ABORT, OP_ADD, OP_DUP, OP_INVOKE, OP_LOAD_INPUT, OP_LOAD_LAMPORTS, OP_LOG, OP_PUSH, OP_STORE_DATA,
+        OP_STORE_LAMPORTS, OP_SUB,
+    };
+    use crate::types::account_state::AccountState;
+    use crate::types::transaction::{AccountMeta, CompiledInstruction, Message, MessageHeader};
+
+    const PAYER: Pubkey   = Pubkey([1; 32]);
+    const PROGRAM: Pubkey = Pubkey([2; 32]);
+    const DATA: Pubkey    = Pubkey([3; 32]);
+    const OTHER: Pubkey   = Pubkey([4; 32]);
+
+    // A transaction over [PAYER, DATA, OTHER, program]: PAYER signs, the
+    // program is the one readonly account.
+    fn tx(program: Pubkey, instructions: Vec<CompiledInstruction>) -> Transaction {
+        let header = MessageHeader {
+            num_required_signatures:        1,
+            num_readonly_signed_accounts:   0,
+            num_readonly_unsigned_accounts: 1,
+        };
+        let message = Message::new(header, vec![PAYER, DATA, OTHER, program], Hash::default(), instructions);
+        Transaction::new(message, vec![Default::default()])
+    }
+
+    // A db holding PAYER, a deployed `bytecode` at PROGRAM, and DATA and
+    // OTHER with 1000 lamports each, owned by PROGRAM.
+    fn db_with_program(bytecode: &[u8]) -> AccountsDB {
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        let mut program = AccountSharedData::new(1, 0, LOADER_ID);
+        *program.data_mut() = bytecode.to_vec();
+        program.set_executable(true);
+        db.store(PROGRAM, program);
+        db.store(DATA, AccountSharedData::new(1_000, 0, PROGRAM));
+        db.store(OTHER, AccountSharedData::new(1_000, 0, PROGRAM));
+        db
+    }
+
+    // DeployProgram's instruction data for `bytecode`.
+    fn deploy_data(bytecode: &[u8]) -> Vec<u8> {
+        let mut data = 0u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&(bytecode.len() as u64).to_le_bytes());
+        data.extend_from_slice(bytecode);
+        data
+    }
+
+    fn run(tx: &Transaction, db: &mut AccountsDB) -> Result<(), SvmError> {
+        execute(tx, db, &Hash::default(), &mut LogCollector::new())
+    }
+
+    fn lamports(db: &AccountsDB, pubkey: &Pubkey) -> u64 {
+        db.load(pubkey).unwrap().lamports()
+    }
+
+    // Add 100 lamports to account `add`, and take them from `sub` if given.
+    fn move_100(sub: Option<u8>, add: u8) -> Vec<u8> {
+        let mut code = Vec::new();
+        if let Some(sub) = sub {
+            code.extend([OP_LOAD_LAMPORTS, sub, OP_PUSH, 100, OP_SUB, OP_STORE_LAMPORTS, sub]);
+        }
+        code.extend([OP_LOAD_LAMPORTS, add, OP_PUSH, 100, OP_ADD, OP_STORE_LAMPORTS, add]);
+        code
+    }
+
+    #[test]
+    fn buggy_native_program_minting_lamports_is_rejected() {
+        // What a native program that credits without debiting leaves
+        // behind: the runtime's check catches it whoever the program is.
+        fn buggy_transfer(accounts: &mut [AccountSharedData], lamports: u64) {
+            accounts[1].set_lamports(accounts[1].lamports() + lamports);
+        }
+        let pre = vec![AccountSharedData::new(500, 0, SYSTEM_PROGRAM_ID), AccountSharedData::new(0, 0, SYSTEM_PROGRAM_ID)];
+        let mut post = pre.clone();
+        buggy_transfer(&mut post, 100);
+
+        assert_eq!(check_lamport_balance(&[0, 1], &pre, &post), Err((500, 600)));
+    }
+
+    #[test]
+    fn program_minting_lamports_is_rejected() {
+        let mut db = db_with_program(&move_100(None, 0));
+        let tx     = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1], vec![])]);
+
+        assert_eq!(
+            run(&tx, &mut db),
+            Err(SvmError::UnbalancedInstruction { instruction: 0, before: 1_000, after: 1_100 })
+        );
+        assert_eq!(lamports(&db, &DATA), 1_000);
+    }
+
+    #[test]
+    fn self_transfer_does_not_mint() {
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        let data = system::transfer(&PAYER, &PAYER, 400).data;
+        let tx   = tx(SYSTEM_PROGRAM_ID, vec![CompiledInstruction::new(3, vec![0, 0], data)]);
+
+        assert_eq!(run(&tx, &mut db), Ok(()));
+        assert_eq!(lamports(&db, &PAYER), 1_000);
+    }
+
+    #[test]
+    fn zero_lamport_transfer_leaves_both_accounts_untouched() {
+        // OTHER does not exist: a zero-lamport transfer must not create
+        // it, nor rewrite PAYER, but must still be signed for.
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        db.store(DATA, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        let version = db.version(&PAYER);
+
+        let data = system::transfer(&PAYER, &OTHER, 0).data;
+        let free = tx(SYSTEM_PROGRAM_ID, vec![CompiledInstruction::new(3, vec![0, 2], data)]);
+        assert_eq!(run(&free, &mut db), Ok(()));
+        assert_eq!(lamports(&db, &PAYER), 1_000);
+        assert_eq!(db.version(&PAYER), version);
+        assert!(db.load(&OTHER).is_none());
+
+        let data = system::transfer(&DATA, &OTHER, 0).data;
+        let unsigned = tx(SYSTEM_PROGRAM_ID, vec![CompiledInstruction::new(3, vec![1, 2], data)]);
+        assert_eq!(
+            run(&unsigned, &mut db),
+            Err(SvmError::SystemProgram { instruction: 0, error: system::SystemProgramError::MissingRequiredSignature })
+        );
+    }
+
+    #[test]
+    fn duplicate_copies_changed_differently_are_rejected() {
+        // Debit one copy of DATA and credit the other: balanced position
+        // by position, but whichever copy were written back would mint
+        // or burn 100 lamports.
+        let mut db = db_with_program(&move_100(Some(0), 1));
+        let tx     = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1, 1], vec![])]);
+
+        assert_eq!(run(&tx, &mut db), Err(SvmError::DuplicateAccountOutOfSync { instruction: 0, account: 1 }));
+        assert_eq!(lamports(&db, &DATA), 1_000);
+    }
+
+    #[test]
+    fn duplicate_copy_changed_once_is_kept() {
+        // DATA at positions 0 and 1; only copy 0 is debited, and OTHER
+        // credited. Counted once, DATA keeps the books balanced, and the
+        // debit survives write-back even though copy 1 comes later.
+        let mut db = db_with_program(&move_100(Some(0), 2));
+        let tx     = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1, 1, 2], vec![])]);
+
+        assert_eq!(run(&tx, &mut db), Ok(()));
+        assert_eq!(lamports(&db, &DATA), 900);
+        assert_eq!(lamports(&db, &OTHER), 1_100);
+    }
+
+    #[test]
+    fn program_taking_another_programs_account_is_rejected() {
+        // A program that hands itself whatever it is passed.
+        fn steal(accounts: &mut [AccountSharedData], thief: &Pubkey) {
+            for account in accounts {
+                account.set_owner(*thief);
+            }
+        }
+        let pre = vec![
+            AccountSharedData::new(10, 0, OTHER),
+            AccountSharedData::new(10, 0, PROGRAM),
+        ];
+        let mut post = pre.clone();
+        steal(&mut post, &OTHER);
+
+        // Position 0 already belonged to the thief; position 1 did not.
+        assert_eq!(check_owner_change(&OTHER, &pre, &post), Err(1));
+    }
+
+    #[test]
+    fn loader_may_claim_only_a_fresh_system_account() {
+        let claimed = |pre: AccountSharedData| {
+            let mut post = pre.clone();
+            post.set_owner(LOADER_ID);
+            check_owner_change(&LOADER_ID, &[pre], &[post])
+        };
+        assert_eq!(claimed(AccountSharedData::new(10, 0, SYSTEM_PROGRAM_ID)), Ok(()));
+        assert_eq!(claimed(AccountSharedData::new(10, 8, SYSTEM_PROGRAM_ID)), Err(0));
+        assert_eq!(claimed(AccountSharedData::new(10, 0, PROGRAM)), Err(0));
+    }
+
+    #[test]
+    fn deploy_into_another_programs_account_is_rejected() {
+        let mut db = db_with_program(&[]);
+        let header = MessageHeader {
+            num_required_signatures:        2,
+            num_readonly_signed_accounts:   0,
+            num_readonly_unsigned_accounts: 1,
+        };
+        let message = Message::new(
+            header,
+            vec![PAYER, DATA, LOADER_ID],
+            Hash::default(),
+            vec![CompiledInstruction::new(2, vec![1], deploy_data(&move_100(None, 0)))],
+        );
+        let tx = Transaction::new(message, vec![Default::default(); 2]);
+
+        assert_eq!(
+            run(&tx, &mut db),
+            Err(SvmError::Loader { instruction: 0, error: loader::LoaderError::AccountAlreadyInUse })
+        );
+        assert_eq!(db.load(&DATA).unwrap().owner(), &PROGRAM);
+    }
+
+    #[test]
+    fn deployed_program_is_executable_and_reaches_the_interpreter() {
+        // OTHER is a plain keypair: system-owned, no data.
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        db.store(OTHER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+
+        // [PAYER, OTHER | LOADER_ID]: deploy into OTHER, then invoke it.
+        let header = MessageHeader {
+            num_required_signatures:        2,
+            num_readonly_signed_accounts:   0,
+            num_readonly_unsigned_accounts: 1,
+        };
+        let bytecode = [OP_PUSH, 42, OP_LOG];
+        let message  = Message::new(
+            header,
+            vec![PAYER, OTHER, LOADER_ID],
+            Hash::default(),
+            vec![
+                CompiledInstruction::new(2, vec![1], deploy_data(&bytecode)),
+                CompiledInstruction::new(1, vec![], vec![]),
+            ],
+        );
+        let tx = Transaction::new(message, vec![Default::default(); 2]);
+
+        let mut logs = LogCollector::new();
+        assert_eq!(execute(&tx, &mut db, &Hash::default(), &mut logs), Ok(()));
+
+        let program = db.load(&OTHER).unwrap();
+        assert!(program.executable());
+        assert_eq!(program.owner(), &LOADER_ID);
+        assert_eq!(program.data(), &bytecode[..]);
+        assert!(logs.into_messages().contains(&"Program log: 42".to_string()));
+    }
+
+    // INVOKE of `program_id` with `accounts` as (acct, flags), signing
+    // with `seeds`.
+    fn invoke_op(program_id: &Pubkey, accounts: &[(u8, u8)], data: &[u8], seeds: &[&[u8]]) -> Vec<u8> {
+        let mut call = program_id.0.to_vec();
+        call.push(accounts.len() as u8);
+        for &(index, flags) in accounts {
+            call.extend([index, flags]);
+        }
+        call.extend((data.len() as u16).to_le_bytes());
+        call.extend_from_slice(data);
+        call.push(seeds.len() as u8);
+        for seed in seeds {
+            call.push(seed.len() as u8);
+            call.extend_from_slice(seed);
+        }
+        let mut code = vec![OP_INVOKE];
+        code.extend((call.len() as u16).to_le_bytes());
+        code.extend(call);
+        code
+    }
+
+    // Run `bytecode` deployed at PROGRAM over [PAYER, `pda`, OTHER]:
+    // PAYER signs, OTHER is readonly.
+    fn run_invoking(bytecode: &[u8], pda: Pubkey, db: &mut AccountsDB) -> Result<(), SvmError> {
+        let mut program = AccountSharedData::new(1, 0, LOADER_ID);
+        *program.data_mut() = bytecode.to_vec();
+        program.set_executable(true);
+        db.store(PROGRAM, program);
+
+        let header = MessageHeader {
+            num_required_signatures:        1,
+            num_readonly_signed_accounts:   0,
+            num_readonly_unsigned_accounts: 3,
+        };
+        let message = Message::new(
+            header,
+            vec![PAYER, pda, OTHER, PROGRAM, SYSTEM_PROGRAM_ID],
+            Hash::default(),
+            vec![CompiledInstruction::new(3, vec![0, 1, 2], vec![])],
+        );
+        run(&Transaction::new(message, vec![Default::default()]), db)
+    }
+
+    #[test]
+    fn program_creates_a_pda_owned_account_through_invoke_signed() {
+        let (pda, bump) = Pubkey::find_program_address(&[b"vault"], &PROGRAM);
+        let create = system::create_account(&PAYER, &pda, 300, 16, &PROGRAM).data;
+        let code   = invoke_op(&SYSTEM_PROGRAM_ID, &[(0, 3), (1, 3)], &create, &[b"vault", &[bump]]);
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+
+        assert_eq!(run_invoking(&code, pda, &mut db), Ok(()));
+        let created = db.load(&pda).unwrap();
+        assert_eq!(created.owner(), &PROGRAM);
+        assert_eq!(created.lamports(), 300);
+        assert_eq!(created.data().len(), 16);
+        assert_eq!(lamports(&db, &PAYER), 700);
+    }
+
+    #[test]
+    fn invoke_signed_with_mismatched_seeds_is_rejected() {
+        // Seeds for a different PDA of the same program: the program does
+        // not get to sign for `pda`.
+        let (pda, _)    = Pubkey::find_program_address(&[b"vault"], &PROGRAM);
+        let (_, bump)   = Pubkey::find_program_address(&[b"other"], &PROGRAM);
+        let create = system::create_account(&PAYER, &pda, 300, 16, &PROGRAM).data;
+        let code   = invoke_op(&SYSTEM_PROGRAM_ID, &[(0, 3), (1, 3)], &create, &[b"other", &[bump]]);
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+
+        assert_eq!(
+            run_invoking(&code, pda, &mut db),
+            Err(SvmError::Program {
+                instruction: 0,
+                error: InterpreterError::Invoke { pc: 0, error: InvokeError::PrivilegeEscalation { pubkey: pda } },
+            })
+        );
+        assert!(db.load(&pda).is_none());
+        assert_eq!(lamports(&db, &PAYER), 1_000);
+    }
+
+    #[test]
+    fn invoke_signed_may_not_make_a_readonly_account_writable() {
+        let transfer = system::transfer(&PAYER, &OTHER, 10).data;
+        let code     = invoke_op(&SYSTEM_PROGRAM_ID, &[(0, 3), (2, 2)], &transfer, &[]);
+        let mut db   = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+
+        assert_eq!(
+            run_invoking(&code, DATA, &mut db),
+            Err(SvmError::Program {
+                instruction: 0,
+                error: InterpreterError::Invoke { pc: 0, error: InvokeError::PrivilegeEscalation { pubkey: OTHER } },
+            })
+        );
+        assert_eq!(lamports(&db, &PAYER), 1_000);
+    }
+
+    // One instruction of the program at PROGRAM over DATA and OTHER, with
+    // OTHER readonly: [PAYER, DATA | PROGRAM, OTHER].
+    fn tx_with_readonly_other(accounts: Vec<u8>) -> Transaction {
+        let header = MessageHeader {
+            num_required_signatures:        1,
+            num_readonly_signed_accounts:   0,
+            num_readonly_unsigned_accounts: 2,
+        };
+        let message = Message::new(
+            header,
+            vec![PAYER, DATA, PROGRAM, OTHER],
+            Hash::default(),
+            vec![CompiledInstruction::new(2, accounts, vec![])],
+        );
+        Transaction::new(message, vec![Default::default()])
+    }
+
+    #[test]
+    fn crediting_a_readonly_account_is_rejected() {
+        // PROGRAM owns both, so only writability stops the move.
+        let mut db = db_with_program(&move_100(Some(0), 1));
+        let tx     = tx_with_readonly_other(vec![1, 3]);
+
+        assert_eq!(run(&tx, &mut db), Err(SvmError::ReadonlyLamportChange { instruction: 0, account: 1 }));
+        assert_eq!(lamports(&db, &OTHER), 1_000);
+    }
+
+    #[test]
+    fn writing_a_readonly_accounts_data_is_rejected() {
+        let mut db    = db_with_program(&[OP_PUSH, 7, OP_STORE_DATA, 1, 0]);
+        db.store(OTHER, AccountSharedData::new(1_000, 8, PROGRAM));
+        let tx = tx_with_readonly_other(vec![1, 3]);
+
+        assert_eq!(run(&tx, &mut db), Err(SvmError::ReadonlyDataModified { instruction: 0, account: 1 }));
+        assert_eq!(db.load(&OTHER).unwrap().data(), &[0; 8]);
+    }
+
+    #[test]
+    fn token_program_sees_an_account_created_for_it_earlier_in_the_transaction() {
+        // PAYER creates NEW owned by the token program, which then makes it
+        // an account of MINT for OTHER: the second instruction must see the
+        // owner and space the first one gave it.
+        const NEW: Pubkey  = Pubkey([5; 32]);
+        const MINT: Pubkey = Pubkey([6; 32]);
+        let mut db = AccountsDB::new();
+        db.store(PAYER, AccountSharedData::new(10_000_000, 0, SYSTEM_PROGRAM_ID));
+        let mut mint = AccountSharedData::new(1_000_000, 0, TOKEN_PROGRAM_ID);
+        *mint.data_mut() = token::Mint { mint_authority: PAYER, supply: 0, decimals: 6, is_initialized: true }.to_data();
+        db.store(MINT, mint);
+
+        let message = Message::compile(Some(&PAYER), &[
+            system::create_account(&PAYER, &NEW, 2_000_000, token::TOKEN_ACCOUNT_SIZE as u64, &TOKEN_PROGRAM_ID),
+            Instruction::new(TOKEN_PROGRAM_ID, vec![
+                AccountMeta::new(NEW, false),
+                AccountMeta::new_readonly(MINT, false),
+                AccountMeta::new_readonly(OTHER, false),
+            ], vec![1]),
+        ])
+        .unwrap();
+        let tx = Transaction::new(message, vec![Default::default(); 2]);
+
+        assert_eq!(run(&tx, &mut db), Ok(()));
+        let account = db.load(&NEW).unwrap();
+        assert_eq!(account.owner(), &TOKEN_PROGRAM_ID);
+        assert_eq!(
+            token::TokenAccount::from_data(account.data()),
+            Some(token::TokenAccount { mint: MINT, owner: OTHER, amount: 0, is_initialized: true })
+        );
+    }
+
+    #[test]
+    fn missing_and_unrunnable_programs_are_told_apart() {
+        let tx = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1], vec![])]);
+
+        let mut db = db_with_program(&[]);
+        db.delete(&PROGRAM);
+        let missing = run(&tx, &mut db).unwrap_err();
+        assert_eq!(missing, SvmError::ProgramAccountNotFound { instruction: 0 });
+        assert_eq!(TransactionError::from(missing), TransactionError::ProgramAccountNotFound);
+
+        // Present, but not an executable account the loader owns.
+        let mut not_executable = AccountSharedData::new(1, 0, LOADER_ID);
+        not_executable.set_executable(false);
+        let mut not_loaded = AccountSharedData::new(1, 0, SYSTEM_PROGRAM_ID);
+        not_loaded.set_executable(true);
+        for program in [not_executable, not_loaded] {
+            db.store(PROGRAM, program);
+            let unsupported = run(&tx, &mut db).unwrap_err();
+            assert_eq!(unsupported, SvmError::UnsupportedProgram { instruction: 0 });
+            assert_eq!(TransactionError::from(unsupported), TransactionError::InvalidProgramForExecution);
+        }
+    }
+
+    #[test]
+    fn program_may_credit_but_not_debit_an_account_it_does_not_own() {
+        // Positions [DATA, OTHER]; OTHER belongs to the SystemProgram.
+        let mut db = db_with_program(&move_100(Some(1), 0));
+        db.store(OTHER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        let tx = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1, 2], vec![])]);
+
+        assert_eq!(run(&tx, &mut db), Err(SvmError::ExternalAccountLamportSpend { instruction: 0, account: 1 }));
+        assert_eq!((lamports(&db, &DATA), lamports(&db, &OTHER)), (1_000, 1_000));
+
+        let mut db = db_with_program(&move_100(Some(0), 1));
+        db.store(OTHER, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        assert_eq!(run(&tx, &mut db), Ok(()));
+        assert_eq!((lamports(&db, &DATA), lamports(&db, &OTHER)), (900, 1_100));
+    }
+
+    #[test]
+    fn interpreted_program_reads_its_input_and_fails_with_the_opcode_at_fault() {
+        // Store the input's first u64 in DATA and log it.
+        let mut db = db_with_program(&[OP_LOAD_INPUT, 0, OP_DUP, OP_LOG, OP_STORE_DATA, 0, 0]);
+        db.store(DATA, AccountSharedData::new(1_000, 8, PROGRAM));
+        let store    = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1], 42u64.to_le_bytes().to_vec())]);
+        let mut logs = LogCollector::new();
+        assert_eq!(execute(&store, &mut db, &Hash::default(), &mut logs), Ok(()));
+        assert_eq!(db.load(&DATA).unwrap().data(), &42u64.to_le_bytes());
+        assert!(logs.into_messages().contains(&"Program log: 42".to_string()));
+
+        let tx = tx(PROGRAM, vec![CompiledInstruction::new(3, vec![1], vec![])]);
+        for (bytecode, error) in [
+            (vec![OP_ABORT, 7], InterpreterError::Aborted(7)),
+            (vec![OP_PUSH, 0, OP_PUSH, 1, OP_SUB], InterpreterError::ArithmeticOverflow { pc: 4 }),
+            (vec![OP_PUSH, 1, OP_ADD], InterpreterError::StackUnderflow { pc: 2 }),
+            (vec![OP_LOAD_INPUT, 0], InterpreterError::InputOutOfBounds { pc: 0, offset: 0 }),
+            (vec![OP_LOAD_LAMPORTS, 1], InterpreterError::AccountIndexOutOfBounds { pc: 0, index: 1 }),
+        ] {
+            let mut db = db_with_program(&bytecode);
+            assert_eq!(run(&tx, &mut db), Err(SvmError::Program { instruction: 0, error }));
+        }
+    }
+
+    #[test]
+    fn two_transfers_from_one_source_apply_together_or_not_at_all() {
+        let transfers = |second: u64| {
+            let message = Message::compile(Some(&PAYER), &[
+                system::transfer(&PAYER, &DATA, 100),
+                system::transfer(&PAYER, &OTHER, second),
+            ])
+            .unwrap();
+            Transaction::new(message, vec![Default::default()])
+        };
+        let mut db = AccountsDB::new();
+        for key in [PAYER, DATA, OTHER] {
+            db.store(key, AccountSharedData::new(1_000, 0, SYSTEM_PROGRAM_ID));
+        }
+
+        assert_eq!(run(&transfers(200), &mut db), Ok(()));
+        assert_eq!([PAYER, DATA, OTHER].map(|key| lamports(&db, &key)), [700, 1_100, 1_200]);
+
+        // 100 leaves PAYER 600, short of the second transfer's 650.
+        assert_eq!(
+            run(&transfers(650), &mut db),
+            Err(SvmError::SystemProgram { instruction: 1, error: system::SystemProgramError::InsufficientFunds })
+        );
+        assert_eq!([PAYER, DATA, OTHER].map(|key| lamports(&db, &key)), [700, 1_100, 1_200]);
+    }
+}
@@ -0,0 +1,324 @@
+// ---------------------------------------------------------------------------
+// TransactionError — why a transaction was rejected or failed, in the shape
+// Solana clients match on.
+//
+// Internally each layer has its own error enum (BankError, SvmError,
+// SystemProgramError) whose Debug output is fine for logs but means
+// nothing to a client. This is the stable, machine-readable form the RPC
+// returns instead, serialized the way Solana's serde output looks:
+//
+//   "BlockhashNotFound"
+//   { "InstructionError": [0, { "Custom": 1 }] }
+//   { "InstructionError": [1, "MissingRequiredSignature"] }
+//
+// A few variants have no Solana equivalent because the behaviour they
+// describe is specific to this node; they are marked below.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/transaction-error/src/lib.rs
+// ---------------------------------------------------------------------------
+
+use serde_json::{json, Value};
+
+use crate::programs::address_lookup_table::AddressLookupError;
+use crate::programs::loader::LoaderError;
+use crate::programs::memo::MemoError;
+use crate::programs::system::SystemProgramError;
+use crate::programs::token::TokenError;
+use crate::runtime::bank::BankError;
+use crate::runtime::interpreter::InterpreterError;
+use crate::runtime::svm::{InvokeError, SvmError};
+
+// ---------------------------------------------------------------------------
+// TransactionError
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransactionError {
+    /// The transaction is malformed: no fee payer, fewer signatures than
+    /// the header requires, or a header or instruction index that does not
+    /// fit account_keys.
+    SanitizeFailure,
+    /// A signature did not verify, or a signer key is not an Ed25519 key.
+    SignatureFailure,
+    /// recent_blockhash is neither recent nor a stored durable nonce.
+    BlockhashNotFound,
+    /// The fee payer cannot cover the fee.
+    InsufficientFundsForFee,
+    /// The transaction references more accounts than the node allows.
+    TooManyAccountLocks,
+    /// This transaction has already been processed.
+    AlreadyProcessed,
+    /// An account the transaction uses is locked by another in-flight
+    /// transaction.
+    AccountInUse,
+    /// An instruction references an account index past account_keys.
+    InvalidAccountIndex,
+    /// An instruction's program does not exist.
+    ProgramAccountNotFound,
+    /// An instruction's program exists but cannot be executed.
+    InvalidProgramForExecution,
+    /// A ComputeBudget instruction is malformed or repeated.
+    InvalidComputeBudget,
+    /// A v0 message names a lookup table account that does not exist.
+    AddressLookupTableNotFound,
+    /// A v0 message's lookup table is not owned by the lookup table program.
+    InvalidAddressLookupTableOwner,
+    /// A v0 message's lookup table account data is malformed.
+    InvalidAddressLookupTableData,
+    /// A v0 message looks up an entry past the end of its table.
+    InvalidAddressLookupTableIndex,
+    /// Not in Solana: the fee exceeds the max fee the client set.
+    FeeExceedsMax { fee: u64, max_fee: u64 },
+    /// Not in Solana: a node middleware check refused the transaction.
+    RejectedByMiddleware(String),
+    /// Not in Solana: an airdrop asked for more than the faucet's
+    /// per-request cap.
+    AirdropCapExceeded { lamports: u64, cap: u64 },
+    /// Instruction number `.0` failed; the transaction was rolled back.
+    InstructionError(u8, InstructionError),
+}
+
+// ---------------------------------------------------------------------------
+// InstructionError — why a single instruction failed.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub enum InstructionError {
+    InvalidInstructionData,
+    InsufficientFunds,
+    MissingRequiredSignature,
+    NotEnoughAccountKeys,
+    InvalidAccountOwner,
+    IncorrectProgramId,
+    InvalidAccountData,
+    ExternalAccountLamportSpend,
+    ExternalAccountDataModified,
+    /// An account's owner was changed by a program that did not own it.
+    ModifiedProgramId,
+    UnbalancedInstruction,
+    AccountAlreadyInitialized,
+    AccountDataTooSmall,
+    ArithmeticOverflow,
+    /// The program changed two copies of an account the instruction
+    /// lists twice in different ways.
+    DuplicateAccountOutOfSync,
+    /// The program changed the lamports of a readonly account.
+    ReadonlyLamportChange,
+    /// The program changed the data, owner or executable flag of a
+    /// readonly account.
+    ReadonlyDataModified,
+    /// A cross-program invocation passed an account as a signer or as
+    /// writable without the caller having that privilege.
+    PrivilegeEscalation,
+    /// A cross-program invocation named an account its caller was not
+    /// passed.
+    MissingAccount,
+    /// A program signed with seeds that do not derive a valid PDA.
+    InvalidSeeds,
+    /// A cross-program invocation targeted a program it cannot reach.
+    UnsupportedProgramId,
+    /// The program crashed: a bad opcode or stack misuse.
+    ProgramFailedToComplete,
+    /// A program-specific error code (e.g. SystemError).
+    Custom(u32),
+}
+
+impl TransactionError {
+    /// The JSON clients see, in Solana's serde shape.
+    pub fn to_json(&self) -> Value {
+        match self {
+            TransactionError::FeeExceedsMax { fee, max_fee } => {
+                json!({ "FeeExceedsMax": { "fee": fee, "max_fee": max_fee } })
+            }
+            TransactionError::RejectedByMiddleware(reason) => json!({ "RejectedByMiddleware": reason }),
+            TransactionError::AirdropCapExceeded { lamports, cap } => {
+                json!({ "AirdropCapExceeded": { "lamports": lamports, "cap": cap } })
+            }
+            TransactionError::InstructionError(index, error) => {
+                json!({ "InstructionError": [index, error.to_json()] })
+            }
+            // Unit variants serialize as their bare name.
+            unit => Value::String(format!("{:?}", unit)),
+        }
+    }
+}
+
+impl InstructionError {
+    /// The JSON clients see, in Solana's serde shape.
+    pub fn to_json(&self) -> Value {
+        match self {
+            InstructionError::Custom(code) => json!({ "Custom": code }),
+            unit => Value::String(format!("{:?}", unit)),
+        }
+    }
+}
+
+impl From<BankError> for TransactionError {
+    fn from(error: BankError) -> Self {
+        match error {
+            BankError::NotEnoughSignatures { .. }
+            | BankError::TooManySignatures { .. }
+            | BankError::NoFeePayer
+            | BankError::InvalidMessage(_)              => TransactionError::SanitizeFailure,
+            BankError::InvalidPublicKey { .. }
+            | BankError::SignatureVerificationFailed { .. } => TransactionError::SignatureFailure,
+            BankError::InvalidComputeBudget(_)          => TransactionError::InvalidComputeBudget,
+            BankError::FeeExceedsMax { fee, max_fee }   => TransactionError::FeeExceedsMax { fee, max_fee },
+            BankError::BlockhashNotFound                => TransactionError::BlockhashNotFound,
+            BankError::InsufficientFundsForFee { .. }   => TransactionError::InsufficientFundsForFee,
+            BankError::TooManyAccounts { .. }           => TransactionError::TooManyAccountLocks,
+            BankError::AlreadyProcessed                 => TransactionError::AlreadyProcessed,
+            BankError::AccountInUse(_)                  => TransactionError::AccountInUse,
+            BankError::AddressLookup(error)             => match error {
+                AddressLookupError::LookupTableAccountNotFound => TransactionError::AddressLookupTableNotFound,
+                AddressLookupError::InvalidAccountOwner        => TransactionError::InvalidAddressLookupTableOwner,
+                AddressLookupError::InvalidAccountData         => TransactionError::InvalidAddressLookupTableData,
+                AddressLookupError::InvalidLookupIndex         => TransactionError::InvalidAddressLookupTableIndex,
+                AddressLookupError::TooManyAccountKeys { .. }  => TransactionError::SanitizeFailure,
+            },
+        }
+    }
+}
+
+impl From<SvmError> for TransactionError {
+    fn from(error: SvmError) -> Self {
+        let at = |instruction: usize, error| TransactionError::InstructionError(instruction as u8, error);
+        match error {
+            SvmError::InvalidAccountIndex { .. }    => TransactionError::InvalidAccountIndex,
+            SvmError::ProgramAccountNotFound { .. } => TransactionError::ProgramAccountNotFound,
+            SvmError::UnsupportedProgram { .. }     => TransactionError::InvalidProgramForExecution,
+            SvmError::SystemProgram { instruction, error } => at(instruction, system_error(error)),
+            SvmError::Token { instruction, error } => at(instruction, token_error(error)),
+            SvmError::Memo { instruction, error } => at(instruction, memo_error(error)),
+            SvmError::ComputeBudget { instruction, .. } => at(instruction, InstructionError::InvalidInstructionData),
+            SvmError::ExternalAccountLamportSpend { instruction, .. } => {
+                at(instruction, InstructionError::ExternalAccountLamportSpend)
+            }
+            SvmError::UnbalancedInstruction { instruction, .. } => {
+                at(instruction, InstructionError::UnbalancedInstruction)
+            }
+            SvmError::IllegalOwnerChange { instruction, .. } => {
+                at(instruction, InstructionError::ModifiedProgramId)
+            }
+            SvmError::ExternalAccountDataModified { instruction, .. } => {
+                at(instruction, InstructionError::ExternalAccountDataModified)
+            }
+            SvmError::DuplicateAccountOutOfSync { instruction, .. } => {
+                at(instruction, InstructionError::DuplicateAccountOutOfSync)
+            }
+            SvmError::ReadonlyLamportChange { instruction, .. } => {
+                at(instruction, InstructionError::ReadonlyLamportChange)
+            }
+            SvmError::ReadonlyDataModified { instruction, .. } => {
+                at(instruction, InstructionError::ReadonlyDataModified)
+            }
+            SvmError::Loader { instruction, error }  => at(instruction, loader_error(error)),
+            SvmError::Program { instruction, error } => at(instruction, interpreter_error(error)),
+        }
+    }
+}
+
+// How a SystemProgram failure is reported. Failures the real program
+// reports as a SystemError carry its code as Custom:
+//   0 AccountAlreadyInUse, 1 ResultWithNegativeLamports (overdraft),
+//   3 InvalidAccountDataLength, 7 NonceBlockhashNotExpired
+fn system_error(error: SystemProgramError) -> InstructionError {
+    match error {
+        SystemProgramError::InvalidInstructionData
+        | SystemProgramError::UnknownInstruction(_)  => InstructionError::InvalidInstructionData,
+        SystemProgramError::AccountAlreadyInUse      => InstructionError::Custom(0),
+        SystemProgramError::InsufficientFunds        => InstructionError::Custom(1),
+        SystemProgramError::InvalidAccountDataLength => InstructionError::Custom(3),
+        SystemProgramError::NonceBlockhashNotExpired => InstructionError::Custom(7),
+        SystemProgramError::AccountNotOwnedBySystem  => InstructionError::InvalidAccountOwner,
+        SystemProgramError::NotEnoughAccounts        => InstructionError::NotEnoughAccountKeys,
+        SystemProgramError::MissingRequiredSignature => InstructionError::MissingRequiredSignature,
+        SystemProgramError::InvalidNonceState        => InstructionError::InvalidAccountData,
+        SystemProgramError::InsufficientFundsForRent => InstructionError::InsufficientFunds,
+    }
+}
+
+// How a token program failure is reported. Failures SPL Token reports as
+// a TokenError carry its code as Custom:
+//   1 InsufficientFunds, 2 InvalidMint, 3 MintMismatch, 4 OwnerMismatch,
+//   6 AlreadyInUse, 9 UninitializedState, 14 Overflow
+fn token_error(error: TokenError) -> InstructionError {
+    match error {
+        TokenError::InvalidInstructionData
+        | TokenError::UnknownInstruction(_)   => InstructionError::InvalidInstructionData,
+        TokenError::NotEnoughAccounts         => InstructionError::NotEnoughAccountKeys,
+        TokenError::MissingRequiredSignature  => InstructionError::MissingRequiredSignature,
+        TokenError::IncorrectProgramId        => InstructionError::IncorrectProgramId,
+        TokenError::InvalidAccountData        => InstructionError::InvalidAccountData,
+        TokenError::InsufficientFunds         => InstructionError::Custom(1),
+        TokenError::InvalidMint               => InstructionError::Custom(2),
+        TokenError::MintMismatch              => InstructionError::Custom(3),
+        TokenError::OwnerMismatch             => InstructionError::Custom(4),
+        TokenError::AlreadyInUse              => InstructionError::Custom(6),
+        TokenError::UninitializedState        => InstructionError::Custom(9),
+        TokenError::Overflow                  => InstructionError::Custom(14),
+    }
+}
+
+// How a memo failure is reported: SPL Memo fails with
+// InvalidInstructionData for bad UTF-8 and MissingRequiredSignature for an
+// account that did not sign.
+fn memo_error(error: MemoError) -> InstructionError {
+    match error {
+        MemoError::InvalidUtf8 { .. }              => InstructionError::InvalidInstructionData,
+        MemoError::MissingRequiredSignature { .. } => InstructionError::MissingRequiredSignature,
+    }
+}
+
+// How a loader failure is reported. Bytecode that does not verify is
+// invalid account data, as an ELF that fails verification is in Solana.
+fn loader_error(error: LoaderError) -> InstructionError {
+    match error {
+        LoaderError::InvalidInstructionData
+        | LoaderError::UnknownInstruction(_)   => InstructionError::InvalidInstructionData,
+        LoaderError::NotEnoughAccounts         => InstructionError::NotEnoughAccountKeys,
+        LoaderError::MissingRequiredSignature  => InstructionError::MissingRequiredSignature,
+        LoaderError::AlreadyDeployed
+        | LoaderError::AccountAlreadyInUse     => InstructionError::AccountAlreadyInitialized,
+        LoaderError::InvalidProgram(_)         => InstructionError::InvalidAccountData,
+    }
+}
+
+// How an interpreted program's failure is reported. ABORT's code is the
+// program's own error, so it is Custom, like a SystemError code.
+fn interpreter_error(error: InterpreterError) -> InstructionError {
+    match error {
+        InterpreterError::InvalidOpcode { .. }
+        | InterpreterError::TruncatedInstruction { .. }
+        | InterpreterError::StackUnderflow { .. }
+        | InterpreterError::StackOverflow { .. }
+        | InterpreterError::InvalidInvoke { .. }        => InstructionError::ProgramFailedToComplete,
+        InterpreterError::AccountIndexOutOfBounds { .. } => InstructionError::NotEnoughAccountKeys,
+        InterpreterError::DataOutOfBounds { .. }         => InstructionError::AccountDataTooSmall,
+        InterpreterError::InputOutOfBounds { .. }        => InstructionError::InvalidInstructionData,
+        InterpreterError::AccountNotOwned { .. }         => InstructionError::ExternalAccountDataModified,
+        InterpreterError::ArithmeticOverflow { .. }      => InstructionError::ArithmeticOverflow,
+        InterpreterError::Aborted(code)                  => InstructionError::Custom(code as u32),
+        InterpreterError::Invoke { error, .. }           => invoke_error(error),
+    }
+}
+
+// How a failed cross-program invocation is reported: the callee's own
+// failure as it would be at the top level, the rest as Solana's CPI
+// errors.
+fn invoke_error(error: InvokeError) -> InstructionError {
+    match error {
+        InvokeError::InvalidSeeds(_)                    => InstructionError::InvalidSeeds,
+        InvokeError::MissingAccount { .. }              => InstructionError::MissingAccount,
+        InvokeError::PrivilegeEscalation { .. }         => InstructionError::PrivilegeEscalation,
+        InvokeError::UnsupportedProgram { .. }          => InstructionError::UnsupportedProgramId,
+        InvokeError::SystemProgram(error)               => system_error(error),
+        InvokeError::Token(error)                       => token_error(error),
+        InvokeError::Memo(error)                        => memo_error(error),
+        InvokeError::ExternalAccountLamportSpend { .. } => InstructionError::ExternalAccountLamportSpend,
+        InvokeError::UnbalancedInstruction { .. }       => InstructionError::UnbalancedInstruction,
+        InvokeError::IllegalOwnerChange { .. }          => InstructionError::ModifiedProgramId,
+        InvokeError::ExternalAccountDataModified { .. } => InstructionError::ExternalAccountDataModified,
+        InvokeError::DuplicateAccountOutOfSync { .. }   => InstructionError::DuplicateAccountOutOfSync,
+        InvokeError::ReadonlyLamportChange { .. }       => InstructionError::ReadonlyLamportChange,
+        InvokeError::ReadonlyDataModified { .. }        => InstructionError::ReadonlyDataModified,
+    }
+}
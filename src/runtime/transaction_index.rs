@@ -0,0 +1,77 @@
+// ---------------------------------------------------------------------------
+// TransactionIndex — where each recorded transaction sits in the ledger.
+//
+// PoH entries hold transactions but nothing to find one by: looking a
+// signature up would mean scanning every entry. The index maps each
+// recorded transaction's signature (signatures[0], its id) to the slot and
+// the index of the entry it was recorded in, plus how it executed, so
+// getTransaction answers with one lookup and one entry access.
+//
+// Entry indices are absolute, as PohGenerator::first_entry_index counts
+// them, so they stay valid while older entries are purged. Statuses for
+// purged entries are dropped with purge_before(): their transactions can
+// no longer be returned anyway.
+//
+// Reference: https://github.com/anza-xyz/agave/blob/master/ledger/src/blockstore.rs (transaction_status_index)
+// ---------------------------------------------------------------------------
+
+use std::collections::HashMap;
+
+use crate::runtime::transaction_error::TransactionError;
+use crate::types::account::Slot;
+use crate::types::transaction::Signature;
+
+// ---------------------------------------------------------------------------
+// TransactionStatus — one recorded transaction's place and outcome.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionStatus {
+    /// Slot the transaction was recorded in.
+    pub slot:        Slot,
+    /// Absolute index of the PoH entry that holds it.
+    pub entry_index: usize,
+    /// Why execution failed; None if it succeeded. The fee was paid
+    /// either way.
+    pub err:         Option<TransactionError>,
+}
+
+// ---------------------------------------------------------------------------
+// TransactionIndex
+// ---------------------------------------------------------------------------
+#[derive(Debug, Default)]
+pub struct TransactionIndex {
+    statuses: HashMap<Signature, TransactionStatus>,
+}
+
+impl TransactionIndex {
+    /// An empty index.
+    pub fn new() -> Self {
+        TransactionIndex::default()
+    }
+
+    /// Record where the transaction with `signature` was recorded and how
+    /// it executed.
+    pub fn insert(&mut self, signature: Signature, status: TransactionStatus) {
+        self.statuses.insert(signature, status);
+    }
+
+    /// The status of the transaction with `signature`, if it is indexed.
+    pub fn get(&self, signature: &Signature) -> Option<&TransactionStatus> {
+        self.statuses.get(signature)
+    }
+
+    /// Drop the statuses of transactions in entries before `entry_index`.
+    pub fn purge_before(&mut self, entry_index: usize) {
+        self.statuses.retain(|_, status| status.entry_index >= entry_index);
+    }
+
+    /// How many transactions are indexed.
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    /// Whether no transaction is indexed.
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+}
@@ -11,6 +11,11 @@
 use std::fmt;
 use std::sync::Arc;
 
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha2::{Digest, Sha256};
+
+use crate::types::rent::Rent;
+
 // ---------------------------------------------------------------------------
 // Pubkey — a 32-byte address (Ed25519 public key).
 //
@@ -19,7 +24,7 @@ use std::sync::Arc;
 //
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/address/src/lib.rs
 // ---------------------------------------------------------------------------
-#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash, BorshSerialize, BorshDeserialize)]
 pub struct Pubkey(pub [u8; 32]);
 
 impl Pubkey {
@@ -41,6 +46,95 @@ impl Pubkey {
     pub fn is_default(&self) -> bool {
         self.0 == [0u8; 32]
     }
+
+    /// Parse the base58 form wallets and RPC clients use.
+    /// Returns None unless the string decodes to exactly 32 bytes.
+    pub fn from_base58(s: &str) -> Option<Self> {
+        let bytes = bs58::decode(s).into_vec().ok()?;
+        Some(Pubkey(bytes.try_into().ok()?))
+    }
+
+    /// The base58 form, e.g. "11111111111111111111111111111111" for zeros.
+    pub fn to_base58(self) -> String {
+        bs58::encode(self.0).into_string()
+    }
+
+    /// Whether these bytes are a point on the Ed25519 curve, i.e. whether
+    /// a private key for this address could exist.
+    pub fn is_on_curve(&self) -> bool {
+        ed25519_dalek::VerifyingKey::from_bytes(&self.0).is_ok()
+    }
+
+    /// The program derived address for `seeds` under `program_id`. Fails if
+    /// the seeds are too many or too long, or if the address lands on the
+    /// curve (see below).
+    pub fn create_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> Result<Pubkey, PubkeyError> {
+        if seeds.len() > MAX_SEEDS {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        if seeds.iter().any(|seed| seed.len() > MAX_SEED_LEN) {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+
+        let mut hasher = Sha256::new();
+        for seed in seeds {
+            hasher.update(seed);
+        }
+        hasher.update(program_id.0);
+        hasher.update(PDA_MARKER);
+        let address = Pubkey(hasher.finalize().into());
+
+        if address.is_on_curve() {
+            return Err(PubkeyError::InvalidSeeds);
+        }
+        Ok(address)
+    }
+
+    /// The first off-curve program derived address for `seeds` plus a bump
+    /// seed, trying bumps from 255 down, and the bump that found it. About
+    /// half of all bumps work, so failing all 256 does not happen in
+    /// practice; if it did, this panics, as Solana's does.
+    pub fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+        for bump in (0..=u8::MAX).rev() {
+            let bump_seed = [bump];
+            let mut seeds_with_bump = seeds.to_vec();
+            seeds_with_bump.push(&bump_seed);
+            if let Ok(address) = Pubkey::create_program_address(&seeds_with_bump, program_id) {
+                return (address, bump);
+            }
+        }
+        panic!("unable to find a viable program address bump seed");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Program derived addresses (PDAs) — addresses a program signs for.
+//
+// A PDA is the SHA-256 of some seeds, the program's ID and a fixed marker.
+// Only addresses off the Ed25519 curve are accepted, so no private key can
+// sign for one; instead the program that derives it can, by passing the
+// seeds to svm::invoke_signed. find_program_address appends a one-byte
+// "bump" seed and searches for one that lands off the curve.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/address/src/syscalls.rs
+// ---------------------------------------------------------------------------
+
+// Most seeds a PDA may be derived from, the bump included.
+pub const MAX_SEEDS: usize = 16;
+
+// Longest a single seed may be, in bytes.
+pub const MAX_SEED_LEN: usize = 32;
+
+// Appended to every PDA preimage, so a PDA can never collide with another
+// kind of hash.
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PubkeyError {
+    /// More than MAX_SEEDS seeds, or a seed longer than MAX_SEED_LEN.
+    MaxSeedLengthExceeded,
+    /// The seeds hash to a point on the curve, which is not a valid PDA.
+    InvalidSeeds,
 }
 
 impl fmt::Debug for Pubkey {
@@ -75,6 +169,14 @@ impl fmt::Display for Pubkey {
 // ---------------------------------------------------------------------------
 pub type Epoch = u64;
 
+// ---------------------------------------------------------------------------
+// Slot — a u64 index of a leader's turn to produce a block.
+//
+// In Solana, a slot is 64 ticks (~400ms). Account versions are stamped with
+// the slot they were written in so state can be queried "as of" a slot.
+// ---------------------------------------------------------------------------
+pub type Slot = u64;
+
 // ---------------------------------------------------------------------------
 // Lamports — the smallest unit of SOL.
 //
@@ -83,6 +185,19 @@ pub type Epoch = u64;
 // ---------------------------------------------------------------------------
 pub type Lamports = u64;
 
+// Largest data an account may hold: 10 MiB. Matches
+// MAX_PERMITTED_DATA_LENGTH in real Solana.
+pub const MAX_PERMITTED_DATA_LENGTH: usize = 10 * 1024 * 1024;
+
+// ---------------------------------------------------------------------------
+// DataTooLarge — a resize past MAX_PERMITTED_DATA_LENGTH was refused.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DataTooLarge {
+    /// The length asked for.
+    pub len: usize,
+}
+
 // ---------------------------------------------------------------------------
 // Account — the owned version.
 //
@@ -110,7 +225,7 @@ pub struct Account {
     /// Arbitrary data stored in this account. For a wallet, this is empty.
     /// For a token account, this holds the token balance, mint, owner, etc.
     /// For a program, this holds the compiled BPF bytecode.
-    /// Max size: 10 MB.
+    /// Max size: MAX_PERMITTED_DATA_LENGTH (10 MiB).
     pub data: Vec<u8>,
 
     /// The program that owns this account.
@@ -161,7 +276,7 @@ impl Account {
     }
 
     /// Convert to the shared (Arc-backed) version.
-    pub fn to_shared(self) -> AccountSharedData {
+    pub fn into_shared(self) -> AccountSharedData {
         AccountSharedData {
             lamports: self.lamports,
             data: Arc::new(self.data),
@@ -232,6 +347,19 @@ impl AccountSharedData {
         self.rent_epoch
     }
 
+    // --- Rent ---
+
+    /// The balance this account needs to be rent-exempt at its current
+    /// data length.
+    pub fn rent_exempt_reserve(&self, rent: &Rent) -> Lamports {
+        rent.minimum_balance(self.data.len())
+    }
+
+    /// Whether the account holds at least its rent-exempt reserve.
+    pub fn is_rent_exempt(&self, rent: &Rent) -> bool {
+        self.lamports >= self.rent_exempt_reserve(rent)
+    }
+
     // --- Setters ---
 
     pub fn set_lamports(&mut self, lamports: Lamports) {
@@ -245,6 +373,17 @@ impl AccountSharedData {
         Arc::make_mut(&mut self.data)
     }
 
+    /// Grow or shrink the data to `len` bytes, zero-filling new space.
+    /// Refuses anything over MAX_PERMITTED_DATA_LENGTH, leaving the data
+    /// as it was, so a client cannot make the node allocate without bound.
+    pub fn resize_data(&mut self, len: usize) -> Result<(), DataTooLarge> {
+        if len > MAX_PERMITTED_DATA_LENGTH {
+            return Err(DataTooLarge { len });
+        }
+        self.data_mut().resize(len, 0);
+        Ok(())
+    }
+
     pub fn set_owner(&mut self, owner: Pubkey) {
         self.owner = owner;
     }
@@ -268,3 +407,26 @@ impl AccountSharedData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_data_zero_fills_and_refuses_past_the_cap() {
+        let mut account = AccountSharedData::new(1, 2, Pubkey::default());
+        account.data_mut().copy_from_slice(&[7, 7]);
+
+        assert_eq!(account.resize_data(4), Ok(()));
+        assert_eq!(account.data(), &[7, 7, 0, 0]);
+        assert_eq!(account.resize_data(1), Ok(()));
+        assert_eq!(account.data(), &[7]);
+
+        // Over the cap is refused and the data is left as it was.
+        let len = MAX_PERMITTED_DATA_LENGTH + 1;
+        assert_eq!(account.resize_data(len), Err(DataTooLarge { len }));
+        assert_eq!(account.data(), &[7]);
+        assert_eq!(account.resize_data(MAX_PERMITTED_DATA_LENGTH), Ok(()));
+        assert_eq!(account.data().len(), MAX_PERMITTED_DATA_LENGTH);
+    }
+}
@@ -0,0 +1,38 @@
+// ---------------------------------------------------------------------------
+// AccountState — structured program state stored in account data.
+//
+// A program's state is a plain struct deriving BorshSerialize and
+// BorshDeserialize, the encoding Solana programs use: fields in declaration
+// order, integers little-endian, a Pubkey as its 32 bytes, a bool as one
+// byte that must be 0 or 1. With fixed-size fields the encoding has a fixed
+// size, which the struct declares as SIZE, so the account that holds it is
+// created with exactly that much space.
+//
+// Instruction data keeps its hand-written decoders (system::decode and the
+// like): their layouts are Solana's wire formats, not Borsh.
+//
+// Reference: https://borsh.io
+// ---------------------------------------------------------------------------
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub trait AccountState: BorshSerialize + BorshDeserialize {
+    /// Bytes of account data the encoded state occupies.
+    const SIZE: usize;
+
+    /// Parse an account's data. None unless it is exactly SIZE bytes of a
+    /// valid encoding.
+    fn from_data(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::SIZE {
+            return None;
+        }
+        borsh::from_slice(data).ok()
+    }
+
+    /// Serialize into exactly SIZE bytes of account data.
+    fn to_data(&self) -> Vec<u8> {
+        let data = borsh::to_vec(self).expect("serializing into a Vec cannot fail");
+        debug_assert_eq!(data.len(), Self::SIZE);
+        data
+    }
+}
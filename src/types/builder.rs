@@ -0,0 +1,120 @@
+// ---------------------------------------------------------------------------
+// TransactionBuilder — assemble a Message one instruction at a time.
+//
+// A Message names accounts by index into one deduplicated account_keys
+// list, sorted by privilege, with a header counting each group (see
+// MessageHeader). Getting that layout right by hand is easy to get wrong.
+// The builder collects pubkey-addressed Instructions, a fee payer and a
+// blockhash, and leaves the layout to Message::compile.
+//
+//   let message = TransactionBuilder::new()
+//       .fee_payer(payer)
+//       .recent_blockhash(blockhash)
+//       .add_transfer(&from, &to, 1_000)
+//       .build()?;
+// ---------------------------------------------------------------------------
+
+use crate::programs::system;
+use crate::types::account::Pubkey;
+use crate::types::transaction::{CompileError, Hash, Instruction, Message};
+
+// ---------------------------------------------------------------------------
+// TransactionBuilder
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, Default)]
+pub struct TransactionBuilder {
+    fee_payer:        Option<Pubkey>,
+    recent_blockhash: Hash,
+    instructions:     Vec<Instruction>,
+}
+
+impl TransactionBuilder {
+    /// A builder with no instructions, no fee payer and a zero blockhash.
+    pub fn new() -> Self {
+        TransactionBuilder::default()
+    }
+
+    /// The account that pays the fee. Without one, the first account
+    /// listed is first in account_keys; it must be a signer for the
+    /// transaction to pay a fee.
+    pub fn fee_payer(mut self, fee_payer: Pubkey) -> Self {
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    /// The blockhash the transaction is valid against.
+    pub fn recent_blockhash(mut self, recent_blockhash: Hash) -> Self {
+        self.recent_blockhash = recent_blockhash;
+        self
+    }
+
+    /// Append an instruction.
+    pub fn add_instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Append a SystemProgram Transfer.
+    pub fn add_transfer(self, from: &Pubkey, to: &Pubkey, lamports: u64) -> Self {
+        self.add_instruction(system::transfer(from, to, lamports))
+    }
+
+    /// Append a SystemProgram CreateAccount.
+    pub fn add_create_account(self, from: &Pubkey, to: &Pubkey, lamports: u64, space: u64, owner: &Pubkey) -> Self {
+        self.add_instruction(system::create_account(from, to, lamports, space, owner))
+    }
+
+    /// Compile the instructions into an unsigned Message (see
+    /// Message::compile for the layout).
+    pub fn build(self) -> Result<Message, CompileError> {
+        let mut message = Message::compile(self.fee_payer.as_ref(), &self.instructions)?;
+        message.recent_blockhash = self.recent_blockhash;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::system::SYSTEM_PROGRAM_ID;
+    use crate::types::transaction::{CompiledInstruction, MessageHeader};
+
+    const FROM:  Pubkey = Pubkey([1; 32]);
+    const TO:    Pubkey = Pubkey([2; 32]);
+    const PAYER: Pubkey = Pubkey([3; 32]);
+    const OWNER: Pubkey = Pubkey([4; 32]);
+
+    #[test]
+    fn transfer_builds_the_hand_rolled_message() {
+        let blockhash = Hash([9; 32]);
+        let built = TransactionBuilder::new().recent_blockhash(blockhash).add_transfer(&FROM, &TO, 1_000).build();
+
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        let by_hand = Message::new(
+            MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+            vec![FROM, TO, SYSTEM_PROGRAM_ID],
+            blockhash,
+            vec![CompiledInstruction::new(2, vec![0, 1], data)],
+        );
+        assert_eq!(built, Ok(by_hand));
+    }
+
+    #[test]
+    fn fee_payer_comes_first_and_shared_keys_appear_once() {
+        let message = TransactionBuilder::new()
+            .fee_payer(PAYER)
+            .add_create_account(&FROM, &TO, 100, 8, &OWNER)
+            .add_transfer(&TO, &FROM, 10)
+            .build()
+            .unwrap();
+
+        assert_eq!(message.account_keys, vec![PAYER, FROM, TO, SYSTEM_PROGRAM_ID]);
+        assert_eq!(
+            message.header,
+            MessageHeader { num_required_signatures: 3, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 }
+        );
+        assert_eq!(message.instructions[0].accounts, vec![1, 2]);
+        assert_eq!(message.instructions[1].accounts, vec![2, 1]);
+    }
+}
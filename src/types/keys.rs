@@ -0,0 +1,28 @@
+// ---------------------------------------------------------------------------
+// Keys — the node's deterministic development keypairs.
+//
+// The node signs for accounts it holds keys for (the /transfer and
+// /createAccount endpoints take a one-byte identifier instead of a
+// signature). Keypair `id` is derived from the Ed25519 seed [id; 32], so
+// every node, and any client, derives the same keys: the account a
+// request calls 1 is always genesis_pubkey(1), wherever it is computed.
+//
+// The address is the verifying key, NOT Pubkey::from_byte(id).
+//
+// These keys are public knowledge. They exist for local development only.
+// ---------------------------------------------------------------------------
+
+use ed25519_dalek::SigningKey;
+
+use crate::types::account::Pubkey;
+
+/// Keypair number `id`: the address and the key that signs for it.
+pub fn genesis_keypair(id: u8) -> (Pubkey, SigningKey) {
+    let key = SigningKey::from_bytes(&[id; 32]);
+    (Pubkey(key.verifying_key().to_bytes()), key)
+}
+
+/// The address of keypair number `id`.
+pub fn genesis_pubkey(id: u8) -> Pubkey {
+    genesis_keypair(id).0
+}
@@ -1,2 +1,7 @@
 pub mod account;
+pub mod account_state;
+pub mod builder;
+pub mod keys;
+pub mod rent;
+pub mod shortvec;
 pub mod transaction;
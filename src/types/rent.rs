@@ -0,0 +1,56 @@
+// ---------------------------------------------------------------------------
+// Rent — the cost of keeping data on-chain.
+//
+// Every account occupies validator memory, so Solana charges for it. In
+// practice accounts never pay rent over time: an account holding at least
+// two years' worth of rent is "rent-exempt", and creating an account below
+// that balance is rejected. The rent-exempt minimum is therefore the real
+// price of an account:
+//
+//   minimum_balance = (ACCOUNT_STORAGE_OVERHEAD + data_len)
+//                     * lamports_per_byte_year * exemption_threshold
+//
+// With the default parameters an empty account costs 890,880 lamports.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/rent/src/lib.rs
+// ---------------------------------------------------------------------------
+
+// Bytes every account is charged for on top of its data — the account
+// metadata (pubkey, owner, lamports, ...) the validator stores with it.
+pub const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+// Default rental rate, in lamports per byte-year. Derived in Solana from
+// $1 per SOL and $0.01 per megabyte-day.
+pub const DEFAULT_LAMPORTS_PER_BYTE_YEAR: u64 = 3480;
+
+// Years of rent an account must hold to be exempt.
+pub const DEFAULT_EXEMPTION_THRESHOLD: f64 = 2.0;
+
+// ---------------------------------------------------------------------------
+// Rent — the rent parameters of a cluster.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rent {
+    /// Rental rate in lamports per byte per year.
+    pub lamports_per_byte_year: u64,
+    /// How many years of rent make an account exempt.
+    pub exemption_threshold: f64,
+}
+
+impl Default for Rent {
+    fn default() -> Self {
+        Rent {
+            lamports_per_byte_year: DEFAULT_LAMPORTS_PER_BYTE_YEAR,
+            exemption_threshold:    DEFAULT_EXEMPTION_THRESHOLD,
+        }
+    }
+}
+
+impl Rent {
+    /// The minimum balance for an account holding `data_len` bytes to be
+    /// rent-exempt.
+    pub fn minimum_balance(&self, data_len: usize) -> u64 {
+        let bytes = ACCOUNT_STORAGE_OVERHEAD.saturating_add(data_len as u64);
+        (bytes.saturating_mul(self.lamports_per_byte_year) as f64 * self.exemption_threshold) as u64
+    }
+}
@@ -0,0 +1,122 @@
+// ---------------------------------------------------------------------------
+// shortvec — Solana's compact-u16 length prefix.
+//
+// Every variable-length array on the Solana wire format (signatures,
+// account keys, instructions, instruction data) is prefixed with its length
+// encoded as a little-endian base-128 varint of at most 3 bytes:
+//
+//   each byte carries 7 bits of the value, low bits first
+//   the high bit (0x80) is set when another byte follows
+//
+//   0      → [0x00]
+//   127    → [0x7f]
+//   128    → [0x80, 0x01]
+//   16383  → [0xff, 0x7f]
+//   16384  → [0x80, 0x80, 0x01]
+//   65535  → [0xff, 0xff, 0x03]   (largest encodable length)
+//
+// Small arrays — the common case — cost a single byte.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/short-vec/src/lib.rs
+// ---------------------------------------------------------------------------
+
+// A compact-u16 never takes more than 3 bytes.
+pub const MAX_ENCODING_LENGTH: usize = 3;
+
+/// Append the compact-u16 encoding of `len` to `buf`.
+///
+/// Panics if `len` does not fit in a u16 — no Solana wire array can be
+/// that long, so reaching this is a bug in the caller.
+pub fn encode_len(len: usize, buf: &mut Vec<u8>) {
+    assert!(len <= u16::MAX as usize, "shortvec length {} exceeds u16", len);
+
+    let mut rem = len;
+    loop {
+        let mut byte = (rem & 0x7f) as u8;
+        rem >>= 7;
+        if rem == 0 {
+            buf.push(byte);
+            break;
+        }
+        byte |= 0x80;
+        buf.push(byte);
+    }
+}
+
+/// Decode a compact-u16 from the start of `bytes`.
+///
+/// Returns `(len, bytes_consumed)`, or None if the input is truncated,
+/// overflows a u16, or is not the canonical (shortest) encoding — e.g.
+/// `[0x80, 0x00]` for 0 is rejected, matching the real decoder.
+pub fn decode_len(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut len = 0usize;
+
+    for (i, &byte) in bytes.iter().take(MAX_ENCODING_LENGTH).enumerate() {
+        // A zero continuation byte means a shorter encoding existed.
+        if i > 0 && byte == 0 {
+            return None;
+        }
+
+        len |= ((byte & 0x7f) as usize) << (i * 7);
+
+        if byte & 0x80 == 0 {
+            return (len <= u16::MAX as usize).then_some((len, i + 1));
+        }
+    }
+
+    // Ran out of input, or the third byte still had its continuation bit.
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(len: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        encode_len(len, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn boundary_lengths_encode_as_in_solana() {
+        let cases: [(usize, &[u8]); 6] = [
+            (0,     &[0x00]),
+            (127,   &[0x7f]),
+            (128,   &[0x80, 0x01]),
+            (16383, &[0xff, 0x7f]),
+            (16384, &[0x80, 0x80, 0x01]),
+            (65535, &[0xff, 0xff, 0x03]),
+        ];
+        for (len, bytes) in cases {
+            assert_eq!(encoded(len), bytes, "encoding {}", len);
+            assert_eq!(decode_len(bytes), Some((len, bytes.len())), "decoding {}", len);
+        }
+    }
+
+    #[test]
+    fn every_length_round_trips_and_ignores_what_follows() {
+        for len in 0..=u16::MAX as usize {
+            let mut bytes = encoded(len);
+            let size = bytes.len();
+            bytes.push(0xaa);
+            assert_eq!(decode_len(&bytes), Some((len, size)));
+        }
+    }
+
+    #[test]
+    fn truncated_overlong_and_non_canonical_input_is_rejected() {
+        let cases: [&[u8]; 7] = [
+            &[],
+            &[0x80],
+            &[0xff, 0xff],
+            &[0x80, 0x00],             // 0 in two bytes
+            &[0xff, 0x80, 0x00],       // 127 in three bytes
+            &[0xff, 0xff, 0x04],       // 65536, past u16
+            &[0x80, 0x80, 0x80, 0x01], // a fourth byte
+        ];
+        for bytes in cases {
+            assert_eq!(decode_len(bytes), None, "{:02x?}", bytes);
+        }
+    }
+}
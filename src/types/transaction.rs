@@ -11,7 +11,13 @@
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/transaction/src/lib.rs
 // ---------------------------------------------------------------------------
 
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+
+use crate::runtime::bank::{self, BankError};
 use crate::types::account::Pubkey;
+use crate::types::shortvec;
+use std::collections::HashMap;
 use std::fmt;
 
 // ---------------------------------------------------------------------------
@@ -23,7 +29,7 @@ use std::fmt;
 //
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/signature/src/lib.rs
 // ---------------------------------------------------------------------------
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 pub struct Signature(pub [u8; 64]);
 
 impl Default for Signature {
@@ -36,6 +42,13 @@ impl Signature {
     pub fn new(bytes: [u8; 64]) -> Self {
         Signature(bytes)
     }
+
+    /// Parse the base58 form clients use as a transaction id.
+    /// Returns None unless the string decodes to exactly 64 bytes.
+    pub fn from_base58(s: &str) -> Option<Self> {
+        let bytes = bs58::decode(s).into_vec().ok()?;
+        Some(Signature(bytes.try_into().ok()?))
+    }
 }
 
 impl fmt::Debug for Signature {
@@ -62,7 +75,7 @@ impl fmt::Debug for Signature {
 //
 // Reference: https://github.com/anza-xyz/solana-sdk/blob/master/hash/src/lib.rs
 // ---------------------------------------------------------------------------
-#[derive(Clone, Copy, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Default, Eq, Hash, PartialEq)]
 pub struct Hash(pub [u8; 32]);
 
 impl Hash {
@@ -157,6 +170,74 @@ impl CompiledInstruction {
             data,
         }
     }
+
+    /// Compile `ix` against a message's keys: its program and each of its
+    /// accounts become their index in `key_index`, which maps every
+    /// account_keys entry to its position. The inverse of the SVM looking
+    /// indices up in account_keys. Privileges are not carried over; they
+    /// come from where each key sits in account_keys.
+    pub fn compile(ix: &Instruction, key_index: &HashMap<Pubkey, u8>) -> Result<Self, CompileError> {
+        let index_of = |pubkey: &Pubkey| {
+            key_index.get(pubkey).copied().ok_or(CompileError::UnknownAccountKey { pubkey: *pubkey })
+        };
+        let accounts = ix.accounts.iter().map(|meta| index_of(&meta.pubkey)).collect::<Result<_, _>>()?;
+        Ok(CompiledInstruction::new(index_of(&ix.program_id)?, accounts, ix.data.clone()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Instruction — an instruction before it is compiled into a Message.
+//
+// Names its program and accounts by Pubkey, with the privileges each
+// account is passed with, rather than by index into account_keys. This is
+// the form a program builds to call another program (svm::invoke_signed).
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/instruction/src/lib.rs
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Instruction {
+    /// The program to invoke.
+    pub program_id: Pubkey,
+
+    /// The accounts passed to the program, in the order it expects them.
+    pub accounts: Vec<AccountMeta>,
+
+    /// Opaque byte payload passed to the program.
+    pub data: Vec<u8>,
+}
+
+impl Instruction {
+    pub fn new(program_id: Pubkey, accounts: Vec<AccountMeta>, data: Vec<u8>) -> Self {
+        Instruction {
+            program_id,
+            accounts,
+            data,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// AccountMeta — one account of an Instruction and how it is passed.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    /// The program may treat the account as having signed.
+    pub is_signer: bool,
+    /// The program may modify the account.
+    pub is_writable: bool,
+}
+
+impl AccountMeta {
+    /// A writable account.
+    pub fn new(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta { pubkey, is_signer, is_writable: true }
+    }
+
+    /// A read-only account.
+    pub fn new_readonly(pubkey: Pubkey, is_signer: bool) -> Self {
+        AccountMeta { pubkey, is_signer, is_writable: false }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -209,10 +290,84 @@ impl Message {
         }
     }
 
+    /// Serialize into the legacy message wire format. These are the bytes
+    /// every signature in the transaction covers.
+    ///
+    /// Every length prefix is a compact-u16 (see types/shortvec.rs):
+    ///
+    ///   [num_required_signatures:        u8]
+    ///   [num_readonly_signed_accounts:   u8]
+    ///   [num_readonly_unsigned_accounts: u8]
+    ///   [num_account_keys:               compact-u16]
+    ///   [account_key_0:                  32 bytes]
+    ///   ...
+    ///   [recent_blockhash:               32 bytes]
+    ///   [num_instructions:               compact-u16]
+    ///   for each instruction:
+    ///     [program_id_index:   u8]
+    ///     [num_accounts:       compact-u16]
+    ///     [accounts:           num_accounts bytes]
+    ///     [data_len:           compact-u16]
+    ///     [data:               data_len bytes]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.serialize_into(&mut buf);
+        buf
+    }
+
+    fn serialize_into(&self, buf: &mut Vec<u8>) {
+        write_message_body(&self.header, &self.account_keys, &self.recent_blockhash, &self.instructions, buf);
+    }
+
+    /// Parse a message from its wire format (the inverse of serialize).
+    /// The message must use up the input exactly.
+    pub fn deserialize(bytes: &[u8]) -> Result<Message, TxDeserializeError> {
+        let mut reader = Reader::new(bytes);
+        let message = Message::read(&mut reader)?;
+        reader.finish()?;
+        Ok(message)
+    }
+
+    fn read(reader: &mut Reader<'_>) -> Result<Message, TxDeserializeError> {
+        let header = MessageHeader {
+            num_required_signatures:        reader.read_u8("num_required_signatures")?,
+            num_readonly_signed_accounts:   reader.read_u8("num_readonly_signed_accounts")?,
+            num_readonly_unsigned_accounts: reader.read_u8("num_readonly_unsigned_accounts")?,
+        };
+
+        let num_keys = reader.read_len("num_account_keys")?;
+        let mut account_keys = Vec::with_capacity(reader.capacity(num_keys, 32));
+        for _ in 0..num_keys {
+            account_keys.push(Pubkey(reader.read_array("account_key")?));
+        }
+
+        let recent_blockhash = Hash(reader.read_array("recent_blockhash")?);
+
+        let num_instructions = reader.read_len("num_instructions")?;
+        let mut instructions = Vec::with_capacity(reader.capacity(num_instructions, 3));
+        for _ in 0..num_instructions {
+            let program_id_index = reader.read_u8("program_id_index")?;
+            let num_accounts     = reader.read_len("num_accounts")?;
+            let accounts         = reader.read_bytes(num_accounts, "accounts")?.to_vec();
+            let data_len         = reader.read_len("data_len")?;
+            let data             = reader.read_bytes(data_len, "data")?.to_vec();
+            instructions.push(CompiledInstruction::new(program_id_index, accounts, data));
+        }
+
+        Ok(Message::new(header, account_keys, recent_blockhash, instructions))
+    }
+
+    /// The account that pays the fee: account_keys[0].
+    /// None for a message with no account keys, which can't be processed.
+    pub fn fee_payer(&self) -> Option<&Pubkey> {
+        self.account_keys.first()
+    }
+
     /// Returns true if the account at `index` is a signer.
     /// Signers are the first `num_required_signatures` entries in account_keys.
+    /// False for an index past the end of account_keys.
     pub fn is_signer(&self, index: usize) -> bool {
-        index < self.header.num_required_signatures as usize
+        index < self.account_keys.len() && index < self.header.num_required_signatures as usize
     }
 
     /// Returns true if the account at `index` is writable.
@@ -220,18 +375,368 @@ impl Message {
     /// An account is writable if it is NOT in either readonly group:
     ///   - readonly signers:   last num_readonly_signed of the signers
     ///   - readonly non-signers: last num_readonly_unsigned of the non-signers
+    ///
+    /// False for an index past the end of account_keys. A header whose
+    /// counts exceed the keys (which sanitize rejects) makes
+    /// every account in the overflowing group readonly rather than panic.
     pub fn is_writable(&self, index: usize) -> bool {
         let num_signers = self.header.num_required_signatures as usize;
         let num_readonly_signed = self.header.num_readonly_signed_accounts as usize;
         let num_readonly_unsigned = self.header.num_readonly_unsigned_accounts as usize;
         let total = self.account_keys.len();
 
-        if index < num_signers {
+        if index >= total {
+            false
+        } else if index < num_signers {
             // Signer — writable unless it falls in the readonly-signed tail.
-            index < num_signers - num_readonly_signed
+            index < num_signers.saturating_sub(num_readonly_signed)
         } else {
             // Non-signer — writable unless it falls in the readonly-unsigned tail.
-            index < total - num_readonly_unsigned
+            index < total.saturating_sub(num_readonly_unsigned)
+        }
+    }
+
+    /// The distinct accounts the message writes and the distinct ones it
+    /// only reads, each in account_keys order: the locks executing it
+    /// needs (see AccountLocks). A key listed more than once is writable
+    /// if any of its positions is.
+    pub fn get_account_locks(&self) -> (Vec<Pubkey>, Vec<Pubkey>) {
+        let mut writable: Vec<Pubkey> = Vec::new();
+        for (i, key) in self.account_keys.iter().enumerate() {
+            if self.is_writable(i) && !writable.contains(key) {
+                writable.push(*key);
+            }
+        }
+        let mut readonly: Vec<Pubkey> = Vec::new();
+        for key in &self.account_keys {
+            if !writable.contains(key) && !readonly.contains(key) {
+                readonly.push(*key);
+            }
+        }
+        (writable, readonly)
+    }
+
+    /// Check that the header fits account_keys and every instruction index
+    /// points into it, so is_signer, is_writable and the SVM can index by
+    /// them without checking again.
+    ///
+    /// Header: no more signers than keys, fewer readonly signers than
+    /// signers (the fee payer, account_keys[0], must be a writable signer)
+    /// and no more readonly non-signers than non-signers. Instructions:
+    /// every program and account index below account_keys.len(), and no
+    /// program at index 0, since the fee payer cannot be a program.
+    pub fn sanitize(&self) -> Result<(), SanitizeError> {
+        let num_keys    = self.account_keys.len();
+        let num_signers = self.header.num_required_signatures as usize;
+        if num_signers > num_keys
+            || self.header.num_readonly_signed_accounts as usize >= num_signers
+            || self.header.num_readonly_unsigned_accounts as usize > num_keys - num_signers
+        {
+            return Err(SanitizeError::InvalidHeader);
+        }
+
+        for (instruction, ix) in self.instructions.iter().enumerate() {
+            let out_of_bounds = |index: u8| SanitizeError::IndexOutOfBounds { instruction, index };
+            if ix.program_id_index == 0 {
+                return Err(SanitizeError::FeePayerAsProgram { instruction });
+            }
+            if ix.program_id_index as usize >= num_keys {
+                return Err(out_of_bounds(ix.program_id_index));
+            }
+            if let Some(&index) = ix.accounts.iter().find(|&&index| index as usize >= num_keys) {
+                return Err(out_of_bounds(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile pubkey-addressed instructions into a message, with a zero
+    /// recent_blockhash for the caller to fill in. Fails if there are more
+    /// than 256 distinct keys, or a privilege group too big for its u8
+    /// header count.
+    pub fn compile(payer: Option<&Pubkey>, instructions: &[Instruction]) -> Result<Message, CompileError> {
+        // account_keys are laid out as Solana's compiler does:
+        //   1. `payer`, if given, first, as a writable signer.
+        //   2. Every other key in order of first appearance — each
+        //      instruction's accounts, then its program. A key listed more
+        //      than once gets the union of its privileges.
+        //   3. Grouped as
+        //        [writable signers | readonly signers | writable non-signers | readonly non-signers]
+        //      keeping that order within each group; the header counts them.
+        // Each instruction's program and accounts are then remapped to
+        // their indices. Solana also sorts each group by pubkey; keeping
+        // first appearance instead leaves the keys where a hand-built
+        // message would put them.
+
+        // --- Collect the distinct keys with their combined privileges ---
+        let mut keys: Vec<AccountMeta> = Vec::new();
+        let mut add = |pubkey: Pubkey, is_signer: bool, is_writable: bool| {
+            match keys.iter_mut().find(|k| k.pubkey == pubkey) {
+                Some(key) => {
+                    key.is_signer   |= is_signer;
+                    key.is_writable |= is_writable;
+                }
+                None => keys.push(AccountMeta { pubkey, is_signer, is_writable }),
+            }
+        };
+        if let Some(payer) = payer {
+            add(*payer, true, true);
+        }
+        for instruction in instructions {
+            for meta in &instruction.accounts {
+                add(meta.pubkey, meta.is_signer, meta.is_writable);
+            }
+            add(instruction.program_id, false, false);
+        }
+
+        // A u8 index addresses 256 keys, 0 through 255.
+        let too_many = CompileError::TooManyAccountKeys { count: keys.len() };
+        if keys.len() > u8::MAX as usize + 1 {
+            return Err(too_many);
+        }
+
+        // --- Group by privilege; sort_by_key is stable ---
+        keys.sort_by_key(|k| (!k.is_signer, !k.is_writable));
+        // With 256 keys a single group can still be one too many for its
+        // u8 count.
+        let count = |group: fn(&AccountMeta) -> bool| u8::try_from(keys.iter().filter(|k| group(k)).count());
+        let header = MessageHeader {
+            num_required_signatures:        count(|k| k.is_signer).map_err(|_| too_many)?,
+            num_readonly_signed_accounts:   count(|k| k.is_signer && !k.is_writable).map_err(|_| too_many)?,
+            num_readonly_unsigned_accounts: count(|k| !k.is_signer && !k.is_writable).map_err(|_| too_many)?,
+        };
+        let account_keys: Vec<Pubkey> = keys.into_iter().map(|k| k.pubkey).collect();
+
+        // --- Remap each instruction to the final positions ---
+        let key_index: HashMap<Pubkey, u8> = account_keys
+            .iter()
+            .enumerate()
+            .map(|(i, pubkey)| (*pubkey, i as u8))
+            .collect();
+        let compiled = instructions
+            .iter()
+            .map(|ix| CompiledInstruction::compile(ix, &key_index).expect("every instruction key was collected"))
+            .collect();
+
+        Ok(Message::new(header, account_keys, Hash::default(), compiled))
+    }
+}
+
+// The fields a legacy and a v0 message share, in wire order: everything
+// Message::serialize lays out.
+fn write_message_body(
+    header: &MessageHeader,
+    account_keys: &[Pubkey],
+    recent_blockhash: &Hash,
+    instructions: &[CompiledInstruction],
+    buf: &mut Vec<u8>,
+) {
+    // Header
+    buf.push(header.num_required_signatures);
+    buf.push(header.num_readonly_signed_accounts);
+    buf.push(header.num_readonly_unsigned_accounts);
+
+    // Account keys
+    shortvec::encode_len(account_keys.len(), buf);
+    for key in account_keys {
+        buf.extend_from_slice(&key.0);
+    }
+
+    // Recent blockhash
+    buf.extend_from_slice(&recent_blockhash.0);
+
+    // Instructions
+    shortvec::encode_len(instructions.len(), buf);
+    for ix in instructions {
+        buf.push(ix.program_id_index);
+        shortvec::encode_len(ix.accounts.len(), buf);
+        buf.extend_from_slice(&ix.accounts);
+        shortvec::encode_len(ix.data.len(), buf);
+        buf.extend_from_slice(&ix.data);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SanitizeError — why Message::sanitize rejected a message.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SanitizeError {
+    /// The header's counts do not fit account_keys, or leave the fee
+    /// payer readonly.
+    InvalidHeader,
+    /// Instruction `instruction` names its program or an account by an
+    /// index past the end of account_keys.
+    IndexOutOfBounds { instruction: usize, index: u8 },
+    /// Instruction `instruction` names the fee payer (index 0) as its
+    /// program.
+    FeePayerAsProgram { instruction: usize },
+}
+
+// ---------------------------------------------------------------------------
+// CompileError — why Message::compile could not build a message, or
+// CompiledInstruction::compile an instruction.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompileError {
+    /// The instructions reference more distinct keys than the message's
+    /// u8 header counts and account indices can describe.
+    TooManyAccountKeys { count: usize },
+    /// The instruction names a key the key map does not have.
+    UnknownAccountKey { pubkey: Pubkey },
+}
+
+// ---------------------------------------------------------------------------
+// MessageAddressTableLookup — "these entries from that lookup table".
+//
+// A v0 message lists, per lookup table account, which of its entries to
+// load as writable and which as readonly. Each index is one byte into the
+// table's address list.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/message/src/versions/v0/mod.rs
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MessageAddressTableLookup {
+    /// Address of the lookup table account.
+    pub account_key: Pubkey,
+
+    /// Indexes into the table of addresses to load as writable.
+    pub writable_indexes: Vec<u8>,
+
+    /// Indexes into the table of addresses to load as readonly.
+    pub readonly_indexes: Vec<u8>,
+}
+
+// ---------------------------------------------------------------------------
+// MessageV0 — a message that can reference lookup tables.
+//
+// Same fields as a legacy Message, but account_keys only holds the
+// "static" keys (signers, programs, anything not in a table). Instruction
+// indexes address the combined list:
+//
+//   [static account_keys | writable lookups (all tables) | readonly lookups (all tables)]
+//
+// Signers must always be static — a table cannot produce a signature.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/message/src/versions/v0/mod.rs
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MessageV0 {
+    /// Describes the signer/writable layout of the static account_keys.
+    pub header: MessageHeader,
+
+    /// Keys spelled out in full in the message.
+    pub account_keys: Vec<Pubkey>,
+
+    /// A recent blockhash, exactly as in a legacy Message.
+    pub recent_blockhash: Hash,
+
+    /// Instructions indexing into static keys followed by loaded keys.
+    pub instructions: Vec<CompiledInstruction>,
+
+    /// Which lookup table entries to load, in order.
+    pub address_table_lookups: Vec<MessageAddressTableLookup>,
+}
+
+impl MessageV0 {
+    /// Serialize into the v0 message wire format, the bytes a v0
+    /// transaction's signatures cover: the 0x80 version prefix, the same
+    /// fields as a legacy message (see Message::serialize), then
+    ///
+    ///   [num_lookups:        compact-u16]
+    ///   for each lookup:
+    ///     [account_key:      32 bytes]
+    ///     [num_writable:     compact-u16]
+    ///     [writable_indexes: num_writable bytes]
+    ///     [num_readonly:     compact-u16]
+    ///     [readonly_indexes: num_readonly bytes]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.serialize_into(&mut buf);
+        buf
+    }
+
+    fn serialize_into(&self, buf: &mut Vec<u8>) {
+        buf.push(MESSAGE_VERSION_PREFIX);
+        write_message_body(&self.header, &self.account_keys, &self.recent_blockhash, &self.instructions, buf);
+
+        shortvec::encode_len(self.address_table_lookups.len(), buf);
+        for lookup in &self.address_table_lookups {
+            buf.extend_from_slice(&lookup.account_key.0);
+            shortvec::encode_len(lookup.writable_indexes.len(), buf);
+            buf.extend_from_slice(&lookup.writable_indexes);
+            shortvec::encode_len(lookup.readonly_indexes.len(), buf);
+            buf.extend_from_slice(&lookup.readonly_indexes);
+        }
+    }
+
+    // Reads what follows the version prefix.
+    fn read(reader: &mut Reader<'_>) -> Result<MessageV0, TxDeserializeError> {
+        let Message { header, account_keys, recent_blockhash, instructions } = Message::read(reader)?;
+
+        let num_lookups = reader.read_len("num_address_table_lookups")?;
+        let mut address_table_lookups = Vec::with_capacity(reader.capacity(num_lookups, 34));
+        for _ in 0..num_lookups {
+            let account_key      = Pubkey(reader.read_array("lookup_table_key")?);
+            let num_writable     = reader.read_len("num_writable_indexes")?;
+            let writable_indexes = reader.read_bytes(num_writable, "writable_indexes")?.to_vec();
+            let num_readonly     = reader.read_len("num_readonly_indexes")?;
+            let readonly_indexes = reader.read_bytes(num_readonly, "readonly_indexes")?.to_vec();
+            address_table_lookups.push(MessageAddressTableLookup { account_key, writable_indexes, readonly_indexes });
+        }
+
+        Ok(MessageV0 { header, account_keys, recent_blockhash, instructions, address_table_lookups })
+    }
+}
+
+// ---------------------------------------------------------------------------
+// VersionedMessage — either message format.
+//
+// On the wire a v0 message is prefixed with 0x80 (high bit set = versioned,
+// low bits = version 0); a legacy message starts straight with its header.
+// The Bank and SVM only ever see the legacy form: v0 messages are resolved
+// against their lookup tables first (see bank::resolve_v0).
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/message/src/versions/mod.rs
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VersionedMessage {
+    Legacy(Message),
+    V0(MessageV0),
+}
+
+// The first byte of a v0 message. A legacy message starts with
+// num_required_signatures, which never has the high bit set.
+pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
+
+impl VersionedMessage {
+    /// The bytes the transaction's signatures cover, in the message's own
+    /// wire format.
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            VersionedMessage::Legacy(msg) => msg.serialize(),
+            VersionedMessage::V0(msg)     => msg.serialize(),
+        }
+    }
+
+    /// The keys spelled out in the message. Every signer is among them.
+    pub fn static_account_keys(&self) -> &[Pubkey] {
+        match self {
+            VersionedMessage::Legacy(msg) => &msg.account_keys,
+            VersionedMessage::V0(msg)     => &msg.account_keys,
+        }
+    }
+
+    pub fn header(&self) -> &MessageHeader {
+        match self {
+            VersionedMessage::Legacy(msg) => &msg.header,
+            VersionedMessage::V0(msg)     => &msg.header,
+        }
+    }
+
+    pub fn recent_blockhash(&self) -> &Hash {
+        match self {
+            VersionedMessage::Legacy(msg) => &msg.recent_blockhash,
+            VersionedMessage::V0(msg)     => &msg.recent_blockhash,
         }
     }
 }
@@ -285,4 +790,576 @@ impl Transaction {
     pub fn is_signed(&self) -> bool {
         self.signatures.len() == self.message.header.num_required_signatures as usize
     }
+
+    /// The transaction's signature — signatures[0], the fee payer's.
+    ///
+    /// This is the transaction's id: explorers and RPC lookups key on it.
+    /// An unsigned transaction has the all-zeros signature.
+    pub fn signature(&self) -> Signature {
+        self.signatures.first().copied().unwrap_or_default()
+    }
+
+    /// The transaction id rendered in base58, the way clients display it
+    /// (87–88 characters for a 64-byte signature).
+    pub fn id_base58(&self) -> String {
+        bs58::encode(self.signature().0).into_string()
+    }
+
+    /// SHA-256 of the serialized message — of exactly the bytes the
+    /// signatures cover. Two transactions with the same message hash
+    /// authorize the same thing, whatever their signatures.
+    pub fn message_hash(&self) -> Hash {
+        Hash(Sha256::digest(self.message.serialize()).into())
+    }
+
+    /// Check the signatures as bank::verify_signatures does and, if they
+    /// all verify, return the message hash (message_hash()) of the bytes
+    /// they were checked against, computed from those same bytes so the
+    /// two cannot disagree.
+    pub fn verify_and_hash(&self) -> Result<Hash, BankError> {
+        let header = &self.message.header;
+        bank::verify_signed_bytes(header, &self.message.account_keys, &self.message.serialize(), &self.signatures)
+    }
+
+    /// Sign with every required signer's key, in any order.
+    ///
+    /// Each key signs the serialized message and its signature goes in the
+    /// slot of the signer it belongs to, so `signatures` ends up in
+    /// account_keys order. Fails, signing nothing, unless `keypairs` covers
+    /// every required signer and holds no key that is not one.
+    pub fn sign(&mut self, keypairs: &[&SigningKey]) -> Result<(), SignerError> {
+        let signers = &self.message.account_keys[..self.signer_count()];
+        if let Some(missing) = signers
+            .iter()
+            .find(|pubkey| !keypairs.iter().any(|key| key.verifying_key().to_bytes() == pubkey.0))
+        {
+            return Err(SignerError::NotEnoughSigners { missing: *missing });
+        }
+        self.sign_partial(keypairs)
+    }
+
+    /// Sign with some of the required signers' keys, leaving the other
+    /// slots as they are — the all-zeros signature if never signed.
+    ///
+    /// This is how several parties sign one transaction offline: each
+    /// calls sign_partial on the same message with its own key, and the
+    /// transaction is complete once every slot is filled. Fails, signing
+    /// nothing, if a key is not one of the message's required signers.
+    pub fn sign_partial(&mut self, keypairs: &[&SigningKey]) -> Result<(), SignerError> {
+        let num_signers = self.signer_count();
+        let positions = keypairs
+            .iter()
+            .map(|key| {
+                let pubkey = Pubkey(key.verifying_key().to_bytes());
+                self.message.account_keys[..num_signers]
+                    .iter()
+                    .position(|signer| *signer == pubkey)
+                    .ok_or(SignerError::KeypairPubkeyMismatch { pubkey })
+            })
+            .collect::<Result<Vec<usize>, SignerError>>()?;
+
+        self.signatures.resize(num_signers, Signature::default());
+        let message_bytes = self.message.serialize();
+        for (key, index) in keypairs.iter().zip(positions) {
+            self.signatures[index] = Signature(key.sign(&message_bytes).to_bytes());
+        }
+        Ok(())
+    }
+
+    // The header's signer count, clamped to account_keys so a malformed
+    // header cannot index past it.
+    fn signer_count(&self) -> usize {
+        (self.message.header.num_required_signatures as usize).min(self.message.account_keys.len())
+    }
+
+    /// Serialize into the wire format:
+    ///   [num_signatures: compact-u16] [sig_0: 64 bytes] ... [message]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        shortvec::encode_len(self.signatures.len(), &mut buf);
+        for sig in &self.signatures {
+            buf.extend_from_slice(&sig.0);
+        }
+        self.message.serialize_into(&mut buf);
+        buf
+    }
+
+    /// Parse a transaction from its wire format (the inverse of serialize).
+    ///
+    /// Rejects a transaction that carries fewer signatures than its message
+    /// requires, and any bytes left over after the message — a padded
+    /// payload is not the transaction that was signed.
+    pub fn deserialize(bytes: &[u8]) -> Result<Transaction, TxDeserializeError> {
+        let mut reader = Reader::new(bytes);
+
+        let num_signatures = reader.read_len("num_signatures")?;
+        let mut signatures = Vec::with_capacity(reader.capacity(num_signatures, 64));
+        for _ in 0..num_signatures {
+            signatures.push(Signature(reader.read_array("signature")?));
+        }
+
+        let message = Message::read(&mut reader)?;
+        reader.finish()?;
+
+        let required = message.header.num_required_signatures as usize;
+        if num_signatures < required {
+            return Err(TxDeserializeError::NotEnoughSignatures {
+                declared: num_signatures,
+                required,
+            });
+        }
+
+        Ok(Transaction::new(message, signatures))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// VersionedTransaction — a transaction whose message may be v0.
+//
+// Same wire format as a Transaction, with either message format after the
+// signatures. The signatures cover the message's own bytes, so they are
+// checked on this form (bank::verify_versioned); bank::resolve_transaction
+// then turns it into the legacy Transaction the Bank executes.
+//
+// Reference: https://github.com/anza-xyz/solana-sdk/blob/master/transaction/src/versioned/mod.rs
+// ---------------------------------------------------------------------------
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VersionedTransaction {
+    pub signatures: Vec<Signature>,
+    pub message:    VersionedMessage,
+}
+
+impl VersionedTransaction {
+    /// The transaction as a legacy Transaction, or None if its message is
+    /// v0 and so needs bank::resolve_transaction.
+    pub fn into_legacy(self) -> Option<Transaction> {
+        match self.message {
+            VersionedMessage::Legacy(message) => Some(Transaction::new(message, self.signatures)),
+            VersionedMessage::V0(_)           => None,
+        }
+    }
+
+    /// The transaction id: signatures[0], the fee payer's, in base58.
+    pub fn id_base58(&self) -> String {
+        bs58::encode(self.signatures.first().copied().unwrap_or_default().0).into_string()
+    }
+
+    /// Serialize into the wire format:
+    ///   [num_signatures: compact-u16] [sig_0: 64 bytes] ... [message]
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        shortvec::encode_len(self.signatures.len(), &mut buf);
+        for sig in &self.signatures {
+            buf.extend_from_slice(&sig.0);
+        }
+        match &self.message {
+            VersionedMessage::Legacy(msg) => msg.serialize_into(&mut buf),
+            VersionedMessage::V0(msg)     => msg.serialize_into(&mut buf),
+        }
+        buf
+    }
+
+    /// Parse a transaction of either message format. A message whose
+    /// first byte has the high bit set is versioned; only version 0
+    /// exists. Rejects too few signatures and trailing bytes, as
+    /// Transaction::deserialize does.
+    pub fn deserialize(bytes: &[u8]) -> Result<VersionedTransaction, TxDeserializeError> {
+        let mut reader = Reader::new(bytes);
+
+        let num_signatures = reader.read_len("num_signatures")?;
+        let mut signatures = Vec::with_capacity(reader.capacity(num_signatures, 64));
+        for _ in 0..num_signatures {
+            signatures.push(Signature(reader.read_array("signature")?));
+        }
+
+        let message = match reader.peek_u8("message")? {
+            prefix if prefix & MESSAGE_VERSION_PREFIX == 0 => VersionedMessage::Legacy(Message::read(&mut reader)?),
+            MESSAGE_VERSION_PREFIX => {
+                reader.read_u8("message_version")?;
+                VersionedMessage::V0(MessageV0::read(&mut reader)?)
+            }
+            prefix => return Err(TxDeserializeError::UnsupportedVersion { version: prefix & !MESSAGE_VERSION_PREFIX }),
+        };
+        reader.finish()?;
+
+        let required = message.header().num_required_signatures as usize;
+        if num_signatures < required {
+            return Err(TxDeserializeError::NotEnoughSignatures {
+                declared: num_signatures,
+                required,
+            });
+        }
+
+        Ok(VersionedTransaction { signatures, message })
+    }
+}
+
+impl From<Transaction> for VersionedTransaction {
+    fn from(tx: Transaction) -> Self {
+        VersionedTransaction { signatures: tx.signatures, message: VersionedMessage::Legacy(tx.message) }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SignerError — why Transaction::sign or sign_partial signed nothing.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SignerError {
+    /// A key was given whose address is not one of the message's required
+    /// signers.
+    KeypairPubkeyMismatch { pubkey: Pubkey },
+    /// No key was given for required signer `missing`.
+    NotEnoughSigners { missing: Pubkey },
+}
+
+// ---------------------------------------------------------------------------
+// TxDeserializeError — why a byte string is not a valid transaction.
+//
+// Every variant names the field being read and the byte offset it started
+// at, so a bad payload can be diagnosed from the error alone.
+// ---------------------------------------------------------------------------
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TxDeserializeError {
+    /// The input ended before `field` could be read in full.
+    Truncated { field: &'static str, offset: usize },
+
+    /// The compact-u16 length prefix for `field` is malformed.
+    InvalidLength { field: &'static str, offset: usize },
+
+    /// Fewer signatures were declared than the message header requires.
+    NotEnoughSignatures { declared: usize, required: usize },
+
+    /// The message ended at `offset` but `remaining` more bytes followed.
+    TrailingBytes { offset: usize, remaining: usize },
+
+    /// The message is versioned, but not version 0.
+    UnsupportedVersion { version: u8 },
+}
+
+// ---------------------------------------------------------------------------
+// Reader — a cursor over wire bytes that never reads out of bounds.
+// ---------------------------------------------------------------------------
+struct Reader<'a> {
+    bytes:  &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, offset: 0 }
+    }
+
+    fn read_bytes(&mut self, len: usize, field: &'static str) -> Result<&'a [u8], TxDeserializeError> {
+        let slice = self
+            .offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.offset..end))
+            .ok_or(TxDeserializeError::Truncated { field, offset: self.offset })?;
+        self.offset += len;
+        Ok(slice)
+    }
+
+    /// Succeeds only if every input byte has been consumed.
+    fn finish(&self) -> Result<(), TxDeserializeError> {
+        match self.bytes.len() - self.offset {
+            0         => Ok(()),
+            remaining => Err(TxDeserializeError::TrailingBytes { offset: self.offset, remaining }),
+        }
+    }
+
+    /// How many of `len` declared items, each at least `item_size` bytes
+    /// on the wire, the rest of the input could hold. A declared length is
+    /// untrusted, so it never sizes an allocation on its own.
+    fn capacity(&self, len: usize, item_size: usize) -> usize {
+        len.min((self.bytes.len() - self.offset) / item_size)
+    }
+
+    fn read_u8(&mut self, field: &'static str) -> Result<u8, TxDeserializeError> {
+        Ok(self.read_bytes(1, field)?[0])
+    }
+
+    fn peek_u8(&self, field: &'static str) -> Result<u8, TxDeserializeError> {
+        self.bytes
+            .get(self.offset)
+            .copied()
+            .ok_or(TxDeserializeError::Truncated { field, offset: self.offset })
+    }
+
+    fn read_array<const N: usize>(&mut self, field: &'static str) -> Result<[u8; N], TxDeserializeError> {
+        Ok(self.read_bytes(N, field)?.try_into().unwrap())
+    }
+
+    fn read_len(&mut self, field: &'static str) -> Result<usize, TxDeserializeError> {
+        let rest = &self.bytes[self.offset..];
+        match shortvec::decode_len(rest) {
+            Some((len, consumed)) => {
+                self.offset += consumed;
+                Ok(len)
+            }
+            // Distinguish running out of input from a bad encoding.
+            None if rest.len() < shortvec::MAX_ENCODING_LENGTH
+                && rest.iter().all(|b| b & 0x80 != 0) =>
+            {
+                Err(TxDeserializeError::Truncated { field, offset: self.offset })
+            }
+            None => Err(TxDeserializeError::InvalidLength { field, offset: self.offset }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::programs::memo::MEMO_PROGRAM_ID;
+    use crate::programs::system;
+    use crate::types::keys::genesis_keypair;
+
+    // A message over four keys with `header`, whose one instruction runs
+    // `program_id_index` on `accounts`.
+    fn message(header: (u8, u8, u8), program_id_index: u8, accounts: Vec<u8>) -> Message {
+        let header = MessageHeader {
+            num_required_signatures:        header.0,
+            num_readonly_signed_accounts:   header.1,
+            num_readonly_unsigned_accounts: header.2,
+        };
+        let keys = (1..=4).map(|i| Pubkey([i; 32])).collect();
+        Message::new(header, keys, Hash::default(), vec![CompiledInstruction::new(program_id_index, accounts, vec![])])
+    }
+
+    #[test]
+    fn well_formed_message_sanitizes() {
+        assert_eq!(message((2, 1, 1), 3, vec![0, 1, 2]).sanitize(), Ok(()));
+    }
+
+    #[test]
+    fn more_signers_than_keys_is_an_invalid_header() {
+        assert_eq!(message((5, 0, 0), 3, vec![]).sanitize(), Err(SanitizeError::InvalidHeader));
+    }
+
+    #[test]
+    fn readonly_fee_payer_is_an_invalid_header() {
+        assert_eq!(message((1, 1, 0), 3, vec![]).sanitize(), Err(SanitizeError::InvalidHeader));
+    }
+
+    #[test]
+    fn no_signers_is_an_invalid_header() {
+        assert_eq!(message((0, 0, 1), 3, vec![]).sanitize(), Err(SanitizeError::InvalidHeader));
+    }
+
+    #[test]
+    fn more_readonly_non_signers_than_non_signers_is_an_invalid_header() {
+        assert_eq!(message((2, 0, 3), 3, vec![]).sanitize(), Err(SanitizeError::InvalidHeader));
+    }
+
+    #[test]
+    fn program_index_past_the_keys_is_out_of_bounds() {
+        assert_eq!(
+            message((1, 0, 1), 4, vec![]).sanitize(),
+            Err(SanitizeError::IndexOutOfBounds { instruction: 0, index: 4 })
+        );
+    }
+
+    #[test]
+    fn account_index_past_the_keys_is_out_of_bounds() {
+        assert_eq!(
+            message((1, 0, 1), 3, vec![0, 9]).sanitize(),
+            Err(SanitizeError::IndexOutOfBounds { instruction: 0, index: 9 })
+        );
+    }
+
+    #[test]
+    fn fee_payer_as_program_is_rejected() {
+        assert_eq!(message((1, 0, 1), 0, vec![1]).sanitize(), Err(SanitizeError::FeePayerAsProgram { instruction: 0 }));
+    }
+
+    // Three instructions signed by a fee payer and the new account it
+    // creates, alongside a writable non-signer and two readonly programs.
+    fn signed_multi_instruction_tx() -> Transaction {
+        let ((payer, payer_key), (new, new_key)) = (genesis_keypair(1), genesis_keypair(2));
+        let to      = Pubkey([3; 32]);
+        let owner   = Pubkey([4; 32]);
+        let mut message = Message::compile(Some(&payer), &[
+            system::create_account(&payer, &new, 1_000_000, 16, &owner),
+            system::transfer(&new, &to, 10),
+            Instruction::new(MEMO_PROGRAM_ID, vec![AccountMeta::new_readonly(payer, true)], b"round trip".to_vec()),
+        ])
+        .unwrap();
+        message.recent_blockhash = Hash([7; 32]);
+        let mut tx = Transaction::new(message, Vec::new());
+        tx.sign(&[&payer_key, &new_key]).unwrap();
+        tx
+    }
+
+    #[test]
+    fn multi_instruction_multi_signer_transaction_round_trips() {
+        let tx    = signed_multi_instruction_tx();
+        let bytes = tx.serialize();
+        assert_eq!(tx.signatures.len(), 2);
+        assert_eq!(tx.message.instructions.len(), 3);
+
+        let parsed = Transaction::deserialize(&bytes).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(parsed.serialize(), bytes);
+        assert_eq!(parsed.message_hash(), tx.message_hash());
+        assert_eq!(VersionedTransaction::deserialize(&bytes), Ok(VersionedTransaction::from(tx)));
+    }
+
+    #[test]
+    fn transaction_missing_a_required_signature_is_rejected() {
+        let mut tx = signed_multi_instruction_tx();
+        tx.signatures.pop();
+        let bytes = tx.serialize();
+
+        let error = TxDeserializeError::NotEnoughSignatures { declared: 1, required: 2 };
+        assert_eq!(Transaction::deserialize(&bytes), Err(error));
+        assert_eq!(VersionedTransaction::deserialize(&bytes), Err(error));
+    }
+
+    #[test]
+    fn padded_transaction_is_rejected() {
+        let mut bytes = signed_multi_instruction_tx().serialize();
+        let offset    = bytes.len();
+        bytes.extend_from_slice(&[0, 0]);
+
+        let error = TxDeserializeError::TrailingBytes { offset, remaining: 2 };
+        assert_eq!(Transaction::deserialize(&bytes), Err(error));
+        assert_eq!(VersionedTransaction::deserialize(&bytes), Err(error));
+    }
+
+    #[test]
+    fn huge_signature_count_fails_on_the_missing_bytes() {
+        // 65,535 signatures declared in three bytes, and none present: the
+        // reader must run out of input, not reserve 4 MiB up front.
+        let bytes = [0xff, 0xff, 0x03];
+
+        let error = TxDeserializeError::Truncated { field: "signature", offset: 3 };
+        assert_eq!(Transaction::deserialize(&bytes), Err(error));
+        assert_eq!(VersionedTransaction::deserialize(&bytes), Err(error));
+        assert_eq!(Reader::new(&bytes).capacity(65_535, 64), 0);
+    }
+
+    #[test]
+    fn compile_groups_keys_by_privilege_and_counts_the_groups() {
+        let key = |i: u8| Pubkey([i; 32]);
+        let first = Instruction::new(
+            key(9),
+            vec![
+                AccountMeta::new_readonly(key(1), true),
+                AccountMeta::new(key(2), false),
+                AccountMeta::new_readonly(key(3), false),
+                AccountMeta::new(key(4), true),
+            ],
+            vec![],
+        );
+        let message = Message::compile(Some(&key(0)), &[first]).unwrap();
+        assert_eq!(message.account_keys, [0, 4, 1, 2, 3, 9].map(key).to_vec());
+        assert_eq!(
+            (message.header.num_required_signatures, message.header.num_readonly_signed_accounts, message.header.num_readonly_unsigned_accounts),
+            (3, 1, 2)
+        );
+        assert_eq!(message.instructions[0], CompiledInstruction::new(5, vec![2, 3, 4, 1], vec![]));
+
+        // Listed readonly and writable, or signer and non-signer: one key
+        // with the union of both.
+        let readonly = Instruction::new(key(9), vec![AccountMeta::new_readonly(key(1), true)], vec![]);
+        let writable = Instruction::new(key(9), vec![AccountMeta::new(key(1), false)], vec![]);
+        let message = Message::compile(None, &[readonly, writable]).unwrap();
+        assert_eq!(message.account_keys, vec![key(1), key(9)]);
+        assert_eq!(
+            (message.header.num_required_signatures, message.header.num_readonly_signed_accounts, message.header.num_readonly_unsigned_accounts),
+            (1, 0, 1)
+        );
+    }
+
+    #[test]
+    fn compile_takes_up_to_256_keys() {
+        // `count` distinct accounts plus the program.
+        let accounts = |count: u16| {
+            let metas = (1..=count).map(|i| {
+                let mut pubkey = [0; 32];
+                pubkey[..2].copy_from_slice(&i.to_le_bytes());
+                AccountMeta::new(Pubkey(pubkey), false)
+            });
+            [Instruction::new(Pubkey([0xff; 32]), metas.collect(), vec![])]
+        };
+
+        let message = Message::compile(None, &accounts(255)).unwrap();
+        assert_eq!(message.account_keys.len(), 256);
+        assert_eq!(message.instructions[0].program_id_index, 255);
+        assert_eq!(message.header.num_readonly_unsigned_accounts, 1);
+
+        assert_eq!(Message::compile(None, &accounts(256)), Err(CompileError::TooManyAccountKeys { count: 257 }));
+        // 256 keys, but 256 readonly non-signers do not fit the header.
+        let mut readonly = accounts(255);
+        readonly[0].accounts.iter_mut().for_each(|meta| meta.is_writable = false);
+        assert_eq!(Message::compile(None, &readonly), Err(CompileError::TooManyAccountKeys { count: 256 }));
+    }
+
+    #[test]
+    fn verify_and_hash_returns_the_message_hash_only_when_every_signature_verifies() {
+        let tx = signed_multi_instruction_tx();
+        assert_eq!(tx.verify_and_hash().unwrap(), tx.message_hash());
+
+        let mut forged = tx.clone();
+        forged.signatures[1].0[0] ^= 1;
+        assert!(matches!(forged.verify_and_hash(), Err(BankError::SignatureVerificationFailed { index: 1 })));
+
+        // Changing what was signed invalidates the fee payer's signature too.
+        let mut altered = tx.clone();
+        altered.message.recent_blockhash = Hash([8; 32]);
+        assert_ne!(altered.message_hash(), tx.message_hash());
+        assert!(matches!(altered.verify_and_hash(), Err(BankError::SignatureVerificationFailed { index: 0 })));
+
+        let mut extra = tx;
+        extra.signatures.push(Signature::default());
+        assert!(matches!(extra.verify_and_hash(), Err(BankError::TooManySignatures { expected: 2, got: 3 })));
+    }
+
+    #[test]
+    fn transfer_compiles_to_the_indices_of_its_keys() {
+        let (from, to) = (Pubkey([1; 32]), Pubkey([2; 32]));
+        let transfer   = system::transfer(&from, &to, 10);
+        let key_index: HashMap<Pubkey, u8> =
+            [(to, 0), (system::SYSTEM_PROGRAM_ID, 1), (from, 2)].into_iter().collect();
+
+        let compiled = CompiledInstruction::compile(&transfer, &key_index).unwrap();
+        assert_eq!(compiled, CompiledInstruction::new(1, vec![2, 0], transfer.data.clone()));
+
+        let partial: HashMap<Pubkey, u8> = [(from, 0), (to, 1)].into_iter().collect();
+        assert_eq!(
+            CompiledInstruction::compile(&transfer, &partial),
+            Err(CompileError::UnknownAccountKey { pubkey: system::SYSTEM_PROGRAM_ID })
+        );
+    }
+
+    #[test]
+    fn signing_in_any_order_or_in_parts_gives_the_same_signatures() {
+        let signed = signed_multi_instruction_tx();
+        let ((_, payer_key), (new, new_key)) = (genesis_keypair(1), genesis_keypair(2));
+        let unsigned = || Transaction::new(signed.message.clone(), Vec::new());
+
+        let mut reversed = unsigned();
+        reversed.sign(&[&new_key, &payer_key]).unwrap();
+        assert_eq!(reversed.signatures, signed.signatures);
+        assert!(reversed.verify_and_hash().is_ok());
+
+        // sign_partial fills its signers' slots and leaves the rest zero.
+        let mut parts = unsigned();
+        parts.sign_partial(&[&new_key]).unwrap();
+        assert_eq!(parts.signatures, vec![Signature::default(), signed.signatures[1]]);
+        parts.sign_partial(&[&payer_key]).unwrap();
+        assert_eq!(parts.signatures, signed.signatures);
+
+        // A missing signer or a key that is not a signer signs nothing.
+        let mut missing = unsigned();
+        assert_eq!(missing.sign(&[&payer_key]), Err(SignerError::NotEnoughSigners { missing: new }));
+        assert!(missing.signatures.is_empty());
+        let (stranger, stranger_key) = genesis_keypair(3);
+        let mut extra = unsigned();
+        assert_eq!(
+            extra.sign(&[&payer_key, &new_key, &stranger_key]),
+            Err(SignerError::KeypairPubkeyMismatch { pubkey: stranger })
+        );
+        assert!(extra.signatures.is_empty());
+    }
 }